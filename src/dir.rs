@@ -0,0 +1,282 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use memchr::{memchr};
+use crate::{
+    result::{Result},
+    lmem,
+    util::{
+        retry::{retry},
+        kstr::{ToKStr},
+        data::{d8, DataSlice},
+    },
+    fd::{FdContainer},
+    file::{self, File, FileType, OpenOptions, Metadata},
+    string::{CStr},
+};
+use crate::kty::{
+    c_int, linux_dirent64, AT_FDCWD, AT_EMPTY_PATH, AT_SYMLINK_NOFOLLOW, O_RDONLY, O_DIRECTORY,
+    SEEK_SET,
+};
+use crate::syscall::{openat, close, getdents, lseek};
+
+/// Offset of the `d_name` field in `linux_dirent64`.
+const NAME_OFFSET: usize = 19;
+
+/// An opened directory.
+pub struct Dir {
+    fd: c_int,
+    owned: bool,
+}
+
+/// An entry in a directory.
+#[derive(Copy, Clone, Debug)]
+pub struct DirEntry<'a> {
+    /// The inode number of the entry.
+    pub ino: u64,
+    /// The position of the next entry in the directory.
+    ///
+    /// = Remarks
+    ///
+    /// This is an opaque value.
+    pub off: i64,
+    /// The type of the entry.
+    pub file_type: FileType,
+    /// The name of the entry.
+    pub name: &'a CStr,
+}
+
+impl Dir {
+    /// Opens a directory.
+    ///
+    /// [argument, path]
+    /// The path of the directory.
+    pub fn open<P: ToKStr>(path: P) -> Result<Dir> {
+        Dir::open_at(AT_FDCWD, path)
+    }
+
+    /// Opens a directory relative to another directory.
+    ///
+    /// [argument, dir]
+    /// The directory relative to which relative paths are interpreted.
+    ///
+    /// [argument, path]
+    /// The path of the directory.
+    ///
+    /// = See also
+    ///
+    /// * link:man:openat(2) and O_DIRECTORY therein
+    pub fn open_at<P: ToKStr>(dir: c_int, path: P) -> Result<Dir> {
+        let fd = path.with_kstr(|path| {
+            retry(|| openat(dir, path, O_RDONLY | O_DIRECTORY, 0))
+        })?;
+        Ok(Dir::from_owned(fd))
+    }
+
+    /// Opens a subdirectory of this directory.
+    ///
+    /// [argument, path]
+    /// The path of the subdirectory relative to this directory.
+    pub fn open_dir<P: ToKStr>(&self, path: P) -> Result<Dir> {
+        Dir::open_at(self.fd, path)
+    }
+
+    /// Opens a file relative to this directory.
+    ///
+    /// [argument, path]
+    /// The path of the file relative to this directory.
+    ///
+    /// [argument, opts]
+    /// The options used to open the file.
+    pub fn open_file<P: ToKStr>(&self, path: P, opts: &OpenOptions) -> Result<File> {
+        opts.open_at(self.fd, path)
+    }
+
+    /// Retrieves information about the directory.
+    pub fn metadata(&self) -> Result<Metadata> {
+        file::metadata_at(self.fd, kstr!(""), AT_EMPTY_PATH)
+    }
+
+    /// Retrieves information about a file relative to this directory.
+    ///
+    /// [argument, path]
+    /// The path of the file relative to this directory.
+    ///
+    /// = Remarks
+    ///
+    /// Symbolic links are not followed. This is usually what one wants when looking at
+    /// the entries returned by `read`.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fstatat(2)
+    pub fn entry_metadata<P: ToKStr>(&self, path: P) -> Result<Metadata> {
+        file::metadata_at(self.fd, path, AT_SYMLINK_NOFOLLOW)
+    }
+
+    /// Reads the next batch of entries from the directory.
+    ///
+    /// [argument, buf]
+    /// The buffer in which the entries will be stored.
+    ///
+    /// [return_value]
+    /// Returns an iterator over the entries in the buffer.
+    ///
+    /// = Remarks
+    ///
+    /// The buffer should be at least a few hundred bytes long. If it is too small to
+    /// hold a single entry, an error is returned. An empty iterator signals that the end
+    /// of the directory has been reached. The entries `.` and `..` are not filtered.
+    ///
+    /// = See also
+    ///
+    /// * link:man:getdents(2)
+    /// * link:lrs::dir::Dir::iter
+    pub fn read<'a>(&self, buf: &'a mut [d8]) -> Result<Entries<'a>> {
+        let buf = buf.align_for_mut::<linux_dirent64>();
+        let len = retry(|| getdents(self.fd, buf))? as usize;
+        let buf = unsafe { buf[..len].as_bytes() };
+        Ok(Entries { buf })
+    }
+
+    /// Creates an iterator over the remaining entries in the directory.
+    ///
+    /// [argument, buf]
+    /// The buffer that will be reused to read batches of entries.
+    ///
+    /// = Remarks
+    ///
+    /// The returned object does not implement `Iterator` because each entry borrows from
+    /// the buffer, which is overwritten when the next batch is read. Use
+    /// `Iter::next_entry` in a `while let` loop instead.
+    ///
+    /// = See also
+    ///
+    /// * link:lrs::dir::Dir::read
+    pub fn iter<'a>(&'a self, buf: &'a mut [d8]) -> Iter<'a> {
+        Iter {
+            dir: self,
+            buf: buf.align_for_mut::<linux_dirent64>(),
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    /// Resets the position of the directory to the first entry.
+    pub fn rewind(&self) -> Result {
+        rv!(lseek(self.fd, 0, SEEK_SET))
+    }
+}
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        if self.owned {
+            close(self.fd);
+        }
+    }
+}
+
+impl From<Dir> for c_int {
+    fn from(dir: Dir) -> Self {
+        let fd = dir.fd;
+        mem::forget(dir);
+        fd
+    }
+}
+
+impl FdContainer for Dir {
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    fn borrow(&self) -> c_int {
+        self.fd
+    }
+
+    fn from_owned(fd: c_int) -> Dir {
+        Dir { fd, owned: true }
+    }
+
+    fn from_borrowed(fd: c_int) -> Dir {
+        Dir { fd, owned: false }
+    }
+}
+
+/// An iterator over a batch of directory entries.
+pub struct Entries<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Entries<'a> {
+    /// Returns whether there are no more entries in the batch.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = DirEntry<'a>;
+
+    fn next(&mut self) -> Option<DirEntry<'a>> {
+        let buf = self.buf;
+        let ent: &linux_dirent64 = lmem::from_bytes(buf)?;
+        let reclen = ent.d_reclen as usize;
+        if reclen < NAME_OFFSET || reclen > buf.len() {
+            self.buf = &[];
+            return None;
+        }
+        let name = &buf[NAME_OFFSET..reclen];
+        let name_len = memchr(0, name).unwrap_or(name.len());
+        let name: &CStr = unsafe { mem::transmute(&name[..name_len]) };
+        self.buf = &buf[reclen..];
+        Some(DirEntry {
+            ino: ent.d_ino,
+            off: ent.d_off,
+            file_type: FileType::from_dirent_type(ent.d_type),
+            name,
+        })
+    }
+}
+
+/// An iterator over all entries of a directory.
+///
+/// = See also
+///
+/// * link:lrs::dir::Dir::iter
+pub struct Iter<'a> {
+    dir: &'a Dir,
+    buf: &'a mut [d8],
+    pos: usize,
+    len: usize,
+}
+
+impl<'a> Iter<'a> {
+    /// Returns the next entry in the directory.
+    ///
+    /// = Remarks
+    ///
+    /// `None` is returned once the end of the directory has been reached.
+    pub fn next_entry(&mut self) -> Option<Result<DirEntry<'_>>> {
+        if self.pos >= self.len {
+            let fd = self.dir.fd;
+            let buf = &mut *self.buf;
+            match retry(|| getdents(fd, buf)) {
+                Err(e) => return Some(Err(e)),
+                Ok(0) => return None,
+                Ok(n) => {
+                    self.pos = 0;
+                    self.len = n as usize;
+                },
+            }
+        }
+        let mut entries = Entries { buf: unsafe { self.buf[self.pos..self.len].as_bytes() } };
+        let entry = entries.next();
+        self.pos = match entry {
+            Some(_) => self.len - entries.buf.len(),
+            _ => self.len,
+        };
+        entry.map(Ok)
+    }
+}
@@ -0,0 +1,544 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem, cmp::{Reverse}};
+use alloc::{boxed::{Box}, vec::{Vec}, collections::{BinaryHeap}};
+use crate::{
+    result::{Result},
+    time::{Time, clock::{MONO}, timer::{Timer}},
+    poll::{Epoll, Events, EpollFlags, EPOLL_IN},
+    fd::{FdContainer, FdIo},
+    signal::{self, Signal, SigSet, SignalFd, SignalInfo},
+    util::{
+        data::{d8},
+        error::{self},
+    },
+};
+use crate::kty::{c_int, epoll_event, _NSIG};
+
+type IoCb<'a> = Box<dyn FnMut(&mut EventLoop<'a>, EpollFlags) + 'a>;
+type TimerCb<'a> = Box<dyn FnMut(&mut EventLoop<'a>) + 'a>;
+type SignalCb<'a> = Box<dyn FnMut(&mut EventLoop<'a>, &SignalInfo) + 'a>;
+type DeferredCb<'a> = Box<dyn FnOnce(&mut EventLoop<'a>) + 'a>;
+
+/// Epoll token of the timerfd.
+const TIMER_TOKEN: u64 = !0;
+/// Epoll token of the signalfd.
+const SIGNAL_TOKEN: u64 = !0 - 1;
+/// Maximum number of epoll events handled per iteration.
+const MAX_EVENTS: usize = 64;
+
+/// The id of a file descriptor registered with an event loop.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IoId(u64);
+
+/// The id of a timer registered with an event loop.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimerId(u64);
+
+/// A vector of entries addressed by index and generation.
+///
+/// = Remarks
+///
+/// Ids contain the generation of the slot so that stale ids of removed entries do not
+/// refer to entries that later reuse the slot.
+struct Slab<T> {
+    slots: Vec<(u32, Option<T>)>,
+    free: Vec<u32>,
+}
+
+impl<T> Slab<T> {
+    fn new() -> Slab<T> {
+        Slab { slots: Vec::new(), free: Vec::new() }
+    }
+
+    fn insert(&mut self, val: T) -> u64 {
+        let idx = match self.free.pop() {
+            Some(idx) => idx,
+            _ => {
+                self.slots.push((0, None));
+                (self.slots.len() - 1) as u32
+            },
+        };
+        let slot = &mut self.slots[idx as usize];
+        slot.1 = Some(val);
+        (slot.0 as u64) << 32 | idx as u64
+    }
+
+    fn get_mut(&mut self, id: u64) -> Option<&mut T> {
+        match self.slots.get_mut(id as u32 as usize) {
+            Some(&mut (gen, Some(ref mut val))) if gen == (id >> 32) as u32 => Some(val),
+            _ => None,
+        }
+    }
+
+    fn remove(&mut self, id: u64) -> Option<T> {
+        let idx = id as u32;
+        match self.slots.get_mut(idx as usize) {
+            Some(slot) if slot.0 == (id >> 32) as u32 && slot.1.is_some() => {
+                slot.0 = slot.0.wrapping_add(1);
+                self.free.push(idx);
+                slot.1.take()
+            },
+            _ => None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.free.len() == self.slots.len()
+    }
+}
+
+struct IoEntry<'a> {
+    fd: c_int,
+    cb: Option<IoCb<'a>>,
+}
+
+struct TimerEntry<'a> {
+    deadline: Time,
+    interval: Option<Time>,
+    cb: Option<TimerCb<'a>>,
+}
+
+/// A single-threaded event loop.
+///
+/// = Remarks
+///
+/// The loop multiplexes file descriptor readiness, timers, and signals over a single
+/// epoll instance. All timers share one timerfd on the monotonic clock that is armed
+/// for the earliest deadline. Signals are delivered through a signalfd.
+///
+/// Callbacks receive a mutable reference to the loop and can register or remove other
+/// callbacks, including themselves.
+///
+/// = Examples
+///
+/// ----
+/// let mut el = EventLoop::new()?;
+/// el.add_timeout(Time::seconds(1), |el| el.stop());
+/// el.run()?;
+/// ----
+pub struct EventLoop<'a> {
+    epoll: Epoll,
+    timer: Timer,
+    armed: Option<Time>,
+    io: Slab<IoEntry<'a>>,
+    timers: Slab<TimerEntry<'a>>,
+    deadlines: BinaryHeap<Reverse<(Time, u64)>>,
+    signal_fd: Option<SignalFd>,
+    signal_mask: SigSet,
+    blocked: SigSet,
+    signals: Vec<Option<SignalCb<'a>>>,
+    num_signals: usize,
+    deferred: Vec<DeferredCb<'a>>,
+    stopped: bool,
+}
+
+impl<'a> EventLoop<'a> {
+    /// Creates a new event loop.
+    pub fn new() -> Result<EventLoop<'a>> {
+        let epoll = Epoll::new()?;
+        let timer = MONO.timer_non_blocking()?;
+        epoll.add(&timer, TIMER_TOKEN, EPOLL_IN)?;
+        let mut signals = Vec::with_capacity(_NSIG);
+        signals.resize_with(_NSIG, || None);
+        Ok(EventLoop {
+            epoll,
+            timer,
+            armed: None,
+            io: Slab::new(),
+            timers: Slab::new(),
+            deadlines: BinaryHeap::new(),
+            signal_fd: None,
+            signal_mask: SigSet::empty(),
+            blocked: SigSet::empty(),
+            signals,
+            num_signals: 0,
+            deferred: Vec::new(),
+            stopped: false,
+        })
+    }
+
+    /// Registers a callback for the readiness of a file descriptor.
+    ///
+    /// [argument, fd]
+    /// The file descriptor.
+    ///
+    /// [argument, flags]
+    /// The events to wait for.
+    ///
+    /// [argument, cb]
+    /// The callback that is invoked with the events that occurred.
+    ///
+    /// [return_value]
+    /// Returns an id that can be used to modify or remove the registration.
+    ///
+    /// = Remarks
+    ///
+    /// The file descriptor must stay open until the registration is removed.
+    pub fn add_io<F, C>(&mut self, fd: &F, flags: EpollFlags, cb: C) -> Result<IoId>
+        where F: FdContainer,
+              C: FnMut(&mut EventLoop<'a>, EpollFlags) + 'a,
+    {
+        let fd = fd.borrow();
+        let id = self.io.insert(IoEntry { fd, cb: Some(Box::new(cb)) });
+        if let Err(e) = self.epoll.add(&FdIo(fd), id, flags) {
+            self.io.remove(id);
+            return Err(e);
+        }
+        Ok(IoId(id))
+    }
+
+    /// Changes the events a file descriptor registration waits for.
+    ///
+    /// [argument, id]
+    /// The id of the registration.
+    ///
+    /// [argument, flags]
+    /// The new events to wait for.
+    pub fn modify_io(&mut self, id: IoId, flags: EpollFlags) -> Result {
+        match self.io.get_mut(id.0) {
+            Some(entry) => self.epoll.modify(&FdIo(entry.fd), id.0, flags),
+            _ => Err(error::InvalidArgument),
+        }
+    }
+
+    /// Removes a file descriptor registration.
+    ///
+    /// [argument, id]
+    /// The id of the registration.
+    pub fn remove_io(&mut self, id: IoId) -> Result {
+        match self.io.remove(id.0) {
+            Some(entry) => self.epoll.delete(&FdIo(entry.fd)),
+            _ => Err(error::InvalidArgument),
+        }
+    }
+
+    /// Registers a callback that is invoked once after a timeout.
+    ///
+    /// [argument, after]
+    /// The time after which the callback is invoked.
+    ///
+    /// [argument, cb]
+    /// The callback.
+    pub fn add_timeout<C>(&mut self, after: Time, cb: C) -> Result<TimerId>
+        where C: FnOnce(&mut EventLoop<'a>) + 'a,
+    {
+        let mut cb = Some(cb);
+        let cb = move |el: &mut EventLoop<'a>| {
+            if let Some(cb) = cb.take() {
+                cb(el);
+            }
+        };
+        self.add_timer(after, None, Box::new(cb))
+    }
+
+    /// Registers a callback that is invoked periodically.
+    ///
+    /// [argument, every]
+    /// The period of the timer.
+    ///
+    /// [argument, cb]
+    /// The callback.
+    ///
+    /// = Remarks
+    ///
+    /// If the loop falls behind, missed invocations are skipped rather than run in a
+    /// burst.
+    pub fn add_interval<C>(&mut self, every: Time, cb: C) -> Result<TimerId>
+        where C: FnMut(&mut EventLoop<'a>) + 'a,
+    {
+        if every <= Time::nanoseconds(0) {
+            return Err(error::InvalidArgument);
+        }
+        self.add_timer(every, Some(every), Box::new(cb))
+    }
+
+    fn add_timer(&mut self, after: Time, interval: Option<Time>,
+                 cb: TimerCb<'a>) -> Result<TimerId> {
+        let deadline = (MONO.get_time()? + after).normalize();
+        let id = self.timers.insert(TimerEntry { deadline, interval, cb: Some(cb) });
+        self.deadlines.push(Reverse((deadline, id)));
+        Ok(TimerId(id))
+    }
+
+    /// Cancels a timer.
+    ///
+    /// [argument, id]
+    /// The id of the timer.
+    pub fn cancel_timer(&mut self, id: TimerId) -> Result {
+        match self.timers.remove(id.0) {
+            Some(_) => Ok(()),
+            _ => Err(error::InvalidArgument),
+        }
+    }
+
+    /// Registers a handler for a signal.
+    ///
+    /// [argument, sig]
+    /// The signal.
+    ///
+    /// [argument, cb]
+    /// The callback that is invoked with the information read from the signalfd.
+    ///
+    /// = Remarks
+    ///
+    /// The signal is blocked in the calling thread so that it is delivered through the
+    /// signalfd. An existing handler for the same signal is replaced.
+    ///
+    /// = See also
+    ///
+    /// * link:man:signalfd(2)
+    pub fn add_signal<C>(&mut self, sig: Signal, cb: C) -> Result
+        where C: FnMut(&mut EventLoop<'a>, &SignalInfo) + 'a,
+    {
+        let idx = signal_index(sig)?;
+        if !self.signal_mask.contains(sig) {
+            let mut mask = self.signal_mask;
+            mask.add(sig);
+            self.set_signal_mask(mask)?;
+            self.num_signals += 1;
+        }
+        self.signals[idx] = Some(Box::new(cb));
+        Ok(())
+    }
+
+    /// Removes the handler of a signal.
+    ///
+    /// [argument, sig]
+    /// The signal.
+    ///
+    /// = Remarks
+    ///
+    /// If the signal was blocked by `add_signal`, it is unblocked.
+    pub fn remove_signal(&mut self, sig: Signal) -> Result {
+        let idx = signal_index(sig)?;
+        if !self.signal_mask.contains(sig) {
+            return Err(error::InvalidArgument);
+        }
+        let mut mask = self.signal_mask;
+        mask.remove(sig);
+        self.set_signal_mask(mask)?;
+        self.signals[idx] = None;
+        self.num_signals -= 1;
+        Ok(())
+    }
+
+    fn set_signal_mask(&mut self, mask: SigSet) -> Result {
+        let old = signal::block(&mask)?;
+        // Remember which signals we blocked so that we only unblock those.
+        for sig in &mask {
+            if !old.contains(sig) {
+                self.blocked.add(sig);
+            }
+        }
+        match self.signal_fd {
+            Some(ref fd) => fd.set_mask(&mask)?,
+            _ => {
+                let fd = SignalFd::new_non_blocking(&mask)?;
+                self.epoll.add(&fd, SIGNAL_TOKEN, EPOLL_IN)?;
+                self.signal_fd = Some(fd);
+            },
+        }
+        let mut unblock = SigSet::empty();
+        for sig in &self.blocked {
+            if !mask.contains(sig) {
+                unblock.add(sig);
+            }
+        }
+        for sig in &unblock {
+            self.blocked.remove(sig);
+        }
+        signal::unblock(&unblock)?;
+        self.signal_mask = mask;
+        Ok(())
+    }
+
+    /// Registers a callback that is invoked during the next iteration of the loop.
+    ///
+    /// [argument, cb]
+    /// The callback.
+    pub fn defer<C>(&mut self, cb: C)
+        where C: FnOnce(&mut EventLoop<'a>) + 'a,
+    {
+        self.deferred.push(Box::new(cb));
+    }
+
+    /// Causes `run` to return after the current iteration.
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    /// Runs the loop until `stop` is called or nothing is registered.
+    pub fn run(&mut self) -> Result {
+        self.stopped = false;
+        while !self.stopped && !self.is_idle() {
+            self.run_once(None)?;
+        }
+        Ok(())
+    }
+
+    fn is_idle(&self) -> bool {
+        self.io.is_empty() && self.timers.is_empty() && self.num_signals == 0 &&
+            self.deferred.is_empty()
+    }
+
+    /// Runs one iteration of the loop.
+    ///
+    /// [argument, timeout]
+    /// The maximum time to wait for events or `None` to wait indefinitely.
+    ///
+    /// = Remarks
+    ///
+    /// Deferred callbacks are run first. If callbacks are deferred while they run, the
+    /// loop does not block waiting for events. If the loop has been stopped or nothing
+    /// is registered after the deferred callbacks have run, this function returns
+    /// immediately.
+    pub fn run_once(&mut self, timeout: Option<Time>) -> Result {
+        let deferred = mem::take(&mut self.deferred);
+        for cb in deferred {
+            cb(self);
+        }
+        if self.stopped || self.is_idle() {
+            return Ok(());
+        }
+        self.arm_timer()?;
+
+        let timeout = match self.deferred.is_empty() {
+            true => timeout,
+            false => Some(Time::nanoseconds(0)),
+        };
+        let mut buf = [d8::new(0); MAX_EVENTS * mem::size_of::<epoll_event>()];
+        let mut events = Events::new(&mut buf);
+        let iter = match self.epoll.wait(&mut events, timeout) {
+            Ok(iter) => iter,
+            Err(error::Interrupted) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        for (token, flags) in iter {
+            match token {
+                TIMER_TOKEN => self.dispatch_timers()?,
+                SIGNAL_TOKEN => self.dispatch_signals()?,
+                _ => self.dispatch_io(token, flags),
+            }
+        }
+        self.arm_timer()
+    }
+
+    fn dispatch_io(&mut self, id: u64, flags: EpollFlags) {
+        let mut cb = match self.io.get_mut(id).and_then(|e| e.cb.take()) {
+            Some(cb) => cb,
+            _ => return,
+        };
+        cb(self, flags);
+        if let Some(entry) = self.io.get_mut(id) {
+            if entry.cb.is_none() {
+                entry.cb = Some(cb);
+            }
+        }
+    }
+
+    fn dispatch_timers(&mut self) -> Result {
+        match self.timer.ticks() {
+            Ok(_) | Err(error::WouldBlock) => { },
+            Err(e) => return Err(e),
+        }
+        self.armed = None;
+        let now = MONO.get_time()?;
+        while let Some(&Reverse((deadline, id))) = self.deadlines.peek() {
+            if deadline > now {
+                break;
+            }
+            self.deadlines.pop();
+            let (mut cb, periodic) = match self.timers.get_mut(id) {
+                Some(entry) if entry.deadline == deadline => {
+                    let cb = match entry.cb.take() {
+                        Some(cb) => cb,
+                        _ => continue,
+                    };
+                    if let Some(iv) = entry.interval {
+                        let mut next = (deadline + iv).normalize();
+                        if next <= now {
+                            next = (now + iv).normalize();
+                        }
+                        entry.deadline = next;
+                        self.deadlines.push(Reverse((next, id)));
+                    }
+                    (cb, entry.interval.is_some())
+                },
+                _ => continue,
+            };
+            if !periodic {
+                self.timers.remove(id);
+            }
+            cb(self);
+            if periodic {
+                if let Some(entry) = self.timers.get_mut(id) {
+                    if entry.cb.is_none() {
+                        entry.cb = Some(cb);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn dispatch_signals(&mut self) -> Result {
+        loop {
+            let info = match self.signal_fd.as_ref().map(|fd| fd.read()) {
+                Some(Ok(info)) => info,
+                Some(Err(error::WouldBlock)) | None => return Ok(()),
+                Some(Err(e)) => return Err(e),
+            };
+            let idx = info.signal.to_raw() as usize - 1;
+            let mut cb = match self.signals.get_mut(idx).and_then(|cb| cb.take()) {
+                Some(cb) => cb,
+                _ => continue,
+            };
+            cb(self, &info);
+            // The handler might have been removed or replaced by the callback.
+            if self.signals[idx].is_none() && self.signal_mask.contains(info.signal) {
+                self.signals[idx] = Some(cb);
+            }
+        }
+    }
+
+    /// Arms the timerfd for the earliest live deadline.
+    fn arm_timer(&mut self) -> Result {
+        let mut next = None;
+        while let Some(&Reverse((deadline, id))) = self.deadlines.peek() {
+            match self.timers.get_mut(id) {
+                Some(entry) if entry.deadline == deadline => {
+                    next = Some(deadline);
+                    break;
+                },
+                _ => { self.deadlines.pop(); },
+            }
+        }
+        if next == self.armed {
+            return Ok(());
+        }
+        match next {
+            Some(deadline) => self.timer.once_at(deadline)?,
+            _ => self.timer.disable()?,
+        }
+        self.armed = next;
+        Ok(())
+    }
+}
+
+impl<'a> Drop for EventLoop<'a> {
+    fn drop(&mut self) {
+        if !self.blocked.is_empty() {
+            let _ = signal::unblock(&self.blocked);
+        }
+    }
+}
+
+fn signal_index(sig: Signal) -> Result<usize> {
+    match sig.to_raw() as usize {
+        n if (1..=_NSIG).contains(&n) => Ok(n - 1),
+        _ => Err(error::InvalidArgument),
+    }
+}
@@ -0,0 +1,299 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use core::ops::{BitOr, Not, BitAnd};
+use core::fmt::{self, Debug, Formatter};
+#[cfg(feature = "alloc")]
+use alloc::sync::{Arc};
+use crate::{
+    result::{Result},
+    lmem,
+    fd::{FdContainer},
+    util::{
+        retry::{retry},
+        error::{self},
+    },
+};
+use crate::kty::{
+    c_int, c_short, pollfd, timespec, EFD_CLOEXEC, EFD_NONBLOCK, EFD_SEMAPHORE, POLLIN,
+};
+use crate::syscall::{eventfd2, ppoll, read, write, close};
+
+/// Flags used when creating an eventfd.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct EventFdFlags(pub c_int);
+
+impl EventFdFlags {
+    /// Returns whether all flags of another set are set in this set.
+    ///
+    /// [argument, other]
+    /// The flags to check.
+    pub fn is_set(self, other: EventFdFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for EventFdFlags {
+    type Output = EventFdFlags;
+    fn bitor(self, other: EventFdFlags) -> EventFdFlags {
+        EventFdFlags(self.0 | other.0)
+    }
+}
+
+impl BitAnd for EventFdFlags {
+    type Output = EventFdFlags;
+    fn bitand(self, other: EventFdFlags) -> EventFdFlags {
+        EventFdFlags(self.0 & other.0)
+    }
+}
+
+impl Not for EventFdFlags {
+    type Output = EventFdFlags;
+    fn not(self) -> EventFdFlags {
+        EventFdFlags(!self.0)
+    }
+}
+
+/// Dummy flag with all flags unset.
+pub const EVENTFD_NONE: EventFdFlags = EventFdFlags(0);
+
+macro_rules! create_flags {
+    ($($(#[$meta:meta])* flag $name:ident = $val:expr;)*) => {
+        $($(#[$meta])* pub const $name: EventFdFlags = EventFdFlags($val);)*
+
+        impl Debug for EventFdFlags {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let mut first = true;
+                $(
+                    if self.0 & $val != 0 {
+                        if !first { core::write!(f, "|")?; }
+                        first = false;
+                        core::write!(f, stringify!($name))?;
+                    }
+                )*
+                if first {
+                    core::write!(f, "EVENTFD_NONE")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+create_flags! {
+    #[doc = "The eventfd is closed when `exec` is called.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "Unless lrs was compiled with the `no-auto-cloexec` flag, this flag is always\n"]
+    #[doc = "set.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:eventfd(2) and EFD_CLOEXEC therein"]
+    flag EVENTFD_CLOSE_ON_EXEC = EFD_CLOEXEC;
+
+    #[doc = "Operations on the eventfd do not block.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:eventfd(2) and EFD_NONBLOCK therein"]
+    flag EVENTFD_NON_BLOCKING = EFD_NONBLOCK;
+
+    #[doc = "Every `take` decrements the counter by one instead of resetting it.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:eventfd(2) and EFD_SEMAPHORE therein"]
+    flag EVENTFD_SEMAPHORE = EFD_SEMAPHORE;
+}
+
+/// An eventfd.
+///
+/// = Remarks
+///
+/// An eventfd is a 64 bit counter that can be waited on. It is readable while the
+/// counter is non-zero.
+///
+/// = See also
+///
+/// * link:man:eventfd(2)
+pub struct EventFd {
+    fd: c_int,
+    owned: bool,
+}
+
+impl EventFd {
+    /// Creates a new eventfd.
+    ///
+    /// [argument, initial]
+    /// The initial value of the counter.
+    ///
+    /// [argument, flags]
+    /// Flags used when creating the eventfd.
+    ///
+    /// = See also
+    ///
+    /// * link:man:eventfd(2)
+    pub fn new(initial: u32, flags: EventFdFlags) -> Result<EventFd> {
+        let fd = rv!(eventfd2(initial, flags.0), -> c_int)?;
+        Ok(EventFd::from_owned(fd))
+    }
+
+    /// Adds a value to the counter.
+    ///
+    /// [argument, val]
+    /// The value to add.
+    ///
+    /// = Remarks
+    ///
+    /// The counter cannot exceed `!0 - 1`. If the addition would exceed this value, the
+    /// call blocks until the counter has been decremented or returns `EAGAIN` if the
+    /// eventfd is non-blocking. Adding `!0` returns `EINVAL`.
+    pub fn add(&self, val: u64) -> Result {
+        retry(|| write(self.fd, lmem::as_data(&val))).map(|_| ())
+    }
+
+    /// Takes the value of the counter.
+    ///
+    /// [return_value]
+    /// Returns the value of the counter or `1` in semaphore mode.
+    ///
+    /// = Remarks
+    ///
+    /// The counter is reset to `0` or, in semaphore mode, decremented by one. If the
+    /// counter is `0`, the call blocks until it becomes non-zero or returns `EAGAIN` if
+    /// the eventfd is non-blocking.
+    pub fn take(&self) -> Result<u64> {
+        let mut val = 0u64;
+        retry(|| read(self.fd, lmem::as_mut_data(&mut val)))?;
+        Ok(val)
+    }
+
+    /// Takes the value of the counter without blocking.
+    ///
+    /// [return_value]
+    /// Returns the value of the counter or `None` if the counter is `0`.
+    ///
+    /// = Remarks
+    ///
+    /// This function does not block even if the eventfd is blocking. However, if
+    /// another thread takes the value of a blocking eventfd concurrently, the call can
+    /// block until the counter becomes non-zero again.
+    pub fn try_take(&self) -> Result<Option<u64>> {
+        let mut fds = [pollfd { fd: self.fd, events: POLLIN as c_short, revents: 0 }];
+        let timeout = timespec { tv_sec: 0, tv_nsec: 0 };
+        if retry(|| ppoll(&mut fds, Some(&timeout), None))? == 0 {
+            return Ok(None);
+        }
+        match self.take() {
+            Ok(val) => Ok(Some(val)),
+            Err(error::WouldBlock) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates a waker from this eventfd.
+    ///
+    /// = Remarks
+    ///
+    /// The eventfd should be non-blocking.
+    #[cfg(feature = "alloc")]
+    pub fn into_waker(self) -> Waker {
+        Waker { efd: Arc::new(self) }
+    }
+}
+
+impl Drop for EventFd {
+    fn drop(&mut self) {
+        if self.owned {
+            close(self.fd);
+        }
+    }
+}
+
+impl From<EventFd> for c_int {
+    fn from(efd: EventFd) -> Self {
+        let fd = efd.fd;
+        mem::forget(efd);
+        fd
+    }
+}
+
+impl FdContainer for EventFd {
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    fn borrow(&self) -> c_int {
+        self.fd
+    }
+
+    fn from_owned(fd: c_int) -> EventFd {
+        EventFd { fd, owned: true }
+    }
+
+    fn from_borrowed(fd: c_int) -> EventFd {
+        EventFd { fd, owned: false }
+    }
+}
+
+/// A handle that wakes up a thread waiting on an eventfd.
+///
+/// = Remarks
+///
+/// The handle can be cloned and sent to other threads. The waiting thread registers
+/// the eventfd returned by `event_fd` with its epoll instance and calls `reset` when
+/// the eventfd becomes readable.
+///
+/// = Examples
+///
+/// ----
+/// let waker = Waker::new()?;
+/// epoll.add(waker.event_fd(), WAKE_TOKEN, EPOLL_IN)?;
+/// let remote = waker.clone();
+/// thread::spawn(move || remote.wake());
+/// ----
+///
+/// = See also
+///
+/// * link:lrs::eventfd::EventFd
+#[cfg(feature = "alloc")]
+#[derive(Clone)]
+pub struct Waker {
+    efd: Arc<EventFd>,
+}
+
+#[cfg(feature = "alloc")]
+impl Waker {
+    /// Creates a new waker backed by a non-blocking eventfd.
+    pub fn new() -> Result<Waker> {
+        Ok(EventFd::new(0, EVENTFD_NON_BLOCKING)?.into_waker())
+    }
+
+    /// Wakes up the waiting thread.
+    ///
+    /// = Remarks
+    ///
+    /// Multiple wakeups before the next `reset` are coalesced.
+    pub fn wake(&self) -> Result {
+        match self.efd.add(1) {
+            Err(error::WouldBlock) => Ok(()),
+            res => res,
+        }
+    }
+
+    /// Resets the waker after a wakeup.
+    ///
+    /// [return_value]
+    /// Returns whether the waker had been woken up.
+    pub fn reset(&self) -> Result<bool> {
+        Ok(self.efd.try_take()?.is_some())
+    }
+
+    /// Returns the eventfd of the waker.
+    pub fn event_fd(&self) -> &EventFd {
+        &self.efd
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Debug for Waker {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Waker").field("fd", &self.efd.fd).finish()
+    }
+}
@@ -0,0 +1,503 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use core::ops::{BitOr, Not, BitAnd, Deref};
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    lmem,
+    fd::{FdContainer},
+    file::{File},
+    util::{
+        retry::{retry},
+        kstr::{ToKStr},
+        data::{d8, DataSlice},
+    },
+};
+use crate::kty::{
+    c_int, c_uint, pid_t, fanotify_event_metadata, fanotify_response, AT_FDCWD,
+    O_RDONLY, O_LARGEFILE, FANOTIFY_METADATA_VERSION, FAN_NOFD, FAN_ALLOW, FAN_DENY,
+    FAN_NONBLOCK, FAN_CLASS_CONTENT, FAN_CLASS_PRE_CONTENT, FAN_UNLIMITED_QUEUE,
+    FAN_UNLIMITED_MARKS, FAN_ACCESS, FAN_MODIFY, FAN_CLOSE_WRITE, FAN_CLOSE_NOWRITE,
+    FAN_OPEN, FAN_OPEN_EXEC, FAN_Q_OVERFLOW, FAN_OPEN_PERM, FAN_ACCESS_PERM,
+    FAN_OPEN_EXEC_PERM, FAN_EVENT_ON_CHILD, FAN_ONDIR, FAN_CLOSE, FAN_MARK_ADD,
+    FAN_MARK_REMOVE, FAN_MARK_MOUNT,
+};
+use crate::syscall::{fanotify_init, fanotify_mark, read, write, close};
+
+/// The minimum size of a buffer that can hold any single event.
+pub const EVENT_BUF_SIZE: usize = mem::size_of::<fanotify_event_metadata>();
+
+macro_rules! flags_type {
+    ($name:ident, $none:ident) => {
+        impl $name {
+            /// Returns whether all flags of another set are set in this set.
+            ///
+            /// [argument, other]
+            /// The flags to check.
+            pub fn is_set(self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+        }
+
+        impl BitOr for $name {
+            type Output = $name;
+            fn bitor(self, other: $name) -> $name {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl BitAnd for $name {
+            type Output = $name;
+            fn bitand(self, other: $name) -> $name {
+                $name(self.0 & other.0)
+            }
+        }
+
+        impl Not for $name {
+            type Output = $name;
+            fn not(self) -> $name {
+                $name(!self.0)
+            }
+        }
+
+        /// Dummy flag with all flags unset.
+        pub const $none: $name = $name(0);
+    }
+}
+
+macro_rules! create_flags {
+    ($ty:ident, $none:ident; $($(#[$meta:meta])* flag $name:ident = $val:expr;)*) => {
+        $($(#[$meta])* pub const $name: $ty = $ty($val);)*
+
+        impl Debug for $ty {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let mut first = true;
+                $(
+                    if self.0 & $val != 0 {
+                        if !first { core::write!(f, "|")?; }
+                        first = false;
+                        core::write!(f, stringify!($name))?;
+                    }
+                )*
+                if first {
+                    core::write!(f, stringify!($none))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Flags used when creating an fanotify group.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct InitFlags(pub c_uint);
+
+flags_type!(InitFlags, INIT_NONE);
+
+create_flags! {
+    InitFlags, INIT_NONE;
+
+    #[doc = "The group receives permission events after the content of the file is\n"]
+    #[doc = "available.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "Without this flag or `INIT_CLASS_PRE_CONTENT`, the group only receives\n"]
+    #[doc = "notification events.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_init(2) and FAN_CLASS_CONTENT therein"]
+    flag INIT_CLASS_CONTENT = FAN_CLASS_CONTENT;
+
+    #[doc = "The group receives permission events before the content of the file is\n"]
+    #[doc = "available.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_init(2) and FAN_CLASS_PRE_CONTENT therein"]
+    flag INIT_CLASS_PRE_CONTENT = FAN_CLASS_PRE_CONTENT;
+
+    #[doc = "The event queue of the group is not limited.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_init(2) and FAN_UNLIMITED_QUEUE therein"]
+    flag INIT_UNLIMITED_QUEUE = FAN_UNLIMITED_QUEUE;
+
+    #[doc = "The number of marks of the group is not limited.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_init(2) and FAN_UNLIMITED_MARKS therein"]
+    flag INIT_UNLIMITED_MARKS = FAN_UNLIMITED_MARKS;
+
+    #[doc = "Reading from the group does not block.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_init(2) and FAN_NONBLOCK therein"]
+    flag INIT_NON_BLOCKING = FAN_NONBLOCK;
+}
+
+/// Events of an fanotify mark.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct EventMask(pub u64);
+
+flags_type!(EventMask, EVENT_NONE);
+
+create_flags! {
+    EventMask, EVENT_NONE;
+
+    #[doc = "The file was read.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_ACCESS therein"]
+    flag EVENT_ACCESS = FAN_ACCESS;
+
+    #[doc = "The file was modified.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_MODIFY therein"]
+    flag EVENT_MODIFY = FAN_MODIFY;
+
+    #[doc = "A file opened for writing was closed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_CLOSE_WRITE therein"]
+    flag EVENT_CLOSE_WRITE = FAN_CLOSE_WRITE;
+
+    #[doc = "A file not opened for writing was closed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_CLOSE_NOWRITE therein"]
+    flag EVENT_CLOSE_NO_WRITE = FAN_CLOSE_NOWRITE;
+
+    #[doc = "The file was opened.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_OPEN therein"]
+    flag EVENT_OPEN = FAN_OPEN;
+
+    #[doc = "The file was opened for execution.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_OPEN_EXEC therein"]
+    flag EVENT_OPEN_EXEC = FAN_OPEN_EXEC;
+
+    #[doc = "Events were lost because the event queue overflowed.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "This event is never associated with a file.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify(7) and FAN_Q_OVERFLOW therein"]
+    flag EVENT_QUEUE_OVERFLOW = FAN_Q_OVERFLOW;
+
+    #[doc = "The file is being opened and the group decides whether this is allowed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_OPEN_PERM therein"]
+    flag EVENT_OPEN_PERM = FAN_OPEN_PERM;
+
+    #[doc = "The file is being read and the group decides whether this is allowed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_ACCESS_PERM therein"]
+    flag EVENT_ACCESS_PERM = FAN_ACCESS_PERM;
+
+    #[doc = "The file is being opened for execution and the group decides whether\n"]
+    #[doc = "this is allowed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_OPEN_EXEC_PERM therein"]
+    flag EVENT_OPEN_EXEC_PERM = FAN_OPEN_EXEC_PERM;
+
+    #[doc = "Events of the direct children of a marked directory are reported.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_EVENT_ON_CHILD therein"]
+    flag EVENT_ON_CHILD = FAN_EVENT_ON_CHILD;
+
+    #[doc = "Events of directories are reported.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "In events, this flag is set if the file is a directory.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fanotify_mark(2) and FAN_ONDIR therein"]
+    flag EVENT_ON_DIR = FAN_ONDIR;
+}
+
+/// A file opened for writing or not opened for writing was closed.
+pub const EVENT_CLOSE: EventMask = EventMask(FAN_CLOSE);
+
+/// All permission events.
+pub const EVENT_ALL_PERM: EventMask =
+    EventMask(FAN_OPEN_PERM | FAN_ACCESS_PERM | FAN_OPEN_EXEC_PERM);
+
+/// An fanotify group.
+///
+/// = Remarks
+///
+/// Creating a group requires the `CAP_SYS_ADMIN` capability.
+///
+/// = See also
+///
+/// * link:man:fanotify(7)
+pub struct Fanotify {
+    fd: c_int,
+    owned: bool,
+}
+
+impl Fanotify {
+    /// Creates a new fanotify group.
+    ///
+    /// [argument, flags]
+    /// Flags used when creating the group.
+    ///
+    /// = Remarks
+    ///
+    /// The files of events are opened read-only.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fanotify_init(2)
+    pub fn new(flags: InitFlags) -> Result<Fanotify> {
+        let fd = rv!(fanotify_init(flags.0, O_RDONLY | O_LARGEFILE), -> c_int)?;
+        Ok(Fanotify::from_owned(fd))
+    }
+
+    fn mark_op<P: ToKStr>(&self, flags: c_uint, path: P, mask: EventMask) -> Result {
+        path.with_kstr(|path| rv!(fanotify_mark(self.fd, flags, mask.0, AT_FDCWD, path)))
+    }
+
+    /// Adds events to the mark of a file or directory.
+    ///
+    /// [argument, path]
+    /// The path of the file or directory.
+    ///
+    /// [argument, mask]
+    /// The events to add.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fanotify_mark(2) and FAN_MARK_ADD therein
+    pub fn mark<P: ToKStr>(&self, path: P, mask: EventMask) -> Result {
+        self.mark_op(FAN_MARK_ADD, path, mask)
+    }
+
+    /// Adds events to the mark of a mount.
+    ///
+    /// [argument, path]
+    /// A path in the mount.
+    ///
+    /// [argument, mask]
+    /// The events to add.
+    ///
+    /// = Remarks
+    ///
+    /// The mark covers all files in the mount.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fanotify_mark(2) and FAN_MARK_MOUNT therein
+    pub fn mark_mount<P: ToKStr>(&self, path: P, mask: EventMask) -> Result {
+        self.mark_op(FAN_MARK_ADD | FAN_MARK_MOUNT, path, mask)
+    }
+
+    /// Removes events from the mark of a file or directory.
+    ///
+    /// [argument, path]
+    /// The path of the file or directory.
+    ///
+    /// [argument, mask]
+    /// The events to remove.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fanotify_mark(2) and FAN_MARK_REMOVE therein
+    pub fn unmark<P: ToKStr>(&self, path: P, mask: EventMask) -> Result {
+        self.mark_op(FAN_MARK_REMOVE, path, mask)
+    }
+
+    /// Removes events from the mark of a mount.
+    ///
+    /// [argument, path]
+    /// A path in the mount.
+    ///
+    /// [argument, mask]
+    /// The events to remove.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fanotify_mark(2) and FAN_MARK_REMOVE therein
+    pub fn unmark_mount<P: ToKStr>(&self, path: P, mask: EventMask) -> Result {
+        self.mark_op(FAN_MARK_REMOVE | FAN_MARK_MOUNT, path, mask)
+    }
+
+    /// Reads the next batch of events.
+    ///
+    /// [argument, buf]
+    /// The buffer in which the events will be stored.
+    ///
+    /// [return_value]
+    /// Returns an iterator over the events in the buffer.
+    ///
+    /// = Remarks
+    ///
+    /// The buffer should be at least `EVENT_BUF_SIZE` bytes long. If the group is
+    /// non-blocking and no events are pending, `EAGAIN` is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fanotify(7)
+    pub fn read<'a>(&'a self, buf: &'a mut [d8]) -> Result<Events<'a>> {
+        let buf = buf.align_for_mut::<fanotify_event_metadata>();
+        let len = retry(|| read(self.fd, buf))? as usize;
+        let buf = unsafe { buf[..len].as_bytes() };
+        Ok(Events { fanotify: self, buf })
+    }
+}
+
+impl Drop for Fanotify {
+    fn drop(&mut self) {
+        if self.owned {
+            close(self.fd);
+        }
+    }
+}
+
+impl From<Fanotify> for c_int {
+    fn from(fanotify: Fanotify) -> Self {
+        let fd = fanotify.fd;
+        mem::forget(fanotify);
+        fd
+    }
+}
+
+impl FdContainer for Fanotify {
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    fn borrow(&self) -> c_int {
+        self.fd
+    }
+
+    fn from_owned(fd: c_int) -> Fanotify {
+        Fanotify { fd, owned: true }
+    }
+
+    fn from_borrowed(fd: c_int) -> Fanotify {
+        Fanotify { fd, owned: false }
+    }
+}
+
+/// An iterator over a batch of fanotify events.
+///
+/// = Remarks
+///
+/// Each item consists of the event mask, the id of the process that caused the event,
+/// and the file of the event. The file is `None` for `EVENT_QUEUE_OVERFLOW` events.
+///
+/// Events that are never yielded are dropped when the iterator is dropped.
+pub struct Events<'a> {
+    fanotify: &'a Fanotify,
+    buf: &'a [u8],
+}
+
+impl<'a> Events<'a> {
+    /// Returns whether there are no more events in the batch.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = (EventMask, pid_t, Option<EventFile<'a>>);
+
+    fn next(&mut self) -> Option<(EventMask, pid_t, Option<EventFile<'a>>)> {
+        let buf = self.buf;
+        let ev: &fanotify_event_metadata = lmem::from_bytes(buf)?;
+        let len = ev.event_len as usize;
+        if ev.vers != FANOTIFY_METADATA_VERSION || len < EVENT_BUF_SIZE || len > buf.len() {
+            self.buf = &[];
+            return None;
+        }
+        self.buf = &buf[len..];
+        let mask = EventMask(ev.mask);
+        let file = match ev.fd {
+            FAN_NOFD => None,
+            fd => Some(EventFile {
+                fanotify: self.fanotify,
+                file: File::from_owned(fd),
+                pending: mask & EVENT_ALL_PERM != EVENT_NONE,
+            }),
+        };
+        Some((mask, ev.pid, file))
+    }
+}
+
+impl<'a> Drop for Events<'a> {
+    fn drop(&mut self) {
+        for _ in self { }
+    }
+}
+
+/// The file of a fanotify event.
+///
+/// = Remarks
+///
+/// The file is opened by the kernel for every event and closed when this object is
+/// dropped.
+///
+/// The process that caused a permission event blocks until the event has been
+/// answered with `allow` or `deny`. If neither has been called, the operation is
+/// allowed when this object is dropped.
+pub struct EventFile<'a> {
+    fanotify: &'a Fanotify,
+    file: File,
+    pending: bool,
+}
+
+impl<'a> EventFile<'a> {
+    fn respond(&mut self, response: u32) -> Result {
+        self.pending = false;
+        let response = fanotify_response {
+            fd: self.file.borrow(),
+            response,
+        };
+        retry(|| write(self.fanotify.fd, lmem::as_data(&response))).map(|_| ())
+    }
+
+    /// Returns whether this is the file of a permission event that has not been
+    /// answered.
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Allows the operation of a permission event.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fanotify(7) and FAN_ALLOW therein
+    pub fn allow(mut self) -> Result {
+        self.respond(FAN_ALLOW)
+    }
+
+    /// Denies the operation of a permission event.
+    ///
+    /// = Remarks
+    ///
+    /// The operation fails with `EPERM` in the process that caused the event.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fanotify(7) and FAN_DENY therein
+    pub fn deny(mut self) -> Result {
+        self.respond(FAN_DENY)
+    }
+}
+
+impl<'a> Deref for EventFile<'a> {
+    type Target = File;
+
+    fn deref(&self) -> &File {
+        &self.file
+    }
+}
+
+impl<'a> Drop for EventFile<'a> {
+    fn drop(&mut self) {
+        if self.pending {
+            let _ = self.respond(FAN_ALLOW);
+        }
+    }
+}
+
+impl<'a> Debug for EventFile<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("EventFile")
+            .field("fd", &self.file.borrow())
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
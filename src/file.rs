@@ -0,0 +1,606 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+pub use self::mode::{
+    Mode, MODE_NONE, MODE_SET_USER_ID, MODE_SET_GROUP_ID, MODE_STICKY, MODE_USER_READ,
+    MODE_USER_WRITE, MODE_USER_EXEC, MODE_GROUP_READ, MODE_GROUP_WRITE, MODE_GROUP_EXEC,
+    MODE_WORLD_READ, MODE_WORLD_WRITE, MODE_WORLD_EXEC,
+};
+pub use self::metadata::{Metadata, Device};
+pub(crate) use self::metadata::{metadata_at};
+
+use core::{mem};
+use crate::{
+    result::{Result},
+    util::{
+        error::{self},
+        retry::{retry},
+        io::{Read, Write},
+        kstr::{ToKStr},
+        data::{d8},
+    },
+    fd::{FdContainer},
+};
+use crate::kty::{
+    c_int, c_uint, c_uchar, umode_t, loff_t, AT_FDCWD, O_RDONLY, O_WRONLY, O_RDWR, O_CREAT, O_EXCL,
+    O_TRUNC, O_APPEND, O_TMPFILE, O_PATH, O_NOFOLLOW, O_DIRECTORY, SEEK_SET, SEEK_CUR,
+    SEEK_END, SEEK_DATA, SEEK_HOLE, DT_FIFO, DT_CHR, DT_DIR, DT_BLK, DT_REG, DT_LNK,
+    DT_SOCK, AT_EMPTY_PATH, AT_SYMLINK_NOFOLLOW, S_IFMT, S_IFREG, S_IFDIR, S_IFLNK, S_IFBLK,
+    S_IFCHR, S_IFIFO, S_IFSOCK,
+};
+use crate::syscall::{
+    openat, close, read, write, readv, writev, pread, pwrite, preadv, pwritev, lseek,
+    ftruncate, fsync, fdatasync,
+};
+
+mod mode;
+mod metadata;
+
+/// An opened file.
+pub struct File {
+    fd: c_int,
+    owned: bool,
+}
+
+/// A seek operation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Seek {
+    /// Seek from the start of the file.
+    Start(i64),
+    /// Seek from the current position in the file.
+    Cur(i64),
+    /// Seek from the end of the file.
+    End(i64),
+    /// Seek to the first non-hole byte at or after the offset.
+    Data(i64),
+    /// Seek to the first hole at or after the offset.
+    Hole(i64),
+}
+
+impl Seek {
+    fn to_raw(self) -> (loff_t, c_uint) {
+        match self {
+            Seek::Start(v) => (v as loff_t, SEEK_SET),
+            Seek::Cur(v)   => (v as loff_t, SEEK_CUR),
+            Seek::End(v)   => (v as loff_t, SEEK_END),
+            Seek::Data(v)  => (v as loff_t, SEEK_DATA),
+            Seek::Hole(v)  => (v as loff_t, SEEK_HOLE),
+        }
+    }
+}
+
+/// The type of a file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileType {
+    /// A regular file.
+    Regular,
+    /// A directory.
+    Directory,
+    /// A symbolic link.
+    Symlink,
+    /// A block device.
+    BlockDevice,
+    /// A character device.
+    CharDevice,
+    /// A named pipe.
+    Fifo,
+    /// A Unix domain socket.
+    Socket,
+    /// The type is not known.
+    ///
+    /// = Remarks
+    ///
+    /// Some filesystems don't report the type of directory entries.
+    Unknown,
+}
+
+impl FileType {
+    /// Creates a file type from the `d_type` field of a directory entry.
+    ///
+    /// [argument, ty]
+    /// The `d_type` field.
+    pub fn from_dirent_type(ty: c_uchar) -> FileType {
+        match ty {
+            DT_REG  => FileType::Regular,
+            DT_DIR  => FileType::Directory,
+            DT_LNK  => FileType::Symlink,
+            DT_BLK  => FileType::BlockDevice,
+            DT_CHR  => FileType::CharDevice,
+            DT_FIFO => FileType::Fifo,
+            DT_SOCK => FileType::Socket,
+            _       => FileType::Unknown,
+        }
+    }
+
+    /// Creates a file type from the `st_mode` field of a `stat` structure.
+    ///
+    /// [argument, mode]
+    /// The `st_mode` field.
+    pub fn from_mode(mode: umode_t) -> FileType {
+        match mode & S_IFMT {
+            S_IFREG  => FileType::Regular,
+            S_IFDIR  => FileType::Directory,
+            S_IFLNK  => FileType::Symlink,
+            S_IFBLK  => FileType::BlockDevice,
+            S_IFCHR  => FileType::CharDevice,
+            S_IFIFO  => FileType::Fifo,
+            S_IFSOCK => FileType::Socket,
+            _        => FileType::Unknown,
+        }
+    }
+}
+
+/// Retrieves information about a file.
+///
+/// [argument, path]
+/// The path of the file.
+///
+/// = Remarks
+///
+/// If the path refers to a symbolic link, the link is followed.
+///
+/// = See also
+///
+/// * link:man:stat(2)
+/// * link:lrs::file::link_metadata
+pub fn metadata<P: ToKStr>(path: P) -> Result<Metadata> {
+    metadata_at(AT_FDCWD, path, 0)
+}
+
+/// Retrieves information about a file without following symbolic links.
+///
+/// [argument, path]
+/// The path of the file.
+///
+/// = See also
+///
+/// * link:man:lstat(2)
+/// * link:lrs::file::metadata
+pub fn link_metadata<P: ToKStr>(path: P) -> Result<Metadata> {
+    metadata_at(AT_FDCWD, path, AT_SYMLINK_NOFOLLOW)
+}
+
+impl File {
+    /// Opens a file in read-only mode.
+    ///
+    /// [argument, path]
+    /// The path of the file.
+    ///
+    /// = See also
+    ///
+    /// * link:lrs::file::OpenOptions
+    pub fn open<P: ToKStr>(path: P) -> Result<File> {
+        OpenOptions::new().read(true).open(path)
+    }
+
+    /// Creates a file in write-only mode, truncating it if it already exists.
+    ///
+    /// [argument, path]
+    /// The path of the file.
+    ///
+    /// = Remarks
+    ///
+    /// If the file is created, it is created with mode `0o666` modified by the umask.
+    ///
+    /// = See also
+    ///
+    /// * link:lrs::file::OpenOptions
+    pub fn create<P: ToKStr>(path: P) -> Result<File> {
+        OpenOptions::new().write(true).create(true).truncate(true).open(path)
+    }
+
+    /// Reads from an offset in the file.
+    ///
+    /// [argument, buf]
+    /// The buffer to read into.
+    ///
+    /// [argument, off]
+    /// The offset from which to read.
+    ///
+    /// [return_value]
+    /// Returns the number of bytes read.
+    ///
+    /// = Remarks
+    ///
+    /// This does not change the position of the file description.
+    ///
+    /// = See also
+    ///
+    /// * link:man:pread(2)
+    pub fn read_at(&self, buf: &mut [d8], off: u64) -> Result<usize> {
+        retry(|| pread(self.fd, buf, off as loff_t)).map(|r| r as usize)
+    }
+
+    /// Reads from an offset in the file into multiple buffers.
+    ///
+    /// [argument, bufs]
+    /// The buffers to read into.
+    ///
+    /// [argument, off]
+    /// The offset from which to read.
+    ///
+    /// [return_value]
+    /// Returns the number of bytes read.
+    ///
+    /// = Remarks
+    ///
+    /// This does not change the position of the file description.
+    ///
+    /// = See also
+    ///
+    /// * link:man:preadv(2)
+    pub fn scatter_read_at(&self, bufs: &mut [&mut [d8]], off: u64) -> Result<usize> {
+        retry(|| preadv(self.fd, bufs, off as loff_t)).map(|r| r as usize)
+    }
+
+    /// Writes to an offset in the file.
+    ///
+    /// [argument, buf]
+    /// The buffer to write.
+    ///
+    /// [argument, off]
+    /// The offset at which to write.
+    ///
+    /// [return_value]
+    /// Returns the number of bytes written.
+    ///
+    /// = Remarks
+    ///
+    /// This does not change the position of the file description. If the file was
+    /// opened in append mode, the kernel ignores the offset and appends the data.
+    ///
+    /// = See also
+    ///
+    /// * link:man:pwrite(2)
+    pub fn write_at(&self, buf: &[u8], off: u64) -> Result<usize> {
+        retry(|| pwrite(self.fd, d8::from_byte_slice(buf), off as loff_t))
+            .map(|r| r as usize)
+    }
+
+    /// Writes to an offset in the file from multiple buffers.
+    ///
+    /// [argument, bufs]
+    /// The buffers to write.
+    ///
+    /// [argument, off]
+    /// The offset at which to write.
+    ///
+    /// [return_value]
+    /// Returns the number of bytes written.
+    ///
+    /// = See also
+    ///
+    /// * link:man:pwritev(2)
+    pub fn gather_write_at(&self, bufs: &[&[u8]], off: u64) -> Result<usize> {
+        retry(|| pwritev(self.fd, d8::from_byte_slice_slice(bufs), off as loff_t))
+            .map(|r| r as usize)
+    }
+
+    /// Changes the position of the file description.
+    ///
+    /// [argument, pos]
+    /// The seek operation.
+    ///
+    /// [return_value]
+    /// Returns the new position measured from the start of the file.
+    ///
+    /// = See also
+    ///
+    /// * link:man:lseek(2)
+    pub fn seek(&self, pos: Seek) -> Result<u64> {
+        let (off, whence) = pos.to_raw();
+        rv!(lseek(self.fd, off, whence), -> u64)
+    }
+
+    /// Returns the current position of the file description.
+    pub fn position(&self) -> Result<u64> {
+        self.seek(Seek::Cur(0))
+    }
+
+    /// Truncates or extends the file.
+    ///
+    /// [argument, len]
+    /// The new length of the file.
+    ///
+    /// = Remarks
+    ///
+    /// If the file is extended, the new space is filled with zeros. The position of the
+    /// file description is not changed.
+    ///
+    /// = See also
+    ///
+    /// * link:man:ftruncate(2)
+    pub fn set_len(&self, len: u64) -> Result {
+        retry(|| ftruncate(self.fd, len as loff_t)).map(|_| ())
+    }
+
+    /// Transfers the data and metadata of the file to the disk.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fsync(2)
+    pub fn sync(&self) -> Result {
+        rv!(fsync(self.fd))
+    }
+
+    /// Transfers the data of the file to the disk.
+    ///
+    /// = Remarks
+    ///
+    /// Metadata which is not required to read the data back is not transferred.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fdatasync(2)
+    pub fn data_sync(&self) -> Result {
+        rv!(fdatasync(self.fd))
+    }
+
+    /// Retrieves information about the file.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fstat(2)
+    pub fn metadata(&self) -> Result<Metadata> {
+        metadata_at(self.fd, kstr!(""), AT_EMPTY_PATH)
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        if self.owned {
+            close(self.fd);
+        }
+    }
+}
+
+impl From<File> for c_int {
+    fn from(file: File) -> Self {
+        let fd = file.fd;
+        mem::forget(file);
+        fd
+    }
+}
+
+impl FdContainer for File {
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    fn borrow(&self) -> c_int {
+        self.fd
+    }
+
+    fn from_owned(fd: c_int) -> File {
+        File { fd, owned: true }
+    }
+
+    fn from_borrowed(fd: c_int) -> File {
+        File { fd, owned: false }
+    }
+}
+
+macro_rules! impl_read_write {
+    ($ty:ty) => {
+        impl Read for $ty {
+            fn scatter_read(&mut self, bufs: &mut [&mut [d8]]) -> Result<usize> {
+                retry(|| readv(self.fd, bufs)).map(|r| r as usize)
+            }
+
+            fn read(&mut self, buf: &mut [d8]) -> Result<usize> {
+                retry(|| read(self.fd, buf)).map(|r| r as usize)
+            }
+        }
+
+        impl Write for $ty {
+            fn gather_write(&mut self, bufs: &[&[u8]]) -> Result<usize> {
+                retry(|| writev(self.fd, d8::from_byte_slice_slice(bufs)))
+                    .map(|r| r as usize)
+            }
+
+            fn write(&mut self, buf: &[u8]) -> Result<usize> {
+                retry(|| write(self.fd, d8::from_byte_slice(buf))).map(|r| r as usize)
+            }
+
+            fn flush(&mut self) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        impl_std_read!($ty);
+        impl_std_write!($ty);
+    }
+}
+
+impl_read_write!(File);
+impl_read_write!(&File);
+
+/// A builder for opening files.
+///
+/// = Remarks
+///
+/// By default, no access mode is selected and opening a file fails with `EINVAL` unless
+/// `read`, `write`, or `path` is set. New files are created with mode `0o666` modified
+/// by the umask.
+///
+/// = Examples
+///
+/// ----
+/// let file = OpenOptions::new().write(true).create(true).exclusive(true)
+///                              .mode(Mode(0o600)).open(kstr!("lock"))?;
+/// ----
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OpenOptions {
+    read: bool,
+    write: bool,
+    flags: c_int,
+    mode: Mode,
+}
+
+impl OpenOptions {
+    /// Creates a new set of options with nothing selected.
+    pub fn new() -> OpenOptions {
+        OpenOptions { read: false, write: false, flags: 0, mode: Mode(0o666) }
+    }
+
+    fn flag(&mut self, flag: c_int, val: bool) -> &mut OpenOptions {
+        if val {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+        self
+    }
+
+    /// Sets whether the file is opened for reading.
+    pub fn read(&mut self, val: bool) -> &mut OpenOptions {
+        self.read = val;
+        self
+    }
+
+    /// Sets whether the file is opened for writing.
+    pub fn write(&mut self, val: bool) -> &mut OpenOptions {
+        self.write = val;
+        self
+    }
+
+    /// Sets whether the file is created if it doesn't exist.
+    ///
+    /// = See also
+    ///
+    /// * link:man:open(2) and O_CREAT therein
+    pub fn create(&mut self, val: bool) -> &mut OpenOptions {
+        self.flag(O_CREAT, val)
+    }
+
+    /// Sets whether opening fails if the file already exists.
+    ///
+    /// = Remarks
+    ///
+    /// This only has an effect together with `create`. Symbolic links are not followed
+    /// in the last component of the path.
+    ///
+    /// = See also
+    ///
+    /// * link:man:open(2) and O_EXCL therein
+    pub fn exclusive(&mut self, val: bool) -> &mut OpenOptions {
+        self.flag(O_EXCL, val)
+    }
+
+    /// Sets whether an existing file is truncated to length `0`.
+    ///
+    /// = See also
+    ///
+    /// * link:man:open(2) and O_TRUNC therein
+    pub fn truncate(&mut self, val: bool) -> &mut OpenOptions {
+        self.flag(O_TRUNC, val)
+    }
+
+    /// Sets whether all writes are performed at the end of the file.
+    ///
+    /// = See also
+    ///
+    /// * link:man:open(2) and O_APPEND therein
+    pub fn append(&mut self, val: bool) -> &mut OpenOptions {
+        self.flag(O_APPEND, val)
+    }
+
+    /// Sets whether an unnamed temporary file is created in the directory.
+    ///
+    /// = Remarks
+    ///
+    /// The path passed to `open` must refer to a directory and `write` must be set. The
+    /// file can later be linked into the filesystem with `linkat`.
+    ///
+    /// = See also
+    ///
+    /// * link:man:open(2) and O_TMPFILE therein
+    pub fn tmpfile(&mut self, val: bool) -> &mut OpenOptions {
+        self.flag(O_TMPFILE, val)
+    }
+
+    /// Sets whether only a path reference is opened.
+    ///
+    /// = Remarks
+    ///
+    /// The returned file cannot be used for reading or writing and the `read` and
+    /// `write` options are ignored.
+    ///
+    /// = See also
+    ///
+    /// * link:man:open(2) and O_PATH therein
+    pub fn path(&mut self, val: bool) -> &mut OpenOptions {
+        self.flag(O_PATH, val)
+    }
+
+    /// Sets whether opening fails if the last component of the path is a symbolic link.
+    ///
+    /// = See also
+    ///
+    /// * link:man:open(2) and O_NOFOLLOW therein
+    pub fn no_follow(&mut self, val: bool) -> &mut OpenOptions {
+        self.flag(O_NOFOLLOW, val)
+    }
+
+    /// Sets whether opening fails if the path does not refer to a directory.
+    ///
+    /// = See also
+    ///
+    /// * link:man:open(2) and O_DIRECTORY therein
+    pub fn directory(&mut self, val: bool) -> &mut OpenOptions {
+        self.flag(O_DIRECTORY, val)
+    }
+
+    /// Sets the mode of newly created files.
+    ///
+    /// [argument, mode]
+    /// The permission bits of the file before the umask is applied.
+    pub fn mode(&mut self, mode: Mode) -> &mut OpenOptions {
+        self.mode = mode;
+        self
+    }
+
+    fn raw_flags(&self) -> c_int {
+        let access = match (self.read, self.write) {
+            (true, false) => O_RDONLY,
+            (false, true) => O_WRONLY,
+            (true, true) => O_RDWR,
+            // O_PATH ignores the access mode. Everything else fails with EINVAL below.
+            (false, false) => O_RDONLY,
+        };
+        access | self.flags
+    }
+
+    /// Opens a file.
+    ///
+    /// [argument, path]
+    /// The path of the file.
+    ///
+    /// = Remarks
+    ///
+    /// Relative paths are interpreted relative to the current working directory.
+    pub fn open<P: ToKStr>(&self, path: P) -> Result<File> {
+        self.open_at(AT_FDCWD, path)
+    }
+
+    /// Opens a file relative to a directory.
+    ///
+    /// [argument, dir]
+    /// The directory relative to which relative paths are interpreted.
+    ///
+    /// [argument, path]
+    /// The path of the file.
+    ///
+    /// = See also
+    ///
+    /// * link:man:openat(2)
+    pub fn open_at<P: ToKStr>(&self, dir: c_int, path: P) -> Result<File> {
+        if !self.read && !self.write && self.flags & O_PATH == 0 {
+            return Err(error::InvalidArgument);
+        }
+        let flags = self.raw_flags();
+        let fd = path.with_kstr(|path| retry(|| openat(dir, path, flags, self.mode.0)))?;
+        Ok(File::from_owned(fd))
+    }
+}
@@ -0,0 +1,189 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    lmem,
+    time::{Time},
+    util::{
+        kstr::{ToKStr},
+    },
+    file::{FileType, Mode},
+};
+use crate::kty::{c_int, umode_t, uid_t, gid_t};
+use crate::syscall::{fstatat, StatType};
+
+/// Information about a file.
+///
+/// = See also
+///
+/// * link:man:stat(2)
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    stat: StatType,
+}
+
+impl Metadata {
+    /// Creates a new object from a `stat` structure filled by the kernel.
+    ///
+    /// [argument, stat]
+    /// The structure.
+    pub fn from_stat(stat: StatType) -> Metadata {
+        Metadata { stat }
+    }
+
+    /// Returns the type of the file.
+    pub fn file_type(&self) -> FileType {
+        FileType::from_mode(self.stat.st_mode as umode_t)
+    }
+
+    /// Returns the permission bits of the file.
+    pub fn mode(&self) -> Mode {
+        Mode(self.stat.st_mode as umode_t).permissions()
+    }
+
+    /// Returns the size of the file in bytes.
+    ///
+    /// = Remarks
+    ///
+    /// For symbolic links this is the length of the target path.
+    pub fn size(&self) -> u64 {
+        self.stat.st_size as u64
+    }
+
+    /// Returns the number of 512 byte blocks allocated for the file.
+    pub fn blocks(&self) -> u64 {
+        self.stat.st_blocks as u64
+    }
+
+    /// Returns the preferred block size for I/O on the file.
+    pub fn block_size(&self) -> u64 {
+        self.stat.st_blksize as u64
+    }
+
+    /// Returns the inode number of the file.
+    pub fn ino(&self) -> u64 {
+        self.stat.st_ino
+    }
+
+    /// Returns the device that contains the file.
+    pub fn dev(&self) -> Device {
+        Device(self.stat.st_dev)
+    }
+
+    /// Returns the device represented by the file.
+    ///
+    /// = Remarks
+    ///
+    /// This is only meaningful for block and character devices.
+    pub fn rdev(&self) -> Device {
+        Device(self.stat.st_rdev)
+    }
+
+    /// Returns the number of hard links to the file.
+    pub fn nlink(&self) -> u64 {
+        self.stat.st_nlink
+    }
+
+    /// Returns the user id of the owner of the file.
+    pub fn uid(&self) -> uid_t {
+        self.stat.st_uid as uid_t
+    }
+
+    /// Returns the group id of the owner of the file.
+    pub fn gid(&self) -> gid_t {
+        self.stat.st_gid as gid_t
+    }
+
+    /// Returns the time of the last access as an offset from the epoch.
+    pub fn atime(&self) -> Time {
+        Time {
+            seconds: self.stat.st_atime as i64,
+            nanoseconds: self.stat.st_atime_nsec as i64,
+        }
+    }
+
+    /// Returns the time of the last modification as an offset from the epoch.
+    pub fn mtime(&self) -> Time {
+        Time {
+            seconds: self.stat.st_mtime as i64,
+            nanoseconds: self.stat.st_mtime_nsec as i64,
+        }
+    }
+
+    /// Returns the time of the last status change as an offset from the epoch.
+    pub fn ctime(&self) -> Time {
+        Time {
+            seconds: self.stat.st_ctime as i64,
+            nanoseconds: self.stat.st_ctime_nsec as i64,
+        }
+    }
+}
+
+impl Debug for Metadata {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metadata")
+            .field("file_type", &self.file_type())
+            .field("mode", &self.mode())
+            .field("size", &self.size())
+            .field("ino", &self.ino())
+            .field("dev", &self.dev())
+            .field("nlink", &self.nlink())
+            .field("uid", &self.uid())
+            .field("gid", &self.gid())
+            .finish()
+    }
+}
+
+/// A device number.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct Device(pub u64);
+
+impl Device {
+    /// Creates a device number from its major and minor parts.
+    ///
+    /// [argument, major]
+    /// The major number.
+    ///
+    /// [argument, minor]
+    /// The minor number.
+    pub fn from_parts(major: u32, minor: u32) -> Device {
+        let (major, minor) = (major as u64, minor as u64);
+        Device((major & 0xfff) << 8 | (major & !0xfff) << 32 |
+               (minor & 0xff) | (minor & !0xff) << 12)
+    }
+
+    /// Returns the major number of the device.
+    pub fn major(self) -> u32 {
+        ((self.0 >> 8) & 0xfff | (self.0 >> 32) & !0xfff) as u32
+    }
+
+    /// Returns the minor number of the device.
+    pub fn minor(self) -> u32 {
+        (self.0 & 0xff | (self.0 >> 12) & !0xff) as u32
+    }
+}
+
+impl Debug for Device {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        core::write!(f, "Device({}:{})", self.major(), self.minor())
+    }
+}
+
+/// Retrieves information about a file relative to a directory.
+///
+/// [argument, dir]
+/// The directory relative to which relative paths are interpreted.
+///
+/// [argument, path]
+/// The path of the file.
+///
+/// [argument, flags]
+/// Flags passed to `fstatat`.
+pub(crate) fn metadata_at<P: ToKStr>(dir: c_int, path: P, flags: c_int) -> Result<Metadata> {
+    let mut stat: StatType = lmem::zeroed();
+    path.with_kstr(|path| rv!(fstatat(dir, path, &mut stat, flags)))?;
+    Ok(Metadata { stat })
+}
@@ -0,0 +1,137 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::ops::{BitOr, Not, BitAnd};
+use core::fmt::{self, Debug, Display, Formatter};
+use crate::kty::{
+    umode_t, S_ISUID, S_ISGID, S_ISVTX, S_IRUSR, S_IWUSR, S_IXUSR, S_IRGRP, S_IWGRP,
+    S_IXGRP, S_IROTH, S_IWOTH, S_IXOTH, S_IALLUGO,
+};
+
+/// The permission bits of a file.
+///
+/// = Remarks
+///
+/// The `Display` implementation prints the mode the way `ls -l` does, e.g.,
+/// `rwxr-xr-x`.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct Mode(pub umode_t);
+
+impl Mode {
+    /// Returns whether all bits of another mode are set in this mode.
+    ///
+    /// [argument, other]
+    /// The bits to check.
+    pub fn is_set(self, other: Mode) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Sets the bits of another mode in this mode.
+    ///
+    /// [argument, other]
+    /// The bits to set.
+    pub fn set(&mut self, other: Mode) {
+        self.0 |= other.0;
+    }
+
+    /// Clears the bits of another mode in this mode.
+    ///
+    /// [argument, other]
+    /// The bits to clear.
+    pub fn unset(&mut self, other: Mode) {
+        self.0 &= !other.0;
+    }
+
+    /// Returns this mode with all non-permission bits cleared.
+    ///
+    /// = Remarks
+    ///
+    /// This removes the file type bits of an `st_mode` field.
+    pub fn permissions(self) -> Mode {
+        Mode(self.0 & S_IALLUGO)
+    }
+}
+
+impl BitOr for Mode {
+    type Output = Mode;
+    fn bitor(self, other: Mode) -> Mode {
+        Mode(self.0 | other.0)
+    }
+}
+
+impl BitAnd for Mode {
+    type Output = Mode;
+    fn bitand(self, other: Mode) -> Mode {
+        Mode(self.0 & other.0)
+    }
+}
+
+impl Not for Mode {
+    type Output = Mode;
+    fn not(self) -> Mode {
+        Mode(!self.0)
+    }
+}
+
+/// Mode with no bits set.
+pub const MODE_NONE: Mode = Mode(0);
+
+/// The set-user-ID bit.
+pub const MODE_SET_USER_ID: Mode = Mode(S_ISUID);
+/// The set-group-ID bit.
+pub const MODE_SET_GROUP_ID: Mode = Mode(S_ISGID);
+/// The sticky bit.
+pub const MODE_STICKY: Mode = Mode(S_ISVTX);
+
+/// The owner can read the file.
+pub const MODE_USER_READ: Mode = Mode(S_IRUSR);
+/// The owner can write the file.
+pub const MODE_USER_WRITE: Mode = Mode(S_IWUSR);
+/// The owner can execute the file.
+pub const MODE_USER_EXEC: Mode = Mode(S_IXUSR);
+/// The group can read the file.
+pub const MODE_GROUP_READ: Mode = Mode(S_IRGRP);
+/// The group can write the file.
+pub const MODE_GROUP_WRITE: Mode = Mode(S_IWGRP);
+/// The group can execute the file.
+pub const MODE_GROUP_EXEC: Mode = Mode(S_IXGRP);
+/// Everyone else can read the file.
+pub const MODE_WORLD_READ: Mode = Mode(S_IROTH);
+/// Everyone else can write the file.
+pub const MODE_WORLD_WRITE: Mode = Mode(S_IWOTH);
+/// Everyone else can execute the file.
+pub const MODE_WORLD_EXEC: Mode = Mode(S_IXOTH);
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // The execute column shows the special bit in place of `x`: lower case if the
+        // execute bit is also set, upper case otherwise.
+        let triple = |r, w, x, special, set: u8, unset: u8| {
+            let x = match (self.0 & x != 0, self.0 & special != 0) {
+                (true, true) => set,
+                (false, true) => unset,
+                (true, false) => b'x',
+                (false, false) => b'-',
+            };
+            [
+                if self.0 & r != 0 { b'r' } else { b'-' },
+                if self.0 & w != 0 { b'w' } else { b'-' },
+                x,
+            ]
+        };
+        let u = triple(S_IRUSR, S_IWUSR, S_IXUSR, S_ISUID, b's', b'S');
+        let g = triple(S_IRGRP, S_IWGRP, S_IXGRP, S_ISGID, b's', b'S');
+        let o = triple(S_IROTH, S_IWOTH, S_IXOTH, S_ISVTX, b't', b'T');
+        for &c in u.iter().chain(g.iter()).chain(o.iter()) {
+            core::write!(f, "{}", c as char)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Mode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        core::write!(f, "Mode({:#o}, {})", self.0, self)
+    }
+}
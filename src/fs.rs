@@ -0,0 +1,377 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    lmem,
+    util::{
+        retry::{retry},
+        kstr::{ToKStr},
+    },
+    fd::{FdContainer},
+};
+use crate::kty::{
+    c_ulong, ST_RDONLY, ST_NOSUID, ST_NODEV, ST_NOEXEC, ST_NOATIME, ADFS_SUPER_MAGIC,
+    AFFS_SUPER_MAGIC, AFS_SUPER_MAGIC, AUTOFS_SUPER_MAGIC, CODA_SUPER_MAGIC, CRAMFS_MAGIC,
+    DEBUGFS_MAGIC, SECURITYFS_MAGIC, SELINUX_MAGIC, SMACK_MAGIC, RAMFS_MAGIC, TMPFS_MAGIC,
+    HUGETLBFS_MAGIC, SQUASHFS_MAGIC, ECRYPTFS_SUPER_MAGIC, EFS_SUPER_MAGIC,
+    EROFS_SUPER_MAGIC_V1, EXT4_SUPER_MAGIC, XENFS_SUPER_MAGIC, BTRFS_SUPER_MAGIC,
+    NILFS_SUPER_MAGIC, F2FS_SUPER_MAGIC, HPFS_SUPER_MAGIC, ISOFS_SUPER_MAGIC,
+    JFFS2_SUPER_MAGIC, XFS_SUPER_MAGIC, PSTOREFS_MAGIC, EFIVARFS_MAGIC, HOSTFS_SUPER_MAGIC,
+    OVERLAYFS_SUPER_MAGIC, MINIX_SUPER_MAGIC, MINIX_SUPER_MAGIC2, MINIX2_SUPER_MAGIC,
+    MINIX2_SUPER_MAGIC2, MINIX3_SUPER_MAGIC, MSDOS_SUPER_MAGIC, NFS_SUPER_MAGIC,
+    OCFS2_SUPER_MAGIC, OPENPROM_SUPER_MAGIC, QNX4_SUPER_MAGIC, QNX6_SUPER_MAGIC,
+    REISERFS_SUPER_MAGIC, SMB_SUPER_MAGIC, CGROUP_SUPER_MAGIC, CGROUP2_SUPER_MAGIC,
+    RDTGROUP_SUPER_MAGIC, TRACEFS_MAGIC, V9FS_MAGIC, BDEVFS_MAGIC, BINFMTFS_MAGIC,
+    DEVPTS_SUPER_MAGIC, BINDERFS_SUPER_MAGIC, PIPEFS_MAGIC, PROC_SUPER_MAGIC, SOCKFS_MAGIC,
+    SYSFS_MAGIC, USBDEVICE_SUPER_MAGIC, ANON_INODE_FS_MAGIC, NSFS_MAGIC, BPF_FS_MAGIC,
+    AAFS_MAGIC, UDF_SUPER_MAGIC,
+};
+use crate::syscall::{statfs, fstatfs, StatfsType};
+
+/// The type of a filesystem.
+///
+/// = Remarks
+///
+/// This is derived from the magic number reported by the kernel. Some filesystems share
+/// a magic number. For example, ext2, ext3, and ext4 are all reported as `Ext` and vfat
+/// is reported as `Msdos`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FsType {
+    /// Acorn ADFS (`ADFS_SUPER_MAGIC`).
+    Adfs,
+    /// Amiga FFS (`AFFS_SUPER_MAGIC`).
+    Affs,
+    /// Andrew File System (`AFS_SUPER_MAGIC`).
+    Afs,
+    /// autofs (`AUTOFS_SUPER_MAGIC`).
+    Autofs,
+    /// Coda (`CODA_SUPER_MAGIC`).
+    Coda,
+    /// cramfs (`CRAMFS_MAGIC`).
+    Cramfs,
+    /// debugfs (`DEBUGFS_MAGIC`).
+    Debugfs,
+    /// securityfs (`SECURITYFS_MAGIC`).
+    Securityfs,
+    /// selinuxfs (`SELINUX_MAGIC`).
+    Selinuxfs,
+    /// smackfs (`SMACK_MAGIC`).
+    Smackfs,
+    /// ramfs (`RAMFS_MAGIC`).
+    Ramfs,
+    /// tmpfs (`TMPFS_MAGIC`).
+    Tmpfs,
+    /// hugetlbfs (`HUGETLBFS_MAGIC`).
+    Hugetlbfs,
+    /// SquashFS (`SQUASHFS_MAGIC`).
+    Squashfs,
+    /// eCryptfs (`ECRYPTFS_SUPER_MAGIC`).
+    Ecryptfs,
+    /// SGI EFS (`EFS_SUPER_MAGIC`).
+    Efs,
+    /// EROFS (`EROFS_SUPER_MAGIC_V1`).
+    Erofs,
+    /// ext2, ext3, or ext4 (`EXT4_SUPER_MAGIC`).
+    Ext,
+    /// xenfs (`XENFS_SUPER_MAGIC`).
+    Xenfs,
+    /// Btrfs (`BTRFS_SUPER_MAGIC`).
+    Btrfs,
+    /// NILFS (`NILFS_SUPER_MAGIC`).
+    Nilfs,
+    /// F2FS (`F2FS_SUPER_MAGIC`).
+    F2fs,
+    /// OS/2 HPFS (`HPFS_SUPER_MAGIC`).
+    Hpfs,
+    /// ISO 9660 (`ISOFS_SUPER_MAGIC`).
+    Isofs,
+    /// JFFS2 (`JFFS2_SUPER_MAGIC`).
+    Jffs2,
+    /// XFS (`XFS_SUPER_MAGIC`).
+    Xfs,
+    /// pstore (`PSTOREFS_MAGIC`).
+    Pstore,
+    /// efivarfs (`EFIVARFS_MAGIC`).
+    Efivarfs,
+    /// UML hostfs (`HOSTFS_SUPER_MAGIC`).
+    Hostfs,
+    /// overlayfs (`OVERLAYFS_SUPER_MAGIC`).
+    Overlayfs,
+    /// Minix (`MINIX_SUPER_MAGIC` and related constants).
+    Minix,
+    /// FAT, i.e., msdos or vfat (`MSDOS_SUPER_MAGIC`).
+    Msdos,
+    /// NFS (`NFS_SUPER_MAGIC`).
+    Nfs,
+    /// OCFS2 (`OCFS2_SUPER_MAGIC`).
+    Ocfs2,
+    /// openpromfs (`OPENPROM_SUPER_MAGIC`).
+    Openprom,
+    /// QNX4 (`QNX4_SUPER_MAGIC`).
+    Qnx4,
+    /// QNX6 (`QNX6_SUPER_MAGIC`).
+    Qnx6,
+    /// ReiserFS (`REISERFS_SUPER_MAGIC`).
+    Reiserfs,
+    /// SMB (`SMB_SUPER_MAGIC`).
+    Smb,
+    /// cgroup v1 (`CGROUP_SUPER_MAGIC`).
+    Cgroup,
+    /// cgroup v2 (`CGROUP2_SUPER_MAGIC`).
+    Cgroup2,
+    /// resctrl (`RDTGROUP_SUPER_MAGIC`).
+    Resctrl,
+    /// tracefs (`TRACEFS_MAGIC`).
+    Tracefs,
+    /// 9P (`V9FS_MAGIC`).
+    V9fs,
+    /// bdev pseudo filesystem (`BDEVFS_MAGIC`).
+    Bdevfs,
+    /// binfmt_misc (`BINFMTFS_MAGIC`).
+    Binfmtfs,
+    /// devpts (`DEVPTS_SUPER_MAGIC`).
+    Devpts,
+    /// binderfs (`BINDERFS_SUPER_MAGIC`).
+    Binderfs,
+    /// pipe pseudo filesystem (`PIPEFS_MAGIC`).
+    Pipefs,
+    /// proc (`PROC_SUPER_MAGIC`).
+    Proc,
+    /// socket pseudo filesystem (`SOCKFS_MAGIC`).
+    Sockfs,
+    /// sysfs (`SYSFS_MAGIC`).
+    Sysfs,
+    /// usbdevfs (`USBDEVICE_SUPER_MAGIC`).
+    Usbdevfs,
+    /// anonymous inode pseudo filesystem (`ANON_INODE_FS_MAGIC`).
+    AnonInodefs,
+    /// namespace pseudo filesystem (`NSFS_MAGIC`).
+    Nsfs,
+    /// BPF filesystem (`BPF_FS_MAGIC`).
+    Bpffs,
+    /// AppArmor filesystem (`AAFS_MAGIC`).
+    Apparmorfs,
+    /// UDF (`UDF_SUPER_MAGIC`).
+    Udf,
+    /// A filesystem with an unknown magic number.
+    Unknown(c_ulong),
+}
+
+impl FsType {
+    /// Creates a filesystem type from a magic number.
+    ///
+    /// [argument, magic]
+    /// The `f_type` field of a `statfs` structure.
+    pub fn from_magic(magic: c_ulong) -> FsType {
+        match magic {
+            ADFS_SUPER_MAGIC      => FsType::Adfs,
+            AFFS_SUPER_MAGIC      => FsType::Affs,
+            AFS_SUPER_MAGIC       => FsType::Afs,
+            AUTOFS_SUPER_MAGIC    => FsType::Autofs,
+            CODA_SUPER_MAGIC      => FsType::Coda,
+            CRAMFS_MAGIC          => FsType::Cramfs,
+            DEBUGFS_MAGIC         => FsType::Debugfs,
+            SECURITYFS_MAGIC      => FsType::Securityfs,
+            SELINUX_MAGIC         => FsType::Selinuxfs,
+            SMACK_MAGIC           => FsType::Smackfs,
+            RAMFS_MAGIC           => FsType::Ramfs,
+            TMPFS_MAGIC           => FsType::Tmpfs,
+            HUGETLBFS_MAGIC       => FsType::Hugetlbfs,
+            SQUASHFS_MAGIC        => FsType::Squashfs,
+            ECRYPTFS_SUPER_MAGIC  => FsType::Ecryptfs,
+            EFS_SUPER_MAGIC       => FsType::Efs,
+            EROFS_SUPER_MAGIC_V1  => FsType::Erofs,
+            EXT4_SUPER_MAGIC      => FsType::Ext,
+            XENFS_SUPER_MAGIC     => FsType::Xenfs,
+            BTRFS_SUPER_MAGIC     => FsType::Btrfs,
+            NILFS_SUPER_MAGIC     => FsType::Nilfs,
+            F2FS_SUPER_MAGIC      => FsType::F2fs,
+            HPFS_SUPER_MAGIC      => FsType::Hpfs,
+            ISOFS_SUPER_MAGIC     => FsType::Isofs,
+            JFFS2_SUPER_MAGIC     => FsType::Jffs2,
+            XFS_SUPER_MAGIC       => FsType::Xfs,
+            PSTOREFS_MAGIC        => FsType::Pstore,
+            EFIVARFS_MAGIC        => FsType::Efivarfs,
+            HOSTFS_SUPER_MAGIC    => FsType::Hostfs,
+            OVERLAYFS_SUPER_MAGIC => FsType::Overlayfs,
+            MINIX_SUPER_MAGIC | MINIX_SUPER_MAGIC2 | MINIX2_SUPER_MAGIC |
+                MINIX2_SUPER_MAGIC2 | MINIX3_SUPER_MAGIC => FsType::Minix,
+            MSDOS_SUPER_MAGIC     => FsType::Msdos,
+            NFS_SUPER_MAGIC       => FsType::Nfs,
+            OCFS2_SUPER_MAGIC     => FsType::Ocfs2,
+            OPENPROM_SUPER_MAGIC  => FsType::Openprom,
+            QNX4_SUPER_MAGIC      => FsType::Qnx4,
+            QNX6_SUPER_MAGIC      => FsType::Qnx6,
+            REISERFS_SUPER_MAGIC  => FsType::Reiserfs,
+            SMB_SUPER_MAGIC       => FsType::Smb,
+            CGROUP_SUPER_MAGIC    => FsType::Cgroup,
+            CGROUP2_SUPER_MAGIC   => FsType::Cgroup2,
+            RDTGROUP_SUPER_MAGIC  => FsType::Resctrl,
+            TRACEFS_MAGIC         => FsType::Tracefs,
+            V9FS_MAGIC            => FsType::V9fs,
+            BDEVFS_MAGIC          => FsType::Bdevfs,
+            BINFMTFS_MAGIC        => FsType::Binfmtfs,
+            DEVPTS_SUPER_MAGIC    => FsType::Devpts,
+            BINDERFS_SUPER_MAGIC  => FsType::Binderfs,
+            PIPEFS_MAGIC          => FsType::Pipefs,
+            PROC_SUPER_MAGIC      => FsType::Proc,
+            SOCKFS_MAGIC          => FsType::Sockfs,
+            SYSFS_MAGIC           => FsType::Sysfs,
+            USBDEVICE_SUPER_MAGIC => FsType::Usbdevfs,
+            ANON_INODE_FS_MAGIC   => FsType::AnonInodefs,
+            NSFS_MAGIC            => FsType::Nsfs,
+            BPF_FS_MAGIC          => FsType::Bpffs,
+            AAFS_MAGIC            => FsType::Apparmorfs,
+            UDF_SUPER_MAGIC       => FsType::Udf,
+            _ => FsType::Unknown(magic),
+        }
+    }
+}
+
+/// Information about a mounted filesystem.
+///
+/// = See also
+///
+/// * link:man:statfs(2)
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct FsInfo {
+    info: StatfsType,
+}
+
+impl FsInfo {
+    /// Retrieves information about the filesystem containing a path.
+    ///
+    /// [argument, path]
+    /// A path in the filesystem.
+    pub fn from_path<P: ToKStr>(path: P) -> Result<FsInfo> {
+        let mut info: StatfsType = lmem::zeroed();
+        path.with_kstr(|path| retry(|| statfs(path, &mut info)))?;
+        Ok(FsInfo { info })
+    }
+
+    /// Retrieves information about the filesystem containing an open file.
+    ///
+    /// [argument, fd]
+    /// A file descriptor of a file in the filesystem.
+    pub fn from_fd<F: FdContainer>(fd: &F) -> Result<FsInfo> {
+        let mut info: StatfsType = lmem::zeroed();
+        retry(|| fstatfs(fd.borrow(), &mut info))?;
+        Ok(FsInfo { info })
+    }
+
+    /// Returns the type of the filesystem.
+    pub fn fs_type(&self) -> FsType {
+        FsType::from_magic(self.info.f_type as c_ulong)
+    }
+
+    /// Returns the magic number of the filesystem.
+    pub fn magic(&self) -> c_ulong {
+        self.info.f_type as c_ulong
+    }
+
+    /// Returns the preferred block size for I/O.
+    pub fn block_size(&self) -> u64 {
+        self.info.f_bsize as u64
+    }
+
+    /// Returns the fundamental block size.
+    ///
+    /// = Remarks
+    ///
+    /// The block counts are measured in units of this size.
+    pub fn fragment_size(&self) -> u64 {
+        self.info.f_frsize as u64
+    }
+
+    /// Returns the total number of blocks in the filesystem.
+    pub fn blocks(&self) -> u64 {
+        self.info.f_blocks as u64
+    }
+
+    /// Returns the number of free blocks in the filesystem.
+    pub fn free_blocks(&self) -> u64 {
+        self.info.f_bfree as u64
+    }
+
+    /// Returns the number of free blocks available to unprivileged users.
+    pub fn available_blocks(&self) -> u64 {
+        self.info.f_bavail as u64
+    }
+
+    /// Returns the total number of inodes in the filesystem.
+    ///
+    /// = Remarks
+    ///
+    /// Some filesystems, e.g., btrfs, report `0` because they allocate inodes
+    /// dynamically.
+    pub fn files(&self) -> u64 {
+        self.info.f_files as u64
+    }
+
+    /// Returns the number of free inodes in the filesystem.
+    pub fn free_files(&self) -> u64 {
+        self.info.f_ffree as u64
+    }
+
+    /// Returns the maximum length of a file name in the filesystem.
+    pub fn max_name_len(&self) -> u64 {
+        self.info.f_namelen as u64
+    }
+
+    /// Returns the id of the filesystem.
+    pub fn id(&self) -> [i32; 2] {
+        self.info.f_fsid.val
+    }
+
+    /// Returns the mount flags of the filesystem.
+    ///
+    /// = Remarks
+    ///
+    /// This is a combination of the `ST_*` constants.
+    pub fn mount_flags(&self) -> c_ulong {
+        self.info.f_flags as c_ulong
+    }
+
+    /// Returns whether the filesystem is mounted read-only.
+    pub fn read_only(&self) -> bool {
+        self.mount_flags() & ST_RDONLY != 0
+    }
+
+    /// Returns whether set-user-ID and set-group-ID bits are ignored.
+    pub fn no_suid(&self) -> bool {
+        self.mount_flags() & ST_NOSUID != 0
+    }
+
+    /// Returns whether device files cannot be accessed.
+    pub fn no_dev(&self) -> bool {
+        self.mount_flags() & ST_NODEV != 0
+    }
+
+    /// Returns whether files cannot be executed.
+    pub fn no_exec(&self) -> bool {
+        self.mount_flags() & ST_NOEXEC != 0
+    }
+
+    /// Returns whether access times are not updated.
+    pub fn no_atime(&self) -> bool {
+        self.mount_flags() & ST_NOATIME != 0
+    }
+}
+
+impl Debug for FsInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FsInfo")
+            .field("fs_type", &self.fs_type())
+            .field("block_size", &self.block_size())
+            .field("blocks", &self.blocks())
+            .field("free_blocks", &self.free_blocks())
+            .field("available_blocks", &self.available_blocks())
+            .field("files", &self.files())
+            .field("free_files", &self.free_files())
+            .field("mount_flags", &self.mount_flags())
+            .finish()
+    }
+}
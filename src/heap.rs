@@ -0,0 +1,297 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{ptr, cmp};
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::{UnsafeCell};
+use core::sync::atomic::{Ordering};
+use crate::kty::{
+    c_int, AtomicInt, PAGE_SIZE, PROT_READ, PROT_WRITE, MAP_PRIVATE, MAP_ANONYMOUS,
+    MREMAP_MAYMOVE,
+};
+use crate::syscall::{mmap, munmap, mremap, futex_wait, futex_wake};
+
+const UNLOCKED: c_int = 0;
+const LOCKED:   c_int = 1;
+const WAITING:  c_int = 2;
+
+/// The smallest size class.
+const MIN_CLASS_SHIFT: usize = 4;
+/// The number of size classes. The largest class is half a page.
+const NUM_CLASSES: usize = 8;
+/// The largest size served from the size classes.
+const MAX_SMALL: usize = 1 << (MIN_CLASS_SHIFT + NUM_CLASSES - 1);
+/// The number of pages mapped at once for the size classes.
+const CHUNK_PAGES: usize = 16;
+
+/// A futex-based lock.
+///
+/// = Remarks
+///
+/// The state is `LOCKED` while the lock is held without contention and `WAITING` if
+/// other threads might be sleeping on the futex.
+struct RawLock {
+    val: AtomicInt,
+}
+
+impl RawLock {
+    const fn new() -> RawLock {
+        RawLock { val: AtomicInt::new(UNLOCKED) }
+    }
+
+    fn lock(&self) -> RawLockGuard<'_> {
+        let acquired = self.val.compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire,
+                                                 Ordering::Relaxed);
+        if acquired.is_err() {
+            while self.val.swap(WAITING, Ordering::Acquire) != UNLOCKED {
+                futex_wait(&self.val, WAITING, None);
+            }
+        }
+        RawLockGuard { lock: self }
+    }
+}
+
+struct RawLockGuard<'a> {
+    lock: &'a RawLock,
+}
+
+impl<'a> Drop for RawLockGuard<'a> {
+    fn drop(&mut self) {
+        if self.lock.val.swap(UNLOCKED, Ordering::Release) == WAITING {
+            futex_wake(&self.lock.val, 1);
+        }
+    }
+}
+
+/// A free object of a size class.
+struct Free {
+    next: *mut Free,
+}
+
+struct State {
+    /// The free lists of the size classes.
+    free: [*mut Free; NUM_CLASSES],
+    /// The next unused page of the current chunk.
+    cur: usize,
+    /// The end of the current chunk.
+    end: usize,
+}
+
+/// An allocator that gets its memory directly from the kernel.
+///
+/// = Remarks
+///
+/// Allocations of up to half a page are served from size classes whose sizes are
+/// powers of two. Freed objects are kept on a free list per size class and never
+/// returned to the kernel. Larger allocations get their own anonymous mapping that
+/// is unmapped when the allocation is freed and resized with `mremap`.
+///
+/// All state is protected by a single futex-based lock. The allocator does not use
+/// thread-local storage and can be used in `no_std` programs.
+///
+/// = Examples
+///
+/// ----
+/// #[global_allocator]
+/// static ALLOC: MmapAlloc = MmapAlloc::new();
+/// ----
+///
+/// = See also
+///
+/// * link:man:mmap(2)
+/// * link:man:mremap(2)
+pub struct MmapAlloc {
+    lock: RawLock,
+    state: UnsafeCell<State>,
+}
+
+unsafe impl Sync for MmapAlloc { }
+
+/// Returns the size class of a small allocation.
+fn class(layout: &Layout) -> Option<usize> {
+    let size = cmp::max(cmp::max(layout.size(), layout.align()), 1 << MIN_CLASS_SHIFT);
+    if size > MAX_SMALL {
+        return None;
+    }
+    let shift = size.next_power_of_two().trailing_zeros() as usize;
+    Some(shift - MIN_CLASS_SHIFT)
+}
+
+/// Returns the size of the objects of a size class.
+fn class_size(class: usize) -> usize {
+    1 << (class + MIN_CLASS_SHIFT)
+}
+
+/// Rounds a size up to a multiple of the page size.
+fn page_round(size: usize) -> Option<usize> {
+    size.checked_add(PAGE_SIZE - 1).map(|s| s & !(PAGE_SIZE - 1))
+}
+
+/// Maps anonymous memory.
+///
+/// Returns null if the mapping fails.
+unsafe fn map(len: usize) -> *mut u8 {
+    let addr = mmap(0, len, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    match addr < 0 {
+        true => ptr::null_mut(),
+        false => addr as *mut u8,
+    }
+}
+
+/// Maps anonymous memory aligned to `align`.
+///
+/// Returns null if the mapping fails.
+unsafe fn map_aligned(len: usize, align: usize) -> *mut u8 {
+    if align <= PAGE_SIZE {
+        return map(len);
+    }
+    let total = match len.checked_add(align - PAGE_SIZE) {
+        Some(total) => total,
+        _ => return ptr::null_mut(),
+    };
+    let base = map(total);
+    if base.is_null() {
+        return base;
+    }
+    let base = base as usize;
+    let start = (base + align - 1) & !(align - 1);
+    if start > base {
+        munmap(base, start - base);
+    }
+    let end = start + len;
+    if base + total > end {
+        munmap(end, base + total - end);
+    }
+    start as *mut u8
+}
+
+/// Splits a fresh page into objects of a size class.
+///
+/// Must be called with the lock held.
+unsafe fn refill(state: &mut State, class: usize) -> bool {
+    if state.cur == state.end {
+        let chunk = map(CHUNK_PAGES * PAGE_SIZE);
+        if chunk.is_null() {
+            return false;
+        }
+        state.cur = chunk as usize;
+        state.end = state.cur + CHUNK_PAGES * PAGE_SIZE;
+    }
+    let page = state.cur;
+    state.cur += PAGE_SIZE;
+    let size = class_size(class);
+    let mut obj = page + PAGE_SIZE;
+    while obj > page {
+        obj -= size;
+        let free = obj as *mut Free;
+        (*free).next = state.free[class];
+        state.free[class] = free;
+    }
+    true
+}
+
+impl MmapAlloc {
+    /// Creates a new allocator.
+    pub const fn new() -> MmapAlloc {
+        MmapAlloc {
+            lock: RawLock::new(),
+            state: UnsafeCell::new(State {
+                free: [ptr::null_mut(); NUM_CLASSES],
+                cur: 0,
+                end: 0,
+            }),
+        }
+    }
+
+    unsafe fn alloc_small(&self, class: usize) -> *mut u8 {
+        let _guard = self.lock.lock();
+        let state = &mut *self.state.get();
+        if state.free[class].is_null() && !refill(state, class) {
+            return ptr::null_mut();
+        }
+        let free = state.free[class];
+        state.free[class] = (*free).next;
+        free as *mut u8
+    }
+
+    unsafe fn dealloc_small(&self, ptr: *mut u8, class: usize) {
+        let _guard = self.lock.lock();
+        let state = &mut *self.state.get();
+        let free = ptr as *mut Free;
+        (*free).next = state.free[class];
+        state.free[class] = free;
+    }
+
+    unsafe fn alloc_large(&self, layout: &Layout) -> *mut u8 {
+        match page_round(layout.size()) {
+            Some(len) => map_aligned(len, layout.align()),
+            _ => ptr::null_mut(),
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for MmapAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        match class(&layout) {
+            Some(class) => self.alloc_small(class),
+            _ => self.alloc_large(&layout),
+        }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        match class(&layout) {
+            Some(class) => {
+                let ptr = self.alloc_small(class);
+                if !ptr.is_null() {
+                    ptr::write_bytes(ptr, 0, layout.size());
+                }
+                ptr
+            },
+            // Fresh anonymous mappings are already zeroed.
+            _ => self.alloc_large(&layout),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        match class(&layout) {
+            Some(class) => self.dealloc_small(ptr, class),
+            _ => {
+                munmap(ptr as usize, page_round(layout.size()).unwrap());
+            },
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let (old_class, new_class) = (class(&layout), class(&new_layout));
+        if old_class.is_some() && old_class == new_class {
+            return ptr;
+        }
+        if old_class.is_none() && new_class.is_none() {
+            let old_len = page_round(layout.size()).unwrap();
+            let new_len = match page_round(new_size) {
+                Some(len) => len,
+                _ => return ptr::null_mut(),
+            };
+            if old_len == new_len {
+                return ptr;
+            }
+            // A moved mapping is only page-aligned.
+            let flags = match layout.align() <= PAGE_SIZE {
+                true => MREMAP_MAYMOVE,
+                false => 0,
+            };
+            let addr = mremap(ptr as usize, old_len, new_len, flags, 0);
+            if addr >= 0 {
+                return addr as *mut u8;
+            }
+        }
+        let new = self.alloc(new_layout);
+        if !new.is_null() {
+            ptr::copy_nonoverlapping(ptr, new, cmp::min(layout.size(), new_size));
+            self.dealloc(ptr, layout);
+        }
+        new
+    }
+}
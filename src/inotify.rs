@@ -0,0 +1,412 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use core::ops::{BitOr, Not, BitAnd};
+use core::fmt::{self, Debug, Formatter};
+use memchr::{memchr};
+use crate::{
+    result::{Result},
+    lmem,
+    fd::{FdContainer},
+    string::{CStr},
+    util::{
+        retry::{retry},
+        kstr::{ToKStr},
+        data::{d8, DataSlice},
+    },
+};
+use crate::kty::{
+    c_int, inotify_event, IN_NONBLOCK, IN_ACCESS, IN_MODIFY, IN_ATTRIB, IN_CLOSE_WRITE,
+    IN_CLOSE_NOWRITE, IN_OPEN, IN_MOVED_FROM, IN_MOVED_TO, IN_CREATE, IN_DELETE,
+    IN_DELETE_SELF, IN_MOVE_SELF, IN_UNMOUNT, IN_Q_OVERFLOW, IN_IGNORED, IN_ONLYDIR,
+    IN_DONT_FOLLOW, IN_EXCL_UNLINK, IN_MASK_ADD, IN_ISDIR, IN_ONESHOT, IN_CLOSE, IN_MOVE,
+    IN_ALL_EVENTS, NAME_MAX,
+};
+use crate::syscall::{inotify_init1, inotify_add_watch, inotify_rm_watch, read, close};
+
+#[cfg(feature = "alloc")]
+pub use self::tree::{TreeWatcher, TreeEvent};
+
+#[cfg(feature = "alloc")]
+mod tree;
+
+/// The minimum size of a buffer that can hold any single event.
+pub const EVENT_BUF_SIZE: usize = mem::size_of::<inotify_event>() + NAME_MAX + 1;
+
+/// Events and options of an inotify watch.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct WatchMask(pub u32);
+
+impl WatchMask {
+    /// Returns whether all flags of another set are set in this set.
+    ///
+    /// [argument, other]
+    /// The flags to check.
+    pub fn is_set(self, other: WatchMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for WatchMask {
+    type Output = WatchMask;
+    fn bitor(self, other: WatchMask) -> WatchMask {
+        WatchMask(self.0 | other.0)
+    }
+}
+
+impl BitAnd for WatchMask {
+    type Output = WatchMask;
+    fn bitand(self, other: WatchMask) -> WatchMask {
+        WatchMask(self.0 & other.0)
+    }
+}
+
+impl Not for WatchMask {
+    type Output = WatchMask;
+    fn not(self) -> WatchMask {
+        WatchMask(!self.0)
+    }
+}
+
+/// Dummy flag with all flags unset.
+pub const WATCH_NONE: WatchMask = WatchMask(0);
+
+macro_rules! create_flags {
+    ($($(#[$meta:meta])* flag $name:ident = $val:expr;)*) => {
+        $($(#[$meta])* pub const $name: WatchMask = WatchMask($val);)*
+
+        impl Debug for WatchMask {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let mut first = true;
+                $(
+                    if self.0 & $val != 0 {
+                        if !first { core::write!(f, "|")?; }
+                        first = false;
+                        core::write!(f, stringify!($name))?;
+                    }
+                )*
+                if first {
+                    core::write!(f, "WATCH_NONE")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+create_flags! {
+    #[doc = "The file was read.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_ACCESS therein"]
+    flag WATCH_ACCESS = IN_ACCESS;
+
+    #[doc = "The file was modified.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_MODIFY therein"]
+    flag WATCH_MODIFY = IN_MODIFY;
+
+    #[doc = "The metadata of the file changed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_ATTRIB therein"]
+    flag WATCH_ATTRIB = IN_ATTRIB;
+
+    #[doc = "A file opened for writing was closed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_CLOSE_WRITE therein"]
+    flag WATCH_CLOSE_WRITE = IN_CLOSE_WRITE;
+
+    #[doc = "A file not opened for writing was closed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_CLOSE_NOWRITE therein"]
+    flag WATCH_CLOSE_NO_WRITE = IN_CLOSE_NOWRITE;
+
+    #[doc = "The file was opened.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_OPEN therein"]
+    flag WATCH_OPEN = IN_OPEN;
+
+    #[doc = "A file was moved out of the watched directory.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_MOVED_FROM therein"]
+    flag WATCH_MOVED_FROM = IN_MOVED_FROM;
+
+    #[doc = "A file was moved into the watched directory.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_MOVED_TO therein"]
+    flag WATCH_MOVED_TO = IN_MOVED_TO;
+
+    #[doc = "A file was created in the watched directory.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_CREATE therein"]
+    flag WATCH_CREATE = IN_CREATE;
+
+    #[doc = "A file was deleted from the watched directory.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_DELETE therein"]
+    flag WATCH_DELETE = IN_DELETE;
+
+    #[doc = "The watched file was deleted.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_DELETE_SELF therein"]
+    flag WATCH_DELETE_SELF = IN_DELETE_SELF;
+
+    #[doc = "The watched file was moved.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_MOVE_SELF therein"]
+    flag WATCH_MOVE_SELF = IN_MOVE_SELF;
+
+    #[doc = "The file system containing the watched file was unmounted.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "This is always reported and does not have to be requested.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_UNMOUNT therein"]
+    flag WATCH_UNMOUNT = IN_UNMOUNT;
+
+    #[doc = "The event queue overflowed and events were lost.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "This is always reported and does not have to be requested. The watch \
+             descriptor of such an event is `-1`.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_Q_OVERFLOW therein"]
+    flag WATCH_QUEUE_OVERFLOW = IN_Q_OVERFLOW;
+
+    #[doc = "The watch was removed.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "This is always reported and does not have to be requested. It is the last \
+             event of the watch and the watch descriptor can be reused afterwards.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_IGNORED therein"]
+    flag WATCH_IGNORED = IN_IGNORED;
+
+    #[doc = "Only watch the path if it is a directory.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_ONLYDIR therein"]
+    flag WATCH_ONLY_DIR = IN_ONLYDIR;
+
+    #[doc = "Don't follow the path if it is a symbolic link.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_DONT_FOLLOW therein"]
+    flag WATCH_DONT_FOLLOW = IN_DONT_FOLLOW;
+
+    #[doc = "Don't report events for files after they have been unlinked from the \
+             watched directory.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_EXCL_UNLINK therein"]
+    flag WATCH_EXCL_UNLINK = IN_EXCL_UNLINK;
+
+    #[doc = "Add the events to an existing watch instead of replacing them.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_MASK_ADD therein"]
+    flag WATCH_MASK_ADD = IN_MASK_ADD;
+
+    #[doc = "The subject of the event is a directory.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_ISDIR therein"]
+    flag WATCH_IS_DIR = IN_ISDIR;
+
+    #[doc = "Remove the watch after one event has been reported.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:inotify(7) and IN_ONESHOT therein"]
+    flag WATCH_ONE_SHOT = IN_ONESHOT;
+}
+
+/// A file opened for writing or not opened for writing was closed.
+pub const WATCH_CLOSE: WatchMask = WatchMask(IN_CLOSE);
+
+/// A file was moved out of or into the watched directory.
+pub const WATCH_MOVE: WatchMask = WatchMask(IN_MOVE);
+
+/// All events that can be requested.
+pub const WATCH_ALL_EVENTS: WatchMask = WatchMask(IN_ALL_EVENTS);
+
+/// A watch descriptor.
+///
+/// [field, 1]
+/// The raw watch descriptor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WatchDescriptor(pub c_int);
+
+/// An inotify instance.
+///
+/// = See also
+///
+/// * link:man:inotify(7)
+pub struct Inotify {
+    fd: c_int,
+    owned: bool,
+}
+
+impl Inotify {
+    /// Creates a new inotify instance.
+    ///
+    /// = See also
+    ///
+    /// * link:man:inotify_init1(2)
+    pub fn new() -> Result<Inotify> {
+        let fd = rv!(inotify_init1(0), -> c_int)?;
+        Ok(Inotify::from_owned(fd))
+    }
+
+    /// Creates a new non-blocking inotify instance.
+    ///
+    /// = See also
+    ///
+    /// * link:man:inotify_init1(2) and IN_NONBLOCK therein
+    pub fn new_non_blocking() -> Result<Inotify> {
+        let fd = rv!(inotify_init1(IN_NONBLOCK), -> c_int)?;
+        Ok(Inotify::from_owned(fd))
+    }
+
+    /// Adds or modifies a watch.
+    ///
+    /// [argument, path]
+    /// The path of the file or directory to watch.
+    ///
+    /// [argument, mask]
+    /// The events to watch and the options of the watch.
+    ///
+    /// [return_value]
+    /// Returns the watch descriptor of the watch.
+    ///
+    /// = Remarks
+    ///
+    /// If the file is already watched by this instance, the existing watch is modified
+    /// and its watch descriptor is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:inotify_add_watch(2)
+    pub fn add_watch<P: ToKStr>(&self, path: P, mask: WatchMask) -> Result<WatchDescriptor> {
+        let wd = path.with_kstr(|path| {
+            rv!(inotify_add_watch(self.fd, path, mask.0), -> c_int)
+        })?;
+        Ok(WatchDescriptor(wd))
+    }
+
+    /// Removes a watch.
+    ///
+    /// [argument, wd]
+    /// The watch descriptor of the watch.
+    ///
+    /// = Remarks
+    ///
+    /// A `WATCH_IGNORED` event is generated for the watch. If the watch has already
+    /// been removed, e.g., because the watched file was deleted, `EINVAL` is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:inotify_rm_watch(2)
+    pub fn remove_watch(&self, wd: WatchDescriptor) -> Result {
+        rv!(inotify_rm_watch(self.fd, wd.0))
+    }
+
+    /// Reads the next batch of events.
+    ///
+    /// [argument, buf]
+    /// The buffer in which the events will be stored.
+    ///
+    /// [return_value]
+    /// Returns an iterator over the events in the buffer.
+    ///
+    /// = Remarks
+    ///
+    /// The buffer should be at least `EVENT_BUF_SIZE` bytes long. If it is too small to
+    /// hold the next event, `EINVAL` is returned. If the instance is non-blocking and no
+    /// events are pending, `EAGAIN` is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:inotify(7)
+    pub fn read<'a>(&self, buf: &'a mut [d8]) -> Result<Events<'a>> {
+        let buf = buf.align_for_mut::<inotify_event>();
+        let len = retry(|| read(self.fd, buf))? as usize;
+        let buf = unsafe { buf[..len].as_bytes() };
+        Ok(Events { buf })
+    }
+}
+
+impl Drop for Inotify {
+    fn drop(&mut self) {
+        if self.owned {
+            close(self.fd);
+        }
+    }
+}
+
+impl From<Inotify> for c_int {
+    fn from(inotify: Inotify) -> Self {
+        let fd = inotify.fd;
+        mem::forget(inotify);
+        fd
+    }
+}
+
+impl FdContainer for Inotify {
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    fn borrow(&self) -> c_int {
+        self.fd
+    }
+
+    fn from_owned(fd: c_int) -> Inotify {
+        Inotify { fd, owned: true }
+    }
+
+    fn from_borrowed(fd: c_int) -> Inotify {
+        Inotify { fd, owned: false }
+    }
+}
+
+/// An iterator over a batch of inotify events.
+///
+/// = Remarks
+///
+/// Each item consists of the watch descriptor, the event mask, the cookie that
+/// connects related `WATCH_MOVED_FROM` and `WATCH_MOVED_TO` events, and the name of
+/// the file relative to the watched directory. The name is `None` if the event
+/// concerns the watched file or directory itself.
+///
+/// The watch descriptor of `WATCH_QUEUE_OVERFLOW` events is `-1`. After a
+/// `WATCH_IGNORED` event, the watch descriptor is no longer valid.
+pub struct Events<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Events<'a> {
+    /// Returns whether there are no more events in the batch.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = (WatchDescriptor, WatchMask, u32, Option<&'a CStr>);
+
+    fn next(&mut self) -> Option<(WatchDescriptor, WatchMask, u32, Option<&'a CStr>)> {
+        let buf = self.buf;
+        let ev: &inotify_event = lmem::from_bytes(buf)?;
+        let start = mem::size_of::<inotify_event>();
+        let end = start + ev.len as usize;
+        if end > buf.len() {
+            self.buf = &[];
+            return None;
+        }
+        let name = &buf[start..end];
+        let name = match (name.len(), memchr(0, name)) {
+            (0, _) | (_, Some(0)) => None,
+            // The kernel always terminates the name. Stop at malformed events instead
+            // of creating a `CStr` without a null byte.
+            (_, None) => {
+                self.buf = &[];
+                return None;
+            },
+            (_, Some(n)) => Some(unsafe { mem::transmute::<&[u8], &CStr>(&name[..n]) }),
+        };
+        self.buf = &buf[end..];
+        Some((WatchDescriptor(ev.wd), WatchMask(ev.mask), ev.cookie, name))
+    }
+}
@@ -0,0 +1,437 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use core::fmt::{self, Debug, Formatter};
+use alloc::{vec, vec::Vec, collections::{BTreeMap, VecDeque}};
+use crate::{
+    result::{Result},
+    dir::{Dir},
+    file::{FileType},
+    string::{ByteStr, CStr},
+    inotify::{
+        Inotify, WatchMask, WatchDescriptor, EVENT_BUF_SIZE, WATCH_CREATE, WATCH_DELETE,
+        WATCH_MODIFY, WATCH_ATTRIB, WATCH_MOVED_FROM, WATCH_MOVED_TO, WATCH_DELETE_SELF,
+        WATCH_ONLY_DIR, WATCH_DONT_FOLLOW, WATCH_QUEUE_OVERFLOW, WATCH_IGNORED,
+        WATCH_IS_DIR,
+    },
+    fd::{FdContainer},
+    util::{
+        data::{d8},
+        kstr::{ToKStr},
+        error::{self},
+    },
+};
+use crate::kty::{c_int};
+use crate::syscall::{ioctl_fionread};
+
+/// The events requested for every watched directory.
+const MASK: WatchMask = WatchMask(WATCH_CREATE.0 | WATCH_DELETE.0 | WATCH_MODIFY.0 |
+                                  WATCH_ATTRIB.0 | WATCH_MOVED_FROM.0 |
+                                  WATCH_MOVED_TO.0 | WATCH_DELETE_SELF.0 |
+                                  WATCH_ONLY_DIR.0 | WATCH_DONT_FOLLOW.0);
+
+/// The size of the buffer used to read directory entries.
+const DIR_BUF_SIZE: usize = 4096;
+
+/// A change in a watched directory tree.
+///
+/// = Remarks
+///
+/// All paths are relative to the root of the tree. The root itself has the empty path.
+#[derive(Clone, PartialEq, Eq)]
+pub enum TreeEvent {
+    /// A file or directory was created or moved into the tree.
+    ///
+    /// = Remarks
+    ///
+    /// When a directory is created, events are also generated for the entries found in
+    /// it when it is added to the watcher.
+    Created(Vec<u8>),
+    /// A file or directory was deleted or moved out of the tree.
+    ///
+    /// = Remarks
+    ///
+    /// When a directory is deleted, events are generated for its entries first.
+    Removed(Vec<u8>),
+    /// The contents or the metadata of a file or directory changed.
+    Modified(Vec<u8>),
+    /// A file or directory was renamed within the tree.
+    Renamed {
+        /// The old path.
+        from: Vec<u8>,
+        /// The new path.
+        to: Vec<u8>,
+    },
+    /// Events were lost and the tree has been scanned again.
+    ///
+    /// = Remarks
+    ///
+    /// The user has to assume that any file in the tree might have changed.
+    Rescan,
+}
+
+impl Debug for TreeEvent {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        fn s(path: &[u8]) -> &ByteStr {
+            path.as_ref()
+        }
+        match *self {
+            TreeEvent::Created(ref p) => f.debug_tuple("Created").field(&s(p)).finish(),
+            TreeEvent::Removed(ref p) => f.debug_tuple("Removed").field(&s(p)).finish(),
+            TreeEvent::Modified(ref p) => f.debug_tuple("Modified").field(&s(p)).finish(),
+            TreeEvent::Renamed { ref from, ref to } => {
+                f.debug_struct("Renamed")
+                    .field("from", &s(from))
+                    .field("to", &s(to))
+                    .finish()
+            },
+            TreeEvent::Rescan => f.write_str("Rescan"),
+        }
+    }
+}
+
+/// A pending `WATCH_MOVED_FROM` event.
+struct MovedFrom {
+    cookie: u32,
+    path: Vec<u8>,
+    is_dir: bool,
+}
+
+fn join(dir: &[u8], name: &[u8]) -> Vec<u8> {
+    let mut path = Vec::with_capacity(dir.len() + name.len() + 1);
+    path.extend_from_slice(dir);
+    if !dir.is_empty() {
+        path.push(b'/');
+    }
+    path.extend_from_slice(name);
+    path
+}
+
+/// Returns whether `path` is `dir` or lies below `dir`.
+fn is_below(path: &[u8], dir: &[u8]) -> bool {
+    path.starts_with(dir) && (path.len() == dir.len() || path[dir.len()] == b'/')
+}
+
+/// A recursive watcher of a directory tree.
+///
+/// = Remarks
+///
+/// The watcher adds an inotify watch for every directory in the tree and keeps them up
+/// to date as directories are created, renamed, and removed. Renames are reported as
+/// `Renamed` if both paths are in the tree. Otherwise they are reported as `Created` or
+/// `Removed`. If the event queue of the kernel overflows, the tree is scanned again
+/// and a `Rescan` event is reported.
+///
+/// Symbolic links are not followed. Changes that happen while a new directory is being
+/// scanned can be reported twice. If a new directory cannot be watched for a reason
+/// other than having been removed, e.g., because the limit on the number of watches has
+/// been reached, the tree is scanned again and a `Rescan` event is reported.
+///
+/// = Examples
+///
+/// ----
+/// let mut watcher = TreeWatcher::new("/srv/www")?;
+/// loop {
+///     match watcher.next_event()? {
+///         TreeEvent::Modified(path) => reload(&path),
+///         TreeEvent::Rescan => reload_all(),
+///         _ => { },
+///     }
+/// }
+/// ----
+///
+/// = See also
+///
+/// * link:man:inotify(7)
+/// * link:lrs::inotify::Inotify
+pub struct TreeWatcher {
+    inotify: Inotify,
+    root: Vec<u8>,
+    root_wd: WatchDescriptor,
+    dirs: BTreeMap<WatchDescriptor, Vec<u8>>,
+    events: VecDeque<TreeEvent>,
+    moved_from: Option<MovedFrom>,
+    buf: Vec<u8>,
+}
+
+impl TreeWatcher {
+    /// Creates a watcher for a directory tree.
+    ///
+    /// [argument, root]
+    /// The path of the root directory of the tree.
+    ///
+    /// = Remarks
+    ///
+    /// All directories in the tree are scanned before this function returns.
+    pub fn new<P: ToKStr>(root: P) -> Result<TreeWatcher> {
+        TreeWatcher::with_inotify(Inotify::new()?, root)
+    }
+
+    /// Creates a non-blocking watcher for a directory tree.
+    ///
+    /// [argument, root]
+    /// The path of the root directory of the tree.
+    ///
+    /// = Remarks
+    ///
+    /// `next_event` returns `EAGAIN` if no events are available. The watcher can be
+    /// polled via `inotify`.
+    pub fn new_non_blocking<P: ToKStr>(root: P) -> Result<TreeWatcher> {
+        TreeWatcher::with_inotify(Inotify::new_non_blocking()?, root)
+    }
+
+    fn with_inotify<P: ToKStr>(inotify: Inotify, root: P) -> Result<TreeWatcher> {
+        let (root, root_wd) = root.with_kstr(|root| {
+            let cstr = unsafe { CStr::from_ptr(root.as_ptr()) };
+            let bytes: &[u8] = cstr.as_ref();
+            Ok((bytes.to_vec(), inotify.add_watch(root, MASK)?))
+        })?;
+        let mut watcher = TreeWatcher {
+            inotify,
+            root,
+            root_wd,
+            dirs: BTreeMap::new(),
+            events: VecDeque::new(),
+            moved_from: None,
+            buf: vec!(0; EVENT_BUF_SIZE * 16),
+        };
+        watcher.dirs.insert(root_wd, Vec::new());
+        watcher.scan(Vec::new(), false);
+        Ok(watcher)
+    }
+
+    /// Returns the path of the root directory.
+    pub fn root(&self) -> &[u8] {
+        &self.root
+    }
+
+    /// Returns the underlying inotify instance.
+    pub fn inotify(&self) -> &Inotify {
+        &self.inotify
+    }
+
+    /// Returns the next event.
+    ///
+    /// = Remarks
+    ///
+    /// This blocks until an event is available unless the watcher is non-blocking.
+    pub fn next_event(&mut self) -> Result<TreeEvent> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(event);
+            }
+            self.read()?;
+        }
+    }
+
+    fn full_path(&self, path: &[u8]) -> Vec<u8> {
+        match path.is_empty() {
+            true => self.root.clone(),
+            false => join(&self.root, path),
+        }
+    }
+
+    /// Watches all directories below `path` and optionally reports their entries as
+    /// created.
+    ///
+    /// `path` itself must already be watched. Directories that cannot be watched or
+    /// opened, e.g., because they have already been removed again, are skipped.
+    fn scan(&mut self, path: Vec<u8>, report: bool) {
+        let mut buf = vec!(0; DIR_BUF_SIZE);
+        let mut stack = vec!(path);
+        while let Some(path) = stack.pop() {
+            let dir = match Dir::open(&self.full_path(&path)[..]) {
+                Ok(dir) => dir,
+                _ => continue,
+            };
+            let mut iter = dir.iter(d8::from_byte_slice_mut(&mut buf));
+            while let Some(Ok(entry)) = iter.next_entry() {
+                let name = entry.name.bytes_with_null();
+                let name = &name[..name.len() - 1];
+                if name == b"." || name == b".." {
+                    continue;
+                }
+                let child = join(&path, name);
+                if report {
+                    self.events.push_back(TreeEvent::Created(child.clone()));
+                }
+                let is_dir = match entry.file_type {
+                    FileType::Directory => true,
+                    FileType::Unknown => match dir.entry_metadata(name) {
+                        Ok(md) => md.file_type() == FileType::Directory,
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                if is_dir {
+                    if let Ok(wd) = self.inotify.add_watch(&self.full_path(&child)[..], MASK) {
+                        self.dirs.insert(wd, child.clone());
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes the watches of `path` and all directories below it.
+    fn unwatch(&mut self, path: &[u8]) {
+        let wds: Vec<_> = self.dirs.iter()
+            .filter(|&(_, p)| is_below(p, path))
+            .map(|(&wd, _)| wd)
+            .collect();
+        for wd in wds {
+            let _ = self.inotify.remove_watch(wd);
+            self.dirs.remove(&wd);
+        }
+    }
+
+    fn rescan(&mut self) -> Result {
+        if let Some(from) = self.moved_from.take() {
+            self.unwatch(&from.path);
+        }
+        let old = mem::take(&mut self.dirs);
+        self.root_wd = self.inotify.add_watch(&self.root[..], MASK)?;
+        self.dirs.insert(self.root_wd, Vec::new());
+        self.scan(Vec::new(), false);
+        for (wd, _) in old {
+            if !self.dirs.contains_key(&wd) {
+                let _ = self.inotify.remove_watch(wd);
+            }
+        }
+        self.events.push_back(TreeEvent::Rescan);
+        Ok(())
+    }
+
+    /// Reports a pending `WATCH_MOVED_FROM` event without a matching `WATCH_MOVED_TO`
+    /// event as removed.
+    fn flush_moved_from(&mut self) {
+        if let Some(from) = self.moved_from.take() {
+            if from.is_dir {
+                self.unwatch(&from.path);
+            }
+            self.events.push_back(TreeEvent::Removed(from.path));
+        }
+    }
+
+    fn read(&mut self) -> Result {
+        let mut buf = mem::take(&mut self.buf);
+        let res = self.read_into(&mut buf);
+        self.buf = buf;
+        res
+    }
+
+    fn read_into(&mut self, buf: &mut [u8]) -> Result {
+        let events = self.inotify.read(d8::from_byte_slice_mut(buf))?;
+        for (wd, mask, cookie, name) in events {
+            if mask.is_set(WATCH_QUEUE_OVERFLOW) {
+                self.rescan()?;
+                continue;
+            }
+            let dir = match self.dirs.get(&wd) {
+                Some(dir) => dir,
+                _ => continue,
+            };
+            let path = name.map(|name| {
+                let name = name.bytes_with_null();
+                join(dir, &name[..name.len() - 1])
+            });
+            let is_dir = mask.is_set(WATCH_IS_DIR);
+
+            if self.moved_from.is_some() && !mask.is_set(WATCH_MOVED_TO) {
+                self.flush_moved_from();
+            }
+
+            if mask.is_set(WATCH_IGNORED) {
+                self.dirs.remove(&wd);
+            } else if mask.is_set(WATCH_DELETE_SELF) {
+                if wd == self.root_wd {
+                    self.events.push_back(TreeEvent::Removed(Vec::new()));
+                }
+            } else if let Some(path) = path {
+                if mask.is_set(WATCH_CREATE) {
+                    self.events.push_back(TreeEvent::Created(path.clone()));
+                    if is_dir {
+                        self.watch_new(path)?;
+                    }
+                } else if mask.is_set(WATCH_DELETE) {
+                    self.events.push_back(TreeEvent::Removed(path));
+                } else if mask.is_set(WATCH_MODIFY) || mask.is_set(WATCH_ATTRIB) {
+                    self.events.push_back(TreeEvent::Modified(path));
+                } else if mask.is_set(WATCH_MOVED_FROM) {
+                    self.moved_from = Some(MovedFrom { cookie, path, is_dir });
+                } else if mask.is_set(WATCH_MOVED_TO) {
+                    self.moved_to(cookie, path, is_dir)?;
+                }
+            }
+        }
+
+        // The matching `WATCH_MOVED_TO` event is usually queued together with the
+        // `WATCH_MOVED_FROM` event. If nothing is queued, the file left the tree.
+        if self.moved_from.is_some() {
+            let mut unread = 0;
+            if ioctl_fionread(self.inotify.borrow(), &mut unread) < 0 || unread == 0 {
+                self.flush_moved_from();
+            }
+        }
+        Ok(())
+    }
+
+    fn watch_new(&mut self, path: Vec<u8>) -> Result {
+        match self.inotify.add_watch(&self.full_path(&path)[..], MASK) {
+            Ok(wd) => {
+                self.dirs.insert(wd, path.clone());
+                self.scan(path, true);
+            },
+            // The directory has already been removed or renamed. Renames within the
+            // tree are handled when the `WATCH_MOVED_TO` event is read.
+            Err(error::DoesNotExist) => { },
+            Err(_) => self.rescan()?,
+        }
+        Ok(())
+    }
+
+    fn moved_to(&mut self, cookie: u32, path: Vec<u8>, is_dir: bool) -> Result {
+        let from = match self.moved_from.take() {
+            Some(from) if from.cookie == cookie => from,
+            from => {
+                self.moved_from = from;
+                self.flush_moved_from();
+                self.events.push_back(TreeEvent::Created(path.clone()));
+                if is_dir {
+                    self.watch_new(path)?;
+                }
+                return Ok(());
+            },
+        };
+        if is_dir {
+            for dir in self.dirs.values_mut() {
+                if is_below(dir, &from.path) {
+                    let mut new = path.clone();
+                    new.extend_from_slice(&dir[from.path.len()..]);
+                    *dir = new;
+                }
+            }
+            // The directory has not been watched if it was renamed before its
+            // `WATCH_CREATE` event was read. Directories created in it in the meantime
+            // are not watched either.
+            if let Ok(wd) = self.inotify.add_watch(&self.full_path(&path)[..], MASK) {
+                self.dirs.insert(wd, path.clone());
+                self.scan(path.clone(), false);
+            }
+        }
+        self.events.push_back(TreeEvent::Renamed { from: from.path, to: path });
+        Ok(())
+    }
+}
+
+impl Debug for TreeWatcher {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let root: &ByteStr = self.root[..].as_ref();
+        f.debug_struct("TreeWatcher")
+            .field("root", &root)
+            .field("dirs", &self.dirs.len())
+            .finish()
+    }
+}
@@ -314,6 +314,20 @@ pub struct linux_dirent64 {
     pub d_name:   [c_char; 0],
 }
 
+/////////////////////
+// include/linux/fs.h
+/////////////////////
+
+pub const DT_UNKNOWN : c_uchar = 0;
+pub const DT_FIFO    : c_uchar = 1;
+pub const DT_CHR     : c_uchar = 2;
+pub const DT_DIR     : c_uchar = 4;
+pub const DT_BLK     : c_uchar = 6;
+pub const DT_REG     : c_uchar = 8;
+pub const DT_LNK     : c_uchar = 10;
+pub const DT_SOCK    : c_uchar = 12;
+pub const DT_WHT     : c_uchar = 14;
+
 ///////////////////////////////
 // include/uapi/linux/sysinfo.h
 ///////////////////////////////
@@ -352,6 +366,7 @@ pub const F_SEAL_SEAL         : c_uint = 0x0001;
 pub const F_SEAL_SHRINK       : c_uint = 0x0002;
 pub const F_SEAL_GROW         : c_uint = 0x0004;
 pub const F_SEAL_WRITE        : c_uint = 0x0008;
+pub const F_SEAL_FUTURE_WRITE : c_uint = 0x0010;
 pub const DN_ACCESS           : c_int = 0x00000001;
 pub const DN_MODIFY           : c_int = 0x00000002;
 pub const DN_CREATE           : c_int = 0x00000004;
@@ -2750,6 +2765,83 @@ pub const ST_NOATIME     : c_ulong = 0x0400;
 pub const ST_NODIRATIME  : c_ulong = 0x0800;
 pub const ST_RELATIME    : c_ulong = 0x1000;
 
+// magic.h
+
+pub const ADFS_SUPER_MAGIC      : c_ulong = 0xadf5;
+pub const AFFS_SUPER_MAGIC      : c_ulong = 0xadff;
+pub const AFS_SUPER_MAGIC       : c_ulong = 0x5346414F;
+pub const AUTOFS_SUPER_MAGIC    : c_ulong = 0x0187;
+pub const CODA_SUPER_MAGIC      : c_ulong = 0x73757245;
+pub const CRAMFS_MAGIC          : c_ulong = 0x28cd3d45;
+pub const CRAMFS_MAGIC_WEND     : c_ulong = 0x453dcd28;
+pub const DEBUGFS_MAGIC         : c_ulong = 0x64626720;
+pub const SECURITYFS_MAGIC      : c_ulong = 0x73636673;
+pub const SELINUX_MAGIC         : c_ulong = 0xf97cff8c;
+pub const SMACK_MAGIC           : c_ulong = 0x43415d53;
+pub const RAMFS_MAGIC           : c_ulong = 0x858458f6;
+pub const TMPFS_MAGIC           : c_ulong = 0x01021994;
+pub const HUGETLBFS_MAGIC       : c_ulong = 0x958458f6;
+pub const SQUASHFS_MAGIC        : c_ulong = 0x73717368;
+pub const ECRYPTFS_SUPER_MAGIC  : c_ulong = 0xf15f;
+pub const EFS_SUPER_MAGIC       : c_ulong = 0x414A53;
+pub const EROFS_SUPER_MAGIC_V1  : c_ulong = 0xE0F5E1E2;
+pub const EXT2_SUPER_MAGIC      : c_ulong = 0xEF53;
+pub const EXT3_SUPER_MAGIC      : c_ulong = 0xEF53;
+pub const XENFS_SUPER_MAGIC     : c_ulong = 0xabba1974;
+pub const EXT4_SUPER_MAGIC      : c_ulong = 0xEF53;
+pub const BTRFS_SUPER_MAGIC     : c_ulong = 0x9123683E;
+pub const NILFS_SUPER_MAGIC     : c_ulong = 0x3434;
+pub const F2FS_SUPER_MAGIC      : c_ulong = 0xF2F52010;
+pub const HPFS_SUPER_MAGIC      : c_ulong = 0xf995e849;
+pub const ISOFS_SUPER_MAGIC     : c_ulong = 0x9660;
+pub const JFFS2_SUPER_MAGIC     : c_ulong = 0x72b6;
+pub const XFS_SUPER_MAGIC       : c_ulong = 0x58465342;
+pub const PSTOREFS_MAGIC        : c_ulong = 0x6165676C;
+pub const EFIVARFS_MAGIC        : c_ulong = 0xde5e81e4;
+pub const HOSTFS_SUPER_MAGIC    : c_ulong = 0x00c0ffee;
+pub const OVERLAYFS_SUPER_MAGIC : c_ulong = 0x794c7630;
+pub const MINIX_SUPER_MAGIC     : c_ulong = 0x137F;
+pub const MINIX_SUPER_MAGIC2    : c_ulong = 0x138F;
+pub const MINIX2_SUPER_MAGIC    : c_ulong = 0x2468;
+pub const MINIX2_SUPER_MAGIC2   : c_ulong = 0x2478;
+pub const MINIX3_SUPER_MAGIC    : c_ulong = 0x4d5a;
+pub const MSDOS_SUPER_MAGIC     : c_ulong = 0x4d44;
+pub const NCP_SUPER_MAGIC       : c_ulong = 0x564c;
+pub const NFS_SUPER_MAGIC       : c_ulong = 0x6969;
+pub const OCFS2_SUPER_MAGIC     : c_ulong = 0x7461636f;
+pub const OPENPROM_SUPER_MAGIC  : c_ulong = 0x9fa1;
+pub const QNX4_SUPER_MAGIC      : c_ulong = 0x002f;
+pub const QNX6_SUPER_MAGIC      : c_ulong = 0x68191122;
+pub const AFS_FS_MAGIC          : c_ulong = 0x6B414653;
+pub const REISERFS_SUPER_MAGIC  : c_ulong = 0x52654973;
+pub const SMB_SUPER_MAGIC       : c_ulong = 0x517B;
+pub const CGROUP_SUPER_MAGIC    : c_ulong = 0x27e0eb;
+pub const CGROUP2_SUPER_MAGIC   : c_ulong = 0x63677270;
+pub const RDTGROUP_SUPER_MAGIC  : c_ulong = 0x7655821;
+pub const STACK_END_MAGIC       : c_ulong = 0x57AC6E9D;
+pub const TRACEFS_MAGIC         : c_ulong = 0x74726163;
+pub const V9FS_MAGIC            : c_ulong = 0x01021997;
+pub const BDEVFS_MAGIC          : c_ulong = 0x62646576;
+pub const DAXFS_MAGIC           : c_ulong = 0x64646178;
+pub const BINFMTFS_MAGIC        : c_ulong = 0x42494e4d;
+pub const DEVPTS_SUPER_MAGIC    : c_ulong = 0x1cd1;
+pub const BINDERFS_SUPER_MAGIC  : c_ulong = 0x6c6f6f70;
+pub const FUTEXFS_SUPER_MAGIC   : c_ulong = 0xBAD1DEA;
+pub const PIPEFS_MAGIC          : c_ulong = 0x50495045;
+pub const PROC_SUPER_MAGIC      : c_ulong = 0x9fa0;
+pub const SOCKFS_MAGIC          : c_ulong = 0x534F434B;
+pub const SYSFS_MAGIC           : c_ulong = 0x62656572;
+pub const USBDEVICE_SUPER_MAGIC : c_ulong = 0x9fa2;
+pub const MTD_INODE_FS_MAGIC    : c_ulong = 0x11307854;
+pub const ANON_INODE_FS_MAGIC   : c_ulong = 0x09041934;
+pub const BTRFS_TEST_MAGIC      : c_ulong = 0x73727279;
+pub const NSFS_MAGIC            : c_ulong = 0x6e736673;
+pub const BPF_FS_MAGIC          : c_ulong = 0xcafe4a11;
+pub const AAFS_MAGIC            : c_ulong = 0x5a3c69f0;
+pub const UDF_SUPER_MAGIC       : c_ulong = 0x15013346;
+pub const BALLOON_KVM_MAGIC     : c_ulong = 0x13661366;
+pub const ZSMALLOC_MAGIC        : c_ulong = 0x58295829;
+
 // timerfd.h
 
 pub const TFD_TIMER_ABSTIME       : c_int = 1 << 0;
@@ -2787,6 +2879,19 @@ pub const EPOLL_CLOEXEC : c_int = O_CLOEXEC;
 pub const EPOLL_CTL_ADD : c_int = 1;
 pub const EPOLL_CTL_DEL : c_int = 2;
 pub const EPOLL_CTL_MOD : c_int = 3;
+pub const EPOLLIN        : c_uint = 0x00000001;
+pub const EPOLLPRI       : c_uint = 0x00000002;
+pub const EPOLLOUT       : c_uint = 0x00000004;
+pub const EPOLLERR       : c_uint = 0x00000008;
+pub const EPOLLHUP       : c_uint = 0x00000010;
+pub const EPOLLNVAL      : c_uint = 0x00000020;
+pub const EPOLLRDNORM    : c_uint = 0x00000040;
+pub const EPOLLRDBAND    : c_uint = 0x00000080;
+pub const EPOLLWRNORM    : c_uint = 0x00000100;
+pub const EPOLLWRBAND    : c_uint = 0x00000200;
+pub const EPOLLMSG       : c_uint = 0x00000400;
+pub const EPOLLRDHUP     : c_uint = 0x00002000;
+pub const EPOLLEXCLUSIVE : c_uint = 1 << 28;
 pub const EPOLLWAKEUP   : c_uint = 1 << 29;
 pub const EPOLLONESHOT  : c_uint = 1 << 30;
 pub const EPOLLET       : c_uint = 1 << 31;
@@ -3434,6 +3539,64 @@ pub const TCP_FASTOPEN             : c_int = 23;
 pub const TCP_TIMESTAMP            : c_int = 24;
 pub const TCP_NOTSENT_LOWAT        : c_int = 25;
 
+#[repr(C)]
+#[derive(Pod, Copy, Clone, Eq, PartialEq)]
+pub struct tcp_info {
+    pub tcpi_state:          __u8,
+    pub tcpi_ca_state:       __u8,
+    pub tcpi_retransmits:    __u8,
+    pub tcpi_probes:         __u8,
+    pub tcpi_backoff:        __u8,
+    pub tcpi_options:        __u8,
+    // tcpi_snd_wscale : 4, tcpi_rcv_wscale : 4
+    pub tcpi_wscale:         __u8,
+
+    pub tcpi_rto:            __u32,
+    pub tcpi_ato:            __u32,
+    pub tcpi_snd_mss:        __u32,
+    pub tcpi_rcv_mss:        __u32,
+
+    pub tcpi_unacked:        __u32,
+    pub tcpi_sacked:         __u32,
+    pub tcpi_lost:           __u32,
+    pub tcpi_retrans:        __u32,
+    pub tcpi_fackets:        __u32,
+
+    pub tcpi_last_data_sent: __u32,
+    pub tcpi_last_ack_sent:  __u32,
+    pub tcpi_last_data_recv: __u32,
+    pub tcpi_last_ack_recv:  __u32,
+
+    pub tcpi_pmtu:           __u32,
+    pub tcpi_rcv_ssthresh:   __u32,
+    pub tcpi_rtt:            __u32,
+    pub tcpi_rttvar:         __u32,
+    pub tcpi_snd_ssthresh:   __u32,
+    pub tcpi_snd_cwnd:       __u32,
+    pub tcpi_advmss:         __u32,
+    pub tcpi_reordering:     __u32,
+
+    pub tcpi_rcv_rtt:        __u32,
+    pub tcpi_rcv_space:      __u32,
+
+    pub tcpi_total_retrans:  __u32,
+
+    pub tcpi_pacing_rate:     __u64,
+    pub tcpi_max_pacing_rate: __u64,
+}
+
+pub const TCP_ESTABLISHED : __u8 = 1;
+pub const TCP_SYN_SENT    : __u8 = 2;
+pub const TCP_SYN_RECV    : __u8 = 3;
+pub const TCP_FIN_WAIT1   : __u8 = 4;
+pub const TCP_FIN_WAIT2   : __u8 = 5;
+pub const TCP_TIME_WAIT   : __u8 = 6;
+pub const TCP_CLOSE       : __u8 = 7;
+pub const TCP_CLOSE_WAIT  : __u8 = 8;
+pub const TCP_LAST_ACK    : __u8 = 9;
+pub const TCP_LISTEN      : __u8 = 10;
+pub const TCP_CLOSING     : __u8 = 11;
+
 ///////////////////////////
 // include/uapi/linux/udp.h
 ///////////////////////////
@@ -5057,6 +5220,67 @@ pub const IN_ALL_EVENTS : u32 = IN_ACCESS | IN_MODIFY | IN_ATTRIB | IN_CLOSE_WRI
 pub const IN_CLOEXEC  : c_int = O_CLOEXEC;
 pub const IN_NONBLOCK : c_int = O_NONBLOCK;
 
+////////////////////////////////
+// include/uapi/linux/fanotify.h
+////////////////////////////////
+
+pub const FAN_ACCESS         : u64 = 0x00000001;
+pub const FAN_MODIFY         : u64 = 0x00000002;
+pub const FAN_CLOSE_WRITE    : u64 = 0x00000008;
+pub const FAN_CLOSE_NOWRITE  : u64 = 0x00000010;
+pub const FAN_OPEN           : u64 = 0x00000020;
+pub const FAN_OPEN_EXEC      : u64 = 0x00001000;
+pub const FAN_Q_OVERFLOW     : u64 = 0x00004000;
+pub const FAN_OPEN_PERM      : u64 = 0x00010000;
+pub const FAN_ACCESS_PERM    : u64 = 0x00020000;
+pub const FAN_OPEN_EXEC_PERM : u64 = 0x00040000;
+pub const FAN_EVENT_ON_CHILD : u64 = 0x08000000;
+pub const FAN_ONDIR          : u64 = 0x40000000;
+pub const FAN_CLOSE          : u64 = FAN_CLOSE_WRITE | FAN_CLOSE_NOWRITE;
+
+pub const FAN_CLOEXEC           : c_uint = 0x00000001;
+pub const FAN_NONBLOCK          : c_uint = 0x00000002;
+pub const FAN_CLASS_NOTIF       : c_uint = 0x00000000;
+pub const FAN_CLASS_CONTENT     : c_uint = 0x00000004;
+pub const FAN_CLASS_PRE_CONTENT : c_uint = 0x00000008;
+pub const FAN_UNLIMITED_QUEUE   : c_uint = 0x00000010;
+pub const FAN_UNLIMITED_MARKS   : c_uint = 0x00000020;
+
+pub const FAN_MARK_ADD                 : c_uint = 0x00000001;
+pub const FAN_MARK_REMOVE              : c_uint = 0x00000002;
+pub const FAN_MARK_DONT_FOLLOW         : c_uint = 0x00000004;
+pub const FAN_MARK_ONLYDIR             : c_uint = 0x00000008;
+pub const FAN_MARK_MOUNT               : c_uint = 0x00000010;
+pub const FAN_MARK_IGNORED_MASK        : c_uint = 0x00000020;
+pub const FAN_MARK_IGNORED_SURV_MODIFY : c_uint = 0x00000040;
+pub const FAN_MARK_FLUSH               : c_uint = 0x00000080;
+pub const FAN_MARK_FILESYSTEM          : c_uint = 0x00000100;
+
+pub const FANOTIFY_METADATA_VERSION : u8 = 3;
+
+#[repr(C)]
+#[derive(Pod, Copy, Clone, Eq, PartialEq)]
+pub struct fanotify_event_metadata {
+    pub event_len:    __u32,
+    pub vers:         __u8,
+    pub reserved:     __u8,
+    pub metadata_len: __u16,
+    pub mask:         __u64,
+    pub fd:           __s32,
+    pub pid:          __s32,
+}
+
+#[repr(C)]
+#[derive(Pod, Copy, Clone, Eq, PartialEq)]
+pub struct fanotify_response {
+    pub fd:       __s32,
+    pub response: __u32,
+}
+
+pub const FAN_ALLOW : __u32 = 0x01;
+pub const FAN_DENY  : __u32 = 0x02;
+pub const FAN_NOFD  : c_int = -1;
+
 //////////////////////////
 // include/linux/eventfd.h
 //////////////////////////
@@ -5091,6 +5315,7 @@ pub const LINUX_REBOOT_CMD_KEXEC      : c_uint = 0x45584543;
 
 pub const MFD_CLOEXEC       : c_uint = 0x0001;
 pub const MFD_ALLOW_SEALING : c_uint = 0x0002;
+pub const MFD_HUGETLB       : c_uint = 0x0004;
 
 ///////////////////////////
 // include/uapi/linux/tty.h
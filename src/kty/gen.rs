@@ -363,6 +363,7 @@ pub const MADV_RANDOM       : kty::c_int = 1;
 pub const MADV_SEQUENTIAL   : kty::c_int = 2;
 pub const MADV_WILLNEED     : kty::c_int = 3;
 pub const MADV_DONTNEED     : kty::c_int = 4;
+pub const MADV_FREE         : kty::c_int = 8;
 pub const MADV_REMOVE       : kty::c_int = 9;
 pub const MADV_DONTFORK     : kty::c_int = 10;
 pub const MADV_DOFORK       : kty::c_int = 11;
@@ -374,10 +375,16 @@ pub const MADV_HUGEPAGE     : kty::c_int = 14;
 pub const MADV_NOHUGEPAGE   : kty::c_int = 15;
 pub const MADV_DONTDUMP     : kty::c_int = 16;
 pub const MADV_DODUMP       : kty::c_int = 17;
+pub const MADV_WIPEONFORK   : kty::c_int = 18;
+pub const MADV_KEEPONFORK   : kty::c_int = 19;
+pub const MADV_COLD         : kty::c_int = 20;
+pub const MADV_PAGEOUT      : kty::c_int = 21;
 pub const MAP_FILE          : kty::c_int = 0;
 pub const MAP_HUGE_SHIFT    : kty::c_int = 26;
 pub const MAP_HUGE_MASK     : kty::c_int = 0x3f;
 
+pub const MAP_FIXED_NOREPLACE : kty::c_int = 0x100000;
+
 //////////////////////////////////
 // include/uapi/asm-generic/mman.h
 //////////////////////////////////
@@ -888,6 +895,8 @@ pub const SIGPOLL   : kty::c_int = kty::SIGIO;
 pub const SIGPWR    : kty::c_int = 30;
 pub const SIGSYS    : kty::c_int = 31;
 pub const SIGUNUSED : kty::c_int = 31;
+pub const SIGRTMIN  : kty::c_int = 32;
+pub const SIGRTMAX  : kty::c_int = kty::_NSIG as kty::c_int;
 
 pub const SA_NOCLDSTOP : kty::c_int = 0x00000001;
 pub const SA_NOCLDWAIT : kty::c_int = 0x00000002;
@@ -194,10 +194,12 @@ pub use kty::gen::{
 pub use kty::gen::{
     PROT_READ, PROT_WRITE, PROT_EXEC, PROT_SEM, PROT_NONE, PROT_GROWSDOWN, PROT_GROWSUP,
     MAP_SHARED, MAP_PRIVATE, MAP_TYPE, MAP_FIXED, MAP_ANONYMOUS, MAP_UNINITIALIZED,
+    MAP_FIXED_NOREPLACE,
     MS_ASYNC, MS_INVALIDATE, MS_SYNC, MADV_NORMAL, MADV_RANDOM, MADV_SEQUENTIAL,
     MADV_WILLNEED, MADV_DONTNEED, MADV_REMOVE, MADV_DONTFORK, MADV_DOFORK, MADV_HWPOISON,
     MADV_SOFT_OFFLINE, MADV_MERGEABLE, MADV_UNMERGEABLE, MADV_HUGEPAGE, MADV_NOHUGEPAGE,
-    MADV_DONTDUMP, MADV_DODUMP, MAP_FILE, MAP_HUGE_SHIFT, MAP_HUGE_MASK,
+    MADV_DONTDUMP, MADV_DODUMP, MADV_FREE, MADV_WIPEONFORK, MADV_KEEPONFORK, MADV_COLD,
+    MADV_PAGEOUT, MAP_FILE, MAP_HUGE_SHIFT, MAP_HUGE_MASK,
 };
 
 ///////////////////////////////////
@@ -405,6 +407,8 @@ pub const SIGPOLL   : c_int = SIGIO;
 pub const SIGPWR    : c_int = 30;
 pub const SIGSYS    : c_int = 31;
 pub const SIGUNUSED : c_int = 31;
+pub const SIGRTMIN  : c_int = 32;
+pub const SIGRTMAX  : c_int = _NSIG as c_int;
 
 pub const SA_NOCLDSTOP : c_int = 0x00000001;
 pub const SA_NOCLDWAIT : c_int = 0x00000002;
@@ -1,6 +1,6 @@
 #![allow(unused)]
 #![cfg_attr(not(feature="std"), no_std)]
-#![feature(asm, structural_match, read_initializer)]
+#![feature(asm, global_asm, structural_match, read_initializer)]
 
 #[macro_use]
 #[allow(unused_imports)]
@@ -15,6 +15,22 @@ pub mod util;
 pub mod kty;
 pub mod syscall;
 pub mod fd;
+pub mod file;
+pub mod dir;
+pub mod fs;
+pub mod poll;
+pub mod inotify;
+pub mod fanotify;
+pub mod eventfd;
+pub mod memfd;
+pub mod mmap;
+pub mod heap;
+pub mod pipe;
+pub mod signal;
+#[cfg(feature = "alloc")]
+pub mod event;
+pub mod process;
+pub mod socket;
 //pub mod lock;
 pub mod result;
 pub mod lmem;
@@ -0,0 +1,319 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::ops::{BitOr, Not, BitAnd, Deref};
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    fd::{FdContainer},
+    file::{File},
+    mmap::{Mapping, MapOptions, PROTECT_READ},
+    util::{
+        kstr::{ToKStr},
+        io::{Write},
+        error::{self},
+    },
+};
+use crate::kty::{
+    c_int, c_uint, MFD_ALLOW_SEALING, MFD_HUGETLB, F_SEAL_SEAL, F_SEAL_SHRINK, F_SEAL_GROW,
+    F_SEAL_WRITE, F_SEAL_FUTURE_WRITE,
+};
+use crate::syscall::{memfd_create, fcntl_add_seals, fcntl_get_seals};
+
+/// Seals of a memfd.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct Seals(pub c_uint);
+
+impl Seals {
+    /// Returns whether all flags of another set are set in this set.
+    ///
+    /// [argument, other]
+    /// The flags to check.
+    pub fn is_set(self, other: Seals) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Seals {
+    type Output = Seals;
+    fn bitor(self, other: Seals) -> Seals {
+        Seals(self.0 | other.0)
+    }
+}
+
+impl BitAnd for Seals {
+    type Output = Seals;
+    fn bitand(self, other: Seals) -> Seals {
+        Seals(self.0 & other.0)
+    }
+}
+
+impl Not for Seals {
+    type Output = Seals;
+    fn not(self) -> Seals {
+        Seals(!self.0)
+    }
+}
+
+/// Dummy flag with all flags unset.
+pub const SEAL_NONE: Seals = Seals(0);
+
+macro_rules! create_flags {
+    ($($(#[$meta:meta])* flag $name:ident = $val:expr;)*) => {
+        $($(#[$meta])* pub const $name: Seals = Seals($val);)*
+
+        impl Debug for Seals {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let mut first = true;
+                $(
+                    if self.0 & $val != 0 {
+                        if !first { core::write!(f, "|")?; }
+                        first = false;
+                        core::write!(f, stringify!($name))?;
+                    }
+                )*
+                if first {
+                    core::write!(f, "SEAL_NONE")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+create_flags! {
+    #[doc = "No further seals can be added.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fcntl(2) and F_SEAL_SEAL therein"]
+    flag SEAL_SEAL = F_SEAL_SEAL;
+
+    #[doc = "The file cannot be shrunk.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fcntl(2) and F_SEAL_SHRINK therein"]
+    flag SEAL_SHRINK = F_SEAL_SHRINK;
+
+    #[doc = "The file cannot be grown.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fcntl(2) and F_SEAL_GROW therein"]
+    flag SEAL_GROW = F_SEAL_GROW;
+
+    #[doc = "The contents of the file cannot be modified.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "This seal cannot be added while writable shared mappings of the file exist.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fcntl(2) and F_SEAL_WRITE therein"]
+    flag SEAL_WRITE = F_SEAL_WRITE;
+
+    #[doc = "The contents of the file cannot be modified via new writes or mappings.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "Writable shared mappings that exist when the seal is added can still be used\n"]
+    #[doc = "to modify the file.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:fcntl(2) and F_SEAL_FUTURE_WRITE therein"]
+    flag SEAL_FUTURE_WRITE = F_SEAL_FUTURE_WRITE;
+}
+
+/// The seals that make the contents of a memfd immutable.
+pub const SEAL_IMMUTABLE: Seals = Seals(F_SEAL_SHRINK | F_SEAL_GROW | F_SEAL_WRITE);
+
+/// An anonymous file backed by memory.
+///
+/// = Remarks
+///
+/// The file can be read and written via `file`. It can be shared with other processes,
+/// e.g., by sending it over a Unix domain socket.
+///
+/// = See also
+///
+/// * link:man:memfd_create(2)
+pub struct Memfd {
+    file: File,
+}
+
+impl Memfd {
+    /// Creates a new memfd.
+    ///
+    /// [argument, name]
+    /// The name of the memfd.
+    ///
+    /// [argument, allow_sealing]
+    /// Whether seals can be added to the memfd.
+    ///
+    /// [argument, hugetlb]
+    /// Whether the memfd is backed by huge pages.
+    ///
+    /// = Remarks
+    ///
+    /// The name is only used for debugging and shows up in `/proc/self/fd`. If
+    /// `allow_sealing` is not set, the memfd is sealed with `SEAL_SEAL`.
+    ///
+    /// = See also
+    ///
+    /// * link:man:memfd_create(2)
+    pub fn create<P: ToKStr>(name: P, allow_sealing: bool,
+                             hugetlb: bool) -> Result<Memfd> {
+        let mut flags = 0;
+        if allow_sealing {
+            flags |= MFD_ALLOW_SEALING;
+        }
+        if hugetlb {
+            flags |= MFD_HUGETLB;
+        }
+        let fd = name.with_kstr(|name| rv!(memfd_create(name, flags), -> c_int))?;
+        Ok(Memfd::from_owned(fd))
+    }
+
+    /// Adds seals to the memfd.
+    ///
+    /// [argument, seals]
+    /// The seals to add.
+    ///
+    /// = Remarks
+    ///
+    /// If the memfd is sealed with `SEAL_SEAL`, `EPERM` is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fcntl(2) and F_ADD_SEALS therein
+    pub fn seal(&self, seals: Seals) -> Result {
+        rv!(fcntl_add_seals(self.file.borrow(), seals.0))
+    }
+
+    /// Returns the seals of the memfd.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fcntl(2) and F_GET_SEALS therein
+    pub fn seals(&self) -> Result<Seals> {
+        let seals = rv!(fcntl_get_seals(self.file.borrow()), -> c_uint)?;
+        Ok(Seals(seals))
+    }
+
+    /// Returns the file of the memfd.
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+}
+
+impl From<Memfd> for c_int {
+    fn from(memfd: Memfd) -> Self {
+        memfd.file.into()
+    }
+}
+
+impl FdContainer for Memfd {
+    fn is_owned(&self) -> bool {
+        self.file.is_owned()
+    }
+
+    fn borrow(&self) -> c_int {
+        self.file.borrow()
+    }
+
+    fn from_owned(fd: c_int) -> Memfd {
+        Memfd { file: File::from_owned(fd) }
+    }
+
+    fn from_borrowed(fd: c_int) -> Memfd {
+        Memfd { file: File::from_borrowed(fd) }
+    }
+}
+
+/// A memfd with immutable contents and a read-only mapping of them.
+///
+/// = Remarks
+///
+/// The memfd is sealed with at least `SEAL_IMMUTABLE`. Neither this process nor any
+/// other process can change the contents seen through the mapping.
+///
+/// = Examples
+///
+/// The sender creates the memfd and sends it to the receiver:
+///
+/// ----
+/// let sealed = SealedReadOnly::new("config", &data)?;
+/// send_fd(&socket, sealed.memfd().borrow())?;
+/// ----
+///
+/// The receiver verifies the seals before using the contents:
+///
+/// ----
+/// let sealed = SealedReadOnly::from_memfd(Memfd::from_owned(fd))?;
+/// parse(&sealed[..]);
+/// ----
+pub struct SealedReadOnly {
+    memfd: Memfd,
+    map: Option<Mapping>,
+}
+
+impl SealedReadOnly {
+    /// Creates a sealed memfd containing data.
+    ///
+    /// [argument, name]
+    /// The name of the memfd.
+    ///
+    /// [argument, data]
+    /// The contents of the memfd.
+    ///
+    /// = Remarks
+    ///
+    /// The memfd is sealed with `SEAL_IMMUTABLE` and `SEAL_SEAL`.
+    pub fn new<P: ToKStr>(name: P, data: &[u8]) -> Result<SealedReadOnly> {
+        let memfd = Memfd::create(name, true, false)?;
+        let mut file = memfd.file();
+        file.write_all(data)?;
+        memfd.seal(SEAL_IMMUTABLE | SEAL_SEAL)?;
+        SealedReadOnly::from_memfd(memfd)
+    }
+
+    /// Verifies the seals of a memfd and maps it.
+    ///
+    /// [argument, memfd]
+    /// The memfd.
+    ///
+    /// = Remarks
+    ///
+    /// If the memfd is not sealed with `SEAL_IMMUTABLE`, `EPERM` is returned.
+    pub fn from_memfd(memfd: Memfd) -> Result<SealedReadOnly> {
+        if !memfd.seals()?.is_set(SEAL_IMMUTABLE) {
+            return Err(error::NotPermitted);
+        }
+        let len = memfd.file.metadata()?.size() as usize;
+        let map = match len {
+            0 => None,
+            _ => {
+                let mut opts = MapOptions::new();
+                opts.protection(PROTECT_READ).shared(true);
+                // The seals guarantee that the contents never change.
+                Some(unsafe { opts.file(&memfd.file, 0, len)? })
+            },
+        };
+        Ok(SealedReadOnly { memfd, map })
+    }
+
+    /// Returns the memfd.
+    pub fn memfd(&self) -> &Memfd {
+        &self.memfd
+    }
+}
+
+impl Deref for SealedReadOnly {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self.map {
+            Some(ref map) => map,
+            _ => &[],
+        }
+    }
+}
+
+impl Debug for SealedReadOnly {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("SealedReadOnly")
+            .field("fd", &self.memfd.borrow())
+            .field("len", &self.len())
+            .finish()
+    }
+}
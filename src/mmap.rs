@@ -0,0 +1,635 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{slice, cmp};
+use core::ops::{BitOr, Not, BitAnd, Deref, DerefMut};
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    lmem,
+    fd::{FdContainer},
+    util::{
+        data::{d8, DataSlice},
+        error::{self},
+    },
+};
+use crate::kty::{
+    c_int, new_utsname, PAGE_SIZE, PROT_READ, PROT_WRITE, PROT_EXEC, MAP_SHARED, MAP_PRIVATE,
+    MAP_ANONYMOUS, MAP_FIXED_NOREPLACE, MAP_POPULATE, MAP_HUGETLB, MS_ASYNC, MS_SYNC,
+    MS_INVALIDATE, MREMAP_MAYMOVE, MADV_NORMAL, MADV_RANDOM, MADV_SEQUENTIAL, MADV_WILLNEED,
+    MADV_DONTNEED, MADV_FREE, MADV_REMOVE, MADV_DONTFORK, MADV_DOFORK, MADV_MERGEABLE,
+    MADV_UNMERGEABLE, MADV_HUGEPAGE, MADV_NOHUGEPAGE, MADV_DONTDUMP, MADV_DODUMP,
+    MADV_WIPEONFORK, MADV_KEEPONFORK, MADV_COLD, MADV_PAGEOUT,
+};
+use crate::syscall::{
+    mmap, munmap, mremap, mprotect, msync, mlock, munlock, mincore, madvise, uname,
+};
+
+/// The protection of a memory mapping.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct Protection(pub c_int);
+
+impl Protection {
+    /// Returns whether all flags of another set are set in this set.
+    ///
+    /// [argument, other]
+    /// The flags to check.
+    pub fn is_set(self, other: Protection) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Protection {
+    type Output = Protection;
+    fn bitor(self, other: Protection) -> Protection {
+        Protection(self.0 | other.0)
+    }
+}
+
+impl BitAnd for Protection {
+    type Output = Protection;
+    fn bitand(self, other: Protection) -> Protection {
+        Protection(self.0 & other.0)
+    }
+}
+
+impl Not for Protection {
+    type Output = Protection;
+    fn not(self) -> Protection {
+        Protection(!self.0)
+    }
+}
+
+/// Dummy flag with all flags unset.
+///
+/// = Remarks
+///
+/// The memory cannot be accessed at all.
+pub const PROTECT_NONE: Protection = Protection(0);
+
+macro_rules! create_flags {
+    ($($(#[$meta:meta])* flag $name:ident = $val:expr;)*) => {
+        $($(#[$meta])* pub const $name: Protection = Protection($val);)*
+
+        impl Debug for Protection {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let mut first = true;
+                $(
+                    if self.0 & $val != 0 {
+                        if !first { core::write!(f, "|")?; }
+                        first = false;
+                        core::write!(f, stringify!($name))?;
+                    }
+                )*
+                if first {
+                    core::write!(f, "PROTECT_NONE")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+create_flags! {
+    #[doc = "The memory can be read.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:mmap(2) and PROT_READ therein"]
+    flag PROTECT_READ = PROT_READ;
+
+    #[doc = "The memory can be written.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:mmap(2) and PROT_WRITE therein"]
+    flag PROTECT_WRITE = PROT_WRITE;
+
+    #[doc = "The memory can be executed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:mmap(2) and PROT_EXEC therein"]
+    flag PROTECT_EXEC = PROT_EXEC;
+}
+
+/// Advice about the usage of memory.
+///
+/// = See also
+///
+/// * link:man:madvise(2)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Advice {
+    /// No special treatment.
+    Normal,
+    /// The memory is accessed in random order.
+    Random,
+    /// The memory is accessed in sequential order.
+    Sequential,
+    /// The memory will be accessed soon.
+    WillNeed,
+    /// The memory will not be accessed soon.
+    ///
+    /// = Remarks
+    ///
+    /// The pages are freed. Afterwards, private anonymous memory reads as zeros and
+    /// other memory is reloaded from the underlying file.
+    DontNeed,
+    /// The pages can be freed lazily.
+    ///
+    /// = Remarks
+    ///
+    /// Only valid for private anonymous memory. Until the memory is written again, it
+    /// can read as zeros at any time.
+    ///
+    /// == Kernel versions
+    ///
+    /// The required kernel version is 4.5.
+    Free,
+    /// The pages and the backing store are freed.
+    ///
+    /// = Remarks
+    ///
+    /// Only valid for shared memory. Afterwards, the memory reads as zeros.
+    Remove,
+    /// The memory is not available in child processes.
+    DontFork,
+    /// Undoes `DontFork`.
+    DoFork,
+    /// Identical pages can be merged by the kernel.
+    Mergeable,
+    /// Undoes `Mergeable`.
+    Unmergeable,
+    /// Transparent huge pages are used.
+    HugePage,
+    /// Transparent huge pages are not used.
+    NoHugePage,
+    /// The memory is excluded from core dumps.
+    DontDump,
+    /// Undoes `DontDump`.
+    DoDump,
+    /// The memory reads as zeros in child processes.
+    ///
+    /// = Remarks
+    ///
+    /// Only valid for private anonymous memory.
+    ///
+    /// == Kernel versions
+    ///
+    /// The required kernel version is 4.14.
+    WipeOnFork,
+    /// Undoes `WipeOnFork`.
+    ///
+    /// == Kernel versions
+    ///
+    /// The required kernel version is 4.14.
+    KeepOnFork,
+    /// The pages are deactivated and reclaimed first under memory pressure.
+    ///
+    /// == Kernel versions
+    ///
+    /// The required kernel version is 5.4.
+    Cold,
+    /// The pages are reclaimed immediately.
+    ///
+    /// == Kernel versions
+    ///
+    /// The required kernel version is 5.4.
+    PageOut,
+}
+
+impl Advice {
+    fn to_raw(self) -> c_int {
+        match self {
+            Advice::Normal      => MADV_NORMAL,
+            Advice::Random      => MADV_RANDOM,
+            Advice::Sequential  => MADV_SEQUENTIAL,
+            Advice::WillNeed    => MADV_WILLNEED,
+            Advice::DontNeed    => MADV_DONTNEED,
+            Advice::Free        => MADV_FREE,
+            Advice::Remove      => MADV_REMOVE,
+            Advice::DontFork    => MADV_DONTFORK,
+            Advice::DoFork      => MADV_DOFORK,
+            Advice::Mergeable   => MADV_MERGEABLE,
+            Advice::Unmergeable => MADV_UNMERGEABLE,
+            Advice::HugePage    => MADV_HUGEPAGE,
+            Advice::NoHugePage  => MADV_NOHUGEPAGE,
+            Advice::DontDump    => MADV_DONTDUMP,
+            Advice::DoDump      => MADV_DODUMP,
+            Advice::WipeOnFork  => MADV_WIPEONFORK,
+            Advice::KeepOnFork  => MADV_KEEPONFORK,
+            Advice::Cold        => MADV_COLD,
+            Advice::PageOut     => MADV_PAGEOUT,
+        }
+    }
+
+    /// Returns the first kernel version that knows the advice.
+    fn min_kernel(self) -> (u32, u32) {
+        match self {
+            Advice::Free => (4, 5),
+            Advice::WipeOnFork | Advice::KeepOnFork => (4, 14),
+            Advice::Cold | Advice::PageOut => (5, 4),
+            _ => (0, 0),
+        }
+    }
+}
+
+/// Returns the major and minor version of the running kernel.
+fn kernel_version() -> Option<(u32, u32)> {
+    let mut name: new_utsname = lmem::zeroed();
+    if uname(&mut name) < 0 {
+        return None;
+    }
+    let mut parts = [0u32; 2];
+    let mut part = 0;
+    for &c in name.release.iter() {
+        match c as u8 {
+            c @ b'0'..=b'9' => {
+                parts[part] = parts[part].checked_mul(10)?.checked_add((c - b'0') as u32)?;
+            },
+            b'.' if part == 0 => part = 1,
+            _ => break,
+        }
+    }
+    Some((parts[0], parts[1]))
+}
+
+/// Advises the kernel about the usage of a range of memory.
+///
+/// [argument, addr]
+/// The page-aligned start of the range.
+///
+/// [argument, len]
+/// The length of the range.
+///
+/// [argument, advice]
+/// The advice.
+///
+/// = Remarks
+///
+/// If `addr` is not page-aligned, `EINVAL` is returned. If the kernel rejects the
+/// advice and the running kernel is older than the first version that supports it,
+/// `ENOSYS` is returned. Otherwise `EINVAL` means that the advice cannot be applied to
+/// the memory or is disabled in the kernel configuration.
+///
+/// # Safety
+///
+/// Some advice, e.g., `Advice::DontNeed` and `Advice::Free`, changes the contents of
+/// the memory. The caller must ensure that no live references point into the range if
+/// such advice is given.
+///
+/// = See also
+///
+/// * link:man:madvise(2)
+pub unsafe fn advise(addr: usize, len: usize, advice: Advice) -> Result {
+    if addr % PAGE_SIZE != 0 {
+        return Err(error::InvalidArgument);
+    }
+    match rv!(madvise(addr, len, advice.to_raw())) {
+        Err(error::InvalidArgument) => match kernel_version() {
+            Some(version) if version < advice.min_kernel() => Err(error::NotImplemented),
+            _ => Err(error::InvalidArgument),
+        },
+        res => res,
+    }
+}
+
+/// A builder for memory mappings.
+///
+/// = Remarks
+///
+/// By default, mappings are private and can be read and written.
+///
+/// = Examples
+///
+/// ----
+/// let map = MapOptions::new().shared(true).populate(true).anonymous(1 << 20)?;
+/// ----
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MapOptions {
+    prot: Protection,
+    flags: c_int,
+    addr: usize,
+}
+
+impl MapOptions {
+    /// Creates a new set of options with the default values.
+    pub fn new() -> MapOptions {
+        MapOptions { prot: PROTECT_READ | PROTECT_WRITE, flags: 0, addr: 0 }
+    }
+
+    fn flag(&mut self, flag: c_int, val: bool) -> &mut MapOptions {
+        if val {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+        self
+    }
+
+    /// Sets the protection of the mapping.
+    pub fn protection(&mut self, prot: Protection) -> &mut MapOptions {
+        self.prot = prot;
+        self
+    }
+
+    /// Sets whether the mapping is shared.
+    ///
+    /// = Remarks
+    ///
+    /// Modifications of a shared mapping are visible to other processes that map the
+    /// same memory and are carried through to the file. Modifications of a private
+    /// mapping are copy-on-write.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mmap(2) and MAP_SHARED therein
+    pub fn shared(&mut self, val: bool) -> &mut MapOptions {
+        self.flag(MAP_SHARED, val)
+    }
+
+    /// Sets whether the pages of the mapping are faulted in immediately.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mmap(2) and MAP_POPULATE therein
+    pub fn populate(&mut self, val: bool) -> &mut MapOptions {
+        self.flag(MAP_POPULATE, val)
+    }
+
+    /// Sets whether the mapping uses huge pages.
+    ///
+    /// = Remarks
+    ///
+    /// The length of the mapping must be a multiple of the huge page size.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mmap(2) and MAP_HUGETLB therein
+    pub fn hugetlb(&mut self, val: bool) -> &mut MapOptions {
+        self.flag(MAP_HUGETLB, val)
+    }
+
+    /// Sets the address at which the mapping is placed.
+    ///
+    /// [argument, addr]
+    /// The page-aligned address or `None` to let the kernel choose the address.
+    ///
+    /// = Remarks
+    ///
+    /// Existing mappings are never replaced. If the range is already in use, `EEXIST`
+    /// is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mmap(2) and MAP_FIXED_NOREPLACE therein
+    pub fn fixed_noreplace(&mut self, addr: Option<usize>) -> &mut MapOptions {
+        self.addr = addr.unwrap_or(0);
+        self.flag(MAP_FIXED_NOREPLACE, addr.is_some())
+    }
+
+    fn map(&self, len: usize, flags: c_int, fd: c_int, off: u64) -> Result<Mapping> {
+        let flags = match self.flags & MAP_SHARED {
+            0 => self.flags | MAP_PRIVATE | flags,
+            _ => self.flags | flags,
+        };
+        let ptr = rv!(unsafe { mmap(self.addr, len, self.prot.0, flags, fd, off) },
+                      -> usize)?;
+        Ok(Mapping { ptr: ptr as *mut u8, len, prot: self.prot })
+    }
+
+    /// Creates an anonymous mapping.
+    ///
+    /// [argument, len]
+    /// The length of the mapping.
+    ///
+    /// = Remarks
+    ///
+    /// The memory is initialized with zeros.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mmap(2) and MAP_ANONYMOUS therein
+    pub fn anonymous(&self, len: usize) -> Result<Mapping> {
+        self.map(len, MAP_ANONYMOUS, -1, 0)
+    }
+
+    /// Maps a file.
+    ///
+    /// [argument, file]
+    /// The file to map.
+    ///
+    /// [argument, off]
+    /// The page-aligned offset in the file at which the mapping starts.
+    ///
+    /// [argument, len]
+    /// The length of the mapping.
+    ///
+    /// = Remarks
+    ///
+    /// Accessing pages beyond the end of the file raises `SIGBUS`.
+    ///
+    /// # Safety
+    ///
+    /// The contents of the mapping change if the file is modified, e.g., by another
+    /// process, while the mapping is borrowed. The caller must ensure that the file is
+    /// not modified or truncated while the mapping is alive.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mmap(2)
+    pub unsafe fn file<F: FdContainer>(&self, file: &F, off: u64,
+                                       len: usize) -> Result<Mapping> {
+        self.map(len, 0, file.borrow(), off)
+    }
+}
+
+/// An owned memory mapping.
+///
+/// = Remarks
+///
+/// The mapping is unmapped when it is dropped. It dereferences to the mapped memory.
+/// Dereferencing panics if the mapping cannot be read or, for mutable access, written.
+///
+/// = See also
+///
+/// * link:man:mmap(2)
+/// * link:lrs::mmap::MapOptions
+pub struct Mapping {
+    ptr: *mut u8,
+    len: usize,
+    prot: Protection,
+}
+
+impl Mapping {
+    /// Creates a private anonymous mapping that can be read and written.
+    ///
+    /// [argument, len]
+    /// The length of the mapping.
+    pub fn anonymous(len: usize) -> Result<Mapping> {
+        MapOptions::new().anonymous(len)
+    }
+
+    /// Returns the address of the mapping.
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    /// Returns the length of the mapping.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the mapping has length `0`.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the protection of the mapping.
+    pub fn protection(&self) -> Protection {
+        self.prot
+    }
+
+    /// Changes the protection of the mapping.
+    ///
+    /// [argument, prot]
+    /// The new protection.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mprotect(2)
+    pub fn protect(&mut self, prot: Protection) -> Result {
+        rv!(mprotect(self.ptr as usize, self.len, prot.0))?;
+        self.prot = prot;
+        Ok(())
+    }
+
+    /// Writes modifications of a shared file mapping back to the file.
+    ///
+    /// [argument, asynchronous]
+    /// Whether to only schedule the write instead of waiting for it to complete.
+    ///
+    /// [argument, invalidate]
+    /// Whether to invalidate other mappings of the file so that they see the new
+    /// contents.
+    ///
+    /// = See also
+    ///
+    /// * link:man:msync(2)
+    pub fn sync(&self, asynchronous: bool, invalidate: bool) -> Result {
+        let mut flags = match asynchronous {
+            true => MS_ASYNC,
+            false => MS_SYNC,
+        };
+        if invalidate {
+            flags |= MS_INVALIDATE;
+        }
+        rv!(msync(self.ptr as usize, self.len, flags))
+    }
+
+    /// Locks the pages of the mapping in memory.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mlock(2)
+    pub fn lock(&self) -> Result {
+        rv!(mlock(self.ptr as usize, self.len))
+    }
+
+    /// Unlocks the pages of the mapping.
+    ///
+    /// = See also
+    ///
+    /// * link:man:munlock(2)
+    pub fn unlock(&self) -> Result {
+        rv!(munlock(self.ptr as usize, self.len))
+    }
+
+    /// Changes the length of the mapping.
+    ///
+    /// [argument, len]
+    /// The new length.
+    ///
+    /// = Remarks
+    ///
+    /// The mapping is moved if it cannot be extended in place.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mremap(2) and MREMAP_MAYMOVE therein
+    pub fn resize(&mut self, len: usize) -> Result {
+        let ptr = unsafe { mremap(self.ptr as usize, self.len, len, MREMAP_MAYMOVE, 0) };
+        let ptr = rv!(ptr, -> usize)?;
+        self.ptr = ptr as *mut u8;
+        self.len = len;
+        Ok(())
+    }
+
+    /// Advises the kernel about the usage of the mapping.
+    ///
+    /// [argument, advice]
+    /// The advice.
+    ///
+    /// = Remarks
+    ///
+    /// Some advice, e.g., `Advice::DontNeed`, changes the contents of the mapping.
+    ///
+    /// = See also
+    ///
+    /// * link:man:madvise(2)
+    /// * link:lrs::mmap::advise
+    pub fn advise(&mut self, advice: Advice) -> Result {
+        unsafe { advise(self.ptr as usize, self.len, advice) }
+    }
+
+    /// Returns the number of pages of the mapping that are resident in memory.
+    ///
+    /// = See also
+    ///
+    /// * link:man:mincore(2)
+    pub fn resident_pages(&self) -> Result<usize> {
+        let mut buf = [d8::new(0); 128];
+        let pages = self.len.div_ceil(PAGE_SIZE);
+        let mut resident = 0;
+        let mut page = 0;
+        while page < pages {
+            let n = cmp::min(pages - page, buf.len());
+            let addr = self.ptr as usize + page * PAGE_SIZE;
+            rv!(mincore(addr, n * PAGE_SIZE, &mut buf[..n]))?;
+            let vec = unsafe { buf[..n].as_bytes() };
+            resident += vec.iter().filter(|&&b| b & 1 != 0).count();
+            page += n;
+        }
+        Ok(resident)
+    }
+}
+
+impl Deref for Mapping {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        assert!(self.prot.is_set(PROTECT_READ));
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl DerefMut for Mapping {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        assert!(self.prot.is_set(PROTECT_READ | PROTECT_WRITE));
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { munmap(self.ptr as usize, self.len); }
+    }
+}
+
+unsafe impl Send for Mapping { }
+unsafe impl Sync for Mapping { }
+
+impl Debug for Mapping {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Mapping")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .field("prot", &self.prot)
+            .finish()
+    }
+}
@@ -0,0 +1,445 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use core::ops::{BitOr, BitAnd, Not};
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    fd::{FdContainer},
+    util::{
+        retry::{retry},
+        data::{d8},
+        io::{Read, Write},
+    },
+};
+use crate::kty::{
+    c_int, c_uint, O_NONBLOCK, SPLICE_F_MOVE, SPLICE_F_NONBLOCK, SPLICE_F_MORE,
+    SPLICE_F_GIFT,
+};
+use crate::syscall::{
+    pipe2, close, read, readv, write, writev, splice, tee, vmsplice, fcntl_getpipe_sz,
+    fcntl_setpipe_sz,
+};
+
+/// Flags for moving data into and out of pipes.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct SpliceFlags(pub c_uint);
+
+impl BitOr for SpliceFlags {
+    type Output = SpliceFlags;
+    fn bitor(self, other: SpliceFlags) -> SpliceFlags {
+        SpliceFlags(self.0 | other.0)
+    }
+}
+
+impl BitAnd for SpliceFlags {
+    type Output = SpliceFlags;
+    fn bitand(self, other: SpliceFlags) -> SpliceFlags {
+        SpliceFlags(self.0 & other.0)
+    }
+}
+
+impl Not for SpliceFlags {
+    type Output = SpliceFlags;
+    fn not(self) -> SpliceFlags {
+        SpliceFlags(!self.0)
+    }
+}
+
+/// Dummy flag with all flags unset.
+pub const SPLICE_NONE: SpliceFlags = SpliceFlags(0);
+
+macro_rules! create_flags {
+    ($($(#[$meta:meta])* flag $name:ident = $val:expr;)*) => {
+        $($(#[$meta])* pub const $name: SpliceFlags = SpliceFlags($val);)*
+
+        impl Debug for SpliceFlags {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let mut first = true;
+                $(
+                    if self.0 & $val != 0 {
+                        if !first { core::write!(f, "|")?; }
+                        first = false;
+                        core::write!(f, stringify!($name))?;
+                    }
+                )*
+                if first {
+                    core::write!(f, "SPLICE_NONE")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+create_flags! {
+    #[doc = "Move pages instead of copying them if possible.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "Current kernels ignore this flag.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:splice(2) and SPLICE_F_MOVE therein"]
+    flag SPLICE_MOVE = SPLICE_F_MOVE;
+
+    #[doc = "Don't block on the pipe.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "The other file descriptor might still block unless it is non-blocking.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:splice(2) and SPLICE_F_NONBLOCK therein"]
+    flag SPLICE_NON_BLOCK = SPLICE_F_NONBLOCK;
+
+    #[doc = "More data will be sent in a subsequent call.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:splice(2) and SPLICE_F_MORE therein"]
+    flag SPLICE_MORE = SPLICE_F_MORE;
+
+    #[doc = "The user pages are gifted to the kernel.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "Only used by `vmsplice`. The buffers must be page-aligned and must not be \
+             modified afterwards.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:vmsplice(2) and SPLICE_F_GIFT therein"]
+    flag SPLICE_GIFT = SPLICE_F_GIFT;
+}
+
+/// Creates a new pipe.
+///
+/// [return_value]
+/// Returns the read end and the write end of the pipe.
+///
+/// = See also
+///
+/// * link:man:pipe2(2)
+pub fn pipe() -> Result<(PipeReader, PipeWriter)> {
+    pipe_with_flags(0)
+}
+
+/// Creates a new non-blocking pipe.
+///
+/// [return_value]
+/// Returns the read end and the write end of the pipe.
+///
+/// = See also
+///
+/// * link:man:pipe2(2) and O_NONBLOCK therein
+pub fn pipe_non_blocking() -> Result<(PipeReader, PipeWriter)> {
+    pipe_with_flags(O_NONBLOCK)
+}
+
+fn pipe_with_flags(flags: c_int) -> Result<(PipeReader, PipeWriter)> {
+    let mut fds = [0; 2];
+    rv!(pipe2(&mut fds, flags))?;
+    Ok((PipeReader::from_owned(fds[0]), PipeWriter::from_owned(fds[1])))
+}
+
+fn capacity(fd: c_int) -> Result<usize> {
+    rv!(fcntl_getpipe_sz(fd), -> usize)
+}
+
+fn set_capacity(fd: c_int, size: usize) -> Result<usize> {
+    let size = if size > c_int::max_value() as usize {
+        c_int::max_value()
+    } else {
+        size as c_int
+    };
+    rv!(fcntl_setpipe_sz(fd, size), -> usize)
+}
+
+/// The read end of a pipe.
+pub struct PipeReader {
+    fd: c_int,
+    owned: bool,
+}
+
+impl PipeReader {
+    /// Returns the capacity of the pipe.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fcntl(2) and F_GETPIPE_SZ therein
+    pub fn capacity(&self) -> Result<usize> {
+        capacity(self.fd)
+    }
+
+    /// Sets the capacity of the pipe.
+    ///
+    /// [argument, size]
+    /// The minimum new capacity.
+    ///
+    /// [return_value]
+    /// Returns the new capacity.
+    ///
+    /// = Remarks
+    ///
+    /// The kernel rounds the capacity up to a power-of-two multiple of the page size.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fcntl(2) and F_SETPIPE_SZ therein
+    pub fn set_capacity(&self, size: usize) -> Result<usize> {
+        set_capacity(self.fd, size)
+    }
+
+    /// Moves data from the pipe to a file descriptor.
+    ///
+    /// [argument, dst]
+    /// The destination.
+    ///
+    /// [argument, off]
+    /// The offset in the destination or `None` to use and update the file position.
+    ///
+    /// [argument, len]
+    /// The maximum number of bytes to move.
+    ///
+    /// [argument, flags]
+    /// Flags to use while moving.
+    ///
+    /// [return_value]
+    /// Returns the number of bytes moved.
+    ///
+    /// = Remarks
+    ///
+    /// If `off` is not `None`, the offset is advanced by the number of bytes moved. If
+    /// `dst` is a pipe, `off` must be `None`. Otherwise `ESPIPE` is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:splice(2)
+    pub fn splice_to<F>(&self, dst: &F, off: Option<&mut u64>, len: usize,
+                        flags: SpliceFlags) -> Result<usize>
+        where F: FdContainer,
+    {
+        let mut off = off;
+        retry(|| splice(self.fd, None, dst.borrow(), off.as_deref_mut(), len,
+                        flags.0)).map(|r| r as usize)
+    }
+
+    /// Copies data from the pipe to another pipe without consuming it.
+    ///
+    /// [argument, dst]
+    /// The destination pipe.
+    ///
+    /// [argument, len]
+    /// The maximum number of bytes to copy.
+    ///
+    /// [argument, flags]
+    /// Flags to use while copying.
+    ///
+    /// [return_value]
+    /// Returns the number of bytes copied.
+    ///
+    /// = See also
+    ///
+    /// * link:man:tee(2)
+    pub fn tee_to(&self, dst: &PipeWriter, len: usize,
+                  flags: SpliceFlags) -> Result<usize> {
+        retry(|| tee(self.fd, dst.fd, len, flags.0)).map(|r| r as usize)
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        if self.owned {
+            close(self.fd);
+        }
+    }
+}
+
+impl From<PipeReader> for c_int {
+    fn from(pipe: PipeReader) -> Self {
+        let fd = pipe.fd;
+        mem::forget(pipe);
+        fd
+    }
+}
+
+impl FdContainer for PipeReader {
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    fn borrow(&self) -> c_int {
+        self.fd
+    }
+
+    fn from_owned(fd: c_int) -> PipeReader {
+        PipeReader { fd, owned: true }
+    }
+
+    fn from_borrowed(fd: c_int) -> PipeReader {
+        PipeReader { fd, owned: false }
+    }
+}
+
+macro_rules! impl_read {
+    ($ty:ty) => {
+        impl Read for $ty {
+            fn scatter_read(&mut self, bufs: &mut [&mut [d8]]) -> Result<usize> {
+                retry(|| readv(self.fd, bufs)).map(|r| r as usize)
+            }
+
+            fn read(&mut self, buf: &mut [d8]) -> Result<usize> {
+                retry(|| read(self.fd, buf)).map(|r| r as usize)
+            }
+        }
+
+        impl_std_read!($ty);
+    }
+}
+
+impl_read!(PipeReader);
+impl_read!(&PipeReader);
+
+/// The write end of a pipe.
+pub struct PipeWriter {
+    fd: c_int,
+    owned: bool,
+}
+
+impl PipeWriter {
+    /// Returns the capacity of the pipe.
+    ///
+    /// = See also
+    ///
+    /// * link:man:fcntl(2) and F_GETPIPE_SZ therein
+    pub fn capacity(&self) -> Result<usize> {
+        capacity(self.fd)
+    }
+
+    /// Sets the capacity of the pipe.
+    ///
+    /// [argument, size]
+    /// The minimum new capacity.
+    ///
+    /// [return_value]
+    /// Returns the new capacity.
+    ///
+    /// = See also
+    ///
+    /// * link:lrs::pipe::PipeReader::set_capacity
+    pub fn set_capacity(&self, size: usize) -> Result<usize> {
+        set_capacity(self.fd, size)
+    }
+
+    /// Moves data from a file descriptor into the pipe.
+    ///
+    /// [argument, src]
+    /// The source.
+    ///
+    /// [argument, off]
+    /// The offset in the source or `None` to use and update the file position.
+    ///
+    /// [argument, len]
+    /// The maximum number of bytes to move.
+    ///
+    /// [argument, flags]
+    /// Flags to use while moving.
+    ///
+    /// [return_value]
+    /// Returns the number of bytes moved.
+    ///
+    /// = Remarks
+    ///
+    /// If `off` is not `None`, the offset is advanced by the number of bytes moved. If
+    /// `src` is a pipe, `off` must be `None`. Otherwise `ESPIPE` is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:splice(2)
+    pub fn splice_from<F>(&self, src: &F, off: Option<&mut u64>, len: usize,
+                          flags: SpliceFlags) -> Result<usize>
+        where F: FdContainer,
+    {
+        let mut off = off;
+        retry(|| splice(src.borrow(), off.as_deref_mut(), self.fd, None, len,
+                        flags.0)).map(|r| r as usize)
+    }
+
+    /// Maps user memory into the pipe.
+    ///
+    /// [argument, bufs]
+    /// The buffers to map.
+    ///
+    /// [argument, flags]
+    /// Flags to use while mapping.
+    ///
+    /// [return_value]
+    /// Returns the number of bytes mapped.
+    ///
+    /// = Remarks
+    ///
+    /// The pages of the buffers are referenced by the pipe until the data has been
+    /// read. Modifications of the buffers during this time can be observed by the
+    /// reader.
+    ///
+    /// This function is unsafe because the caller must ensure that the buffers are
+    /// not modified, freed, or reused until the reader has consumed the data. If
+    /// `SPLICE_GIFT` is set, the buffers must never be modified again.
+    ///
+    /// = See also
+    ///
+    /// * link:man:vmsplice(2)
+    pub unsafe fn vmsplice(&self, bufs: &[&[u8]], flags: SpliceFlags) -> Result<usize> {
+        retry(|| vmsplice(self.fd, d8::from_byte_slice_slice(bufs), flags.0))
+            .map(|r| r as usize)
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        if self.owned {
+            close(self.fd);
+        }
+    }
+}
+
+impl From<PipeWriter> for c_int {
+    fn from(pipe: PipeWriter) -> Self {
+        let fd = pipe.fd;
+        mem::forget(pipe);
+        fd
+    }
+}
+
+impl FdContainer for PipeWriter {
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    fn borrow(&self) -> c_int {
+        self.fd
+    }
+
+    fn from_owned(fd: c_int) -> PipeWriter {
+        PipeWriter { fd, owned: true }
+    }
+
+    fn from_borrowed(fd: c_int) -> PipeWriter {
+        PipeWriter { fd, owned: false }
+    }
+}
+
+macro_rules! impl_write {
+    ($ty:ty) => {
+        impl Write for $ty {
+            fn gather_write(&mut self, bufs: &[&[u8]]) -> Result<usize> {
+                retry(|| writev(self.fd, d8::from_byte_slice_slice(bufs)))
+                    .map(|r| r as usize)
+            }
+
+            fn write(&mut self, buf: &[u8]) -> Result<usize> {
+                retry(|| write(self.fd, d8::from_byte_slice(buf))).map(|r| r as usize)
+            }
+
+            fn flush(&mut self) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        impl_std_write!($ty);
+    }
+}
+
+impl_write!(PipeWriter);
+impl_write!(&PipeWriter);
@@ -0,0 +1,392 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem, slice};
+use core::ops::{BitOr, Not, BitAnd};
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    time::{Time},
+    util::{
+        data::{d8, DataSlice},
+    },
+    fd::{FdContainer},
+    signal::{SigSet},
+};
+use crate::kty::{
+    c_int, c_uint, epoll_event, EPOLL_CTL_ADD, EPOLL_CTL_MOD, EPOLL_CTL_DEL,
+    EPOLLIN, EPOLLOUT, EPOLLRDHUP, EPOLLPRI, EPOLLERR, EPOLLHUP, EPOLLET, EPOLLONESHOT,
+    EPOLLWAKEUP, EPOLLEXCLUSIVE,
+};
+use crate::syscall::{epoll_create, epoll_ctl, epoll_pwait, close};
+
+/// Flags of an epoll registration or event.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct EpollFlags(pub c_uint);
+
+impl EpollFlags {
+    /// Returns whether all flags of another set are set in this set.
+    ///
+    /// [argument, other]
+    /// The flags to check.
+    pub fn is_set(self, other: EpollFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for EpollFlags {
+    type Output = EpollFlags;
+    fn bitor(self, other: EpollFlags) -> EpollFlags {
+        EpollFlags(self.0 | other.0)
+    }
+}
+
+impl BitAnd for EpollFlags {
+    type Output = EpollFlags;
+    fn bitand(self, other: EpollFlags) -> EpollFlags {
+        EpollFlags(self.0 & other.0)
+    }
+}
+
+impl Not for EpollFlags {
+    type Output = EpollFlags;
+    fn not(self) -> EpollFlags {
+        EpollFlags(!self.0)
+    }
+}
+
+/// Dummy flag with all flags unset.
+pub const EPOLL_NONE: EpollFlags = EpollFlags(0);
+
+macro_rules! create_flags {
+    ($($(#[$meta:meta])* flag $name:ident = $val:expr;)*) => {
+        $($(#[$meta])* pub const $name: EpollFlags = EpollFlags($val);)*
+
+        impl Debug for EpollFlags {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let mut first = true;
+                $(
+                    if self.0 & $val != 0 {
+                        if !first { core::write!(f, "|")?; }
+                        first = false;
+                        core::write!(f, stringify!($name))?;
+                    }
+                )*
+                if self.0 == 0 {
+                    core::write!(f, "EPOLL_NONE")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+create_flags! {
+    #[doc = "The file descriptor is readable.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLIN therein"]
+    flag EPOLL_IN = EPOLLIN;
+
+    #[doc = "The file descriptor is writable.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLOUT therein"]
+    flag EPOLL_OUT = EPOLLOUT;
+
+    #[doc = "The peer of a stream socket closed the connection or shut down writing.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLRDHUP therein"]
+    flag EPOLL_RDHUP = EPOLLRDHUP;
+
+    #[doc = "Priority data is available.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLPRI therein"]
+    flag EPOLL_PRI = EPOLLPRI;
+
+    #[doc = "An error occurred on the file descriptor.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "This is always reported and does not have to be requested.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLERR therein"]
+    flag EPOLL_ERR = EPOLLERR;
+
+    #[doc = "The file descriptor was hung up.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "This is always reported and does not have to be requested.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLHUP therein"]
+    flag EPOLL_HUP = EPOLLHUP;
+
+    #[doc = "Use edge-triggered notification.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLET therein"]
+    flag EPOLL_ET = EPOLLET;
+
+    #[doc = "Disable the registration after one event has been reported.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "The registration can be re-enabled with `Epoll::modify`.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLONESHOT therein"]
+    flag EPOLL_ONESHOT = EPOLLONESHOT;
+
+    #[doc = "Prevent the system from suspending while the event is being processed.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLWAKEUP therein"]
+    flag EPOLL_WAKEUP = EPOLLWAKEUP;
+
+    #[doc = "Wake only one of several epoll instances waiting on the same file.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "This can only be used with `Epoll::add`.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:epoll_ctl(2) and EPOLLEXCLUSIVE therein"]
+    flag EPOLL_EXCLUSIVE = EPOLLEXCLUSIVE;
+}
+
+/// An epoll instance.
+pub struct Epoll {
+    fd: c_int,
+    owned: bool,
+}
+
+impl Epoll {
+    /// Creates a new epoll instance.
+    ///
+    /// = See also
+    ///
+    /// * link:man:epoll_create1(2)
+    pub fn new() -> Result<Epoll> {
+        let fd = rv!(epoll_create(0), -> c_int)?;
+        Ok(Epoll::from_owned(fd))
+    }
+
+    fn ctl<F: FdContainer>(&self, op: c_int, fd: &F, token: u64,
+                           flags: EpollFlags) -> Result {
+        let mut event = epoll_event { events: flags.0, data: token };
+        rv!(epoll_ctl(self.fd, op, fd.borrow(), Some(&mut event)))
+    }
+
+    /// Adds a file descriptor to the epoll instance.
+    ///
+    /// [argument, fd]
+    /// The file descriptor to add.
+    ///
+    /// [argument, token]
+    /// A value that is returned with all events of this file descriptor.
+    ///
+    /// [argument, flags]
+    /// The events to wait for and how to report them.
+    ///
+    /// = See also
+    ///
+    /// * link:man:epoll_ctl(2) and EPOLL_CTL_ADD therein
+    pub fn add<F: FdContainer>(&self, fd: &F, token: u64, flags: EpollFlags) -> Result {
+        self.ctl(EPOLL_CTL_ADD, fd, token, flags)
+    }
+
+    /// Modifies a file descriptor in the epoll instance.
+    ///
+    /// [argument, fd]
+    /// The file descriptor to modify.
+    ///
+    /// [argument, token]
+    /// The new token of the file descriptor.
+    ///
+    /// [argument, flags]
+    /// The new flags of the file descriptor.
+    ///
+    /// = See also
+    ///
+    /// * link:man:epoll_ctl(2) and EPOLL_CTL_MOD therein
+    pub fn modify<F: FdContainer>(&self, fd: &F, token: u64, flags: EpollFlags) -> Result {
+        self.ctl(EPOLL_CTL_MOD, fd, token, flags)
+    }
+
+    /// Removes a file descriptor from the epoll instance.
+    ///
+    /// [argument, fd]
+    /// The file descriptor to remove.
+    ///
+    /// = See also
+    ///
+    /// * link:man:epoll_ctl(2) and EPOLL_CTL_DEL therein
+    pub fn delete<F: FdContainer>(&self, fd: &F) -> Result {
+        rv!(epoll_ctl(self.fd, EPOLL_CTL_DEL, fd.borrow(), None))
+    }
+
+    /// Waits for events.
+    ///
+    /// [argument, events]
+    /// The buffer in which the events will be stored.
+    ///
+    /// [argument, timeout]
+    /// The maximum time to wait or `None` to wait indefinitely.
+    ///
+    /// [return_value]
+    /// Returns an iterator over the `(token, flags)` pairs of the events.
+    ///
+    /// = Remarks
+    ///
+    /// The timeout is rounded up to milliseconds. If the call is interrupted by a signal
+    /// handler, `EINTR` is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:epoll_wait(2)
+    pub fn wait<'a>(&self, events: &'a mut Events<'_>,
+                    timeout: Option<Time>) -> Result<EventIter<'a>> {
+        self.wait_inner(events, timeout, None)
+    }
+
+    /// Waits for events with a temporary signal mask.
+    ///
+    /// [argument, events]
+    /// The buffer in which the events will be stored.
+    ///
+    /// [argument, timeout]
+    /// The maximum time to wait or `None` to wait indefinitely.
+    ///
+    /// [argument, mask]
+    /// The signal mask that is installed while waiting.
+    ///
+    /// [return_value]
+    /// Returns an iterator over the `(token, flags)` pairs of the events.
+    ///
+    /// = See also
+    ///
+    /// * link:man:epoll_pwait(2)
+    /// * link:lrs::poll::Epoll::wait
+    pub fn wait_masked<'a>(&self, events: &'a mut Events<'_>, timeout: Option<Time>,
+                           mask: &SigSet) -> Result<EventIter<'a>> {
+        self.wait_inner(events, timeout, Some(mask))
+    }
+
+    fn wait_inner<'a>(&self, events: &'a mut Events<'_>, timeout: Option<Time>,
+                      mask: Option<&SigSet>) -> Result<EventIter<'a>> {
+        let timeout = timeout_to_millis(timeout);
+        events.len = 0;
+        let mask = mask.map(|m| m.as_raw());
+        let n = rv!(epoll_pwait(self.fd, events.buf, timeout, mask), -> usize)?;
+        events.len = n;
+        Ok(EventIter { events: events.buf[..n].iter() })
+    }
+}
+
+/// Converts an optional timeout to milliseconds, rounding up.
+fn timeout_to_millis(timeout: Option<Time>) -> c_int {
+    let t = match timeout {
+        Some(t) => t.normalize(),
+        _ => return -1,
+    };
+    if t.seconds < 0 {
+        return 0;
+    }
+    let millis = t.seconds.saturating_mul(1000)
+                          .saturating_add((t.nanoseconds + 999_999) / 1_000_000);
+    if millis > c_int::max_value() as i64 {
+        c_int::max_value()
+    } else {
+        millis as c_int
+    }
+}
+
+impl Drop for Epoll {
+    fn drop(&mut self) {
+        if self.owned {
+            close(self.fd);
+        }
+    }
+}
+
+impl From<Epoll> for c_int {
+    fn from(epoll: Epoll) -> Self {
+        let fd = epoll.fd;
+        mem::forget(epoll);
+        fd
+    }
+}
+
+impl FdContainer for Epoll {
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    fn borrow(&self) -> c_int {
+        self.fd
+    }
+
+    fn from_owned(fd: c_int) -> Epoll {
+        Epoll { fd, owned: true }
+    }
+
+    fn from_borrowed(fd: c_int) -> Epoll {
+        Epoll { fd, owned: false }
+    }
+}
+
+/// A buffer for epoll events.
+pub struct Events<'a> {
+    buf: &'a mut [epoll_event],
+    len: usize,
+}
+
+impl<'a> Events<'a> {
+    /// Creates a new event buffer.
+    ///
+    /// [argument, buf]
+    /// The memory in which the events will be stored.
+    ///
+    /// = Remarks
+    ///
+    /// The number of events that can be returned by a single call to `wait` is the
+    /// length of the buffer divided by the size of `epoll_event`.
+    pub fn new(buf: &'a mut [d8]) -> Events<'a> {
+        let buf = buf.align_for_mut::<epoll_event>();
+        let cap = buf.len() / mem::size_of::<epoll_event>();
+        let buf = unsafe {
+            slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut epoll_event, cap)
+        };
+        Events { buf, len: 0 }
+    }
+
+    /// Returns the maximum number of events that can be stored in the buffer.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns the number of events returned by the last call to `wait`.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the last call to `wait` returned no events.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the events returned by the last call to `wait`.
+    pub fn iter(&self) -> EventIter<'_> {
+        EventIter { events: self.buf[..self.len].iter() }
+    }
+}
+
+/// An iterator over epoll events.
+///
+/// = Remarks
+///
+/// Each item is the token that was passed to `Epoll::add` together with the events
+/// that occurred.
+pub struct EventIter<'a> {
+    events: slice::Iter<'a, epoll_event>,
+}
+
+impl<'a> Iterator for EventIter<'a> {
+    type Item = (u64, EpollFlags);
+
+    fn next(&mut self) -> Option<(u64, EpollFlags)> {
+        self.events.next().map(|e| (e.data, EpollFlags(e.events)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.events.size_hint()
+    }
+}
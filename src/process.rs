@@ -0,0 +1,97 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+#[cfg(feature = "alloc")]
+pub use self::command::{Command, Stdio};
+pub use self::wait::{
+    ExitStatus, WaitStatus, WaitId, Usage, WaitFlags, WAIT_EXITED, WAIT_STOPPED,
+    WAIT_CONTINUED, WAIT_NO_WAIT, wait_pid, wait_any, wait_pgid, try_wait,
+};
+
+use crate::{
+    result::{Result},
+    pipe::{PipeReader, PipeWriter},
+    signal::{Signal},
+};
+use crate::kty::{c_int, pid_t};
+use crate::syscall::{kill};
+
+#[cfg(feature = "alloc")]
+mod command;
+mod wait;
+
+/// A handle to a child process.
+///
+/// = Remarks
+///
+/// Dropping the handle neither kills nor reaps the child.
+pub struct Child {
+    pid: pid_t,
+    /// The parent end of the standard input of the child if it is `Stdio::Piped`.
+    pub stdin: Option<PipeWriter>,
+    /// The parent end of the standard output of the child if it is `Stdio::Piped`.
+    pub stdout: Option<PipeReader>,
+    /// The parent end of the standard error of the child if it is `Stdio::Piped`.
+    pub stderr: Option<PipeReader>,
+}
+
+impl Child {
+    /// Creates a handle from a process id.
+    ///
+    /// [argument, pid]
+    /// The process id of a child of the calling process.
+    pub fn from_pid(pid: pid_t) -> Child {
+        Child {
+            pid,
+            stdin: None,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    /// Returns the process id of the child.
+    pub fn id(&self) -> pid_t {
+        self.pid
+    }
+
+    /// Sends a signal to the child.
+    ///
+    /// [argument, sig]
+    /// The signal to send.
+    ///
+    /// = See also
+    ///
+    /// * link:man:kill(2)
+    pub fn kill(&self, sig: Signal) -> Result {
+        rv!(kill(self.pid, sig.to_raw()))
+    }
+
+    /// Waits for the child to terminate and reaps it.
+    ///
+    /// = Remarks
+    ///
+    /// The standard input of the child is closed before waiting so that children
+    /// reading from it can terminate.
+    ///
+    /// = See also
+    ///
+    /// * link:man:waitid(2)
+    pub fn wait(&mut self) -> Result<ExitStatus> {
+        self.stdin = None;
+        wait_pid(self.pid, WAIT_EXITED, None).map(|s| s.status)
+    }
+
+    /// Reaps the child if it has terminated.
+    ///
+    /// [return_value]
+    /// Returns the exit status or `None` if the child is still running.
+    ///
+    /// = See also
+    ///
+    /// * link:man:waitid(2) and WNOHANG therein
+    pub fn try_wait(&self) -> Result<Option<ExitStatus>> {
+        let status = try_wait(WaitId::Pid(self.pid), WAIT_EXITED, None)?;
+        Ok(status.map(|s| s.status))
+    }
+}
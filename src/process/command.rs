@@ -0,0 +1,585 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem, ptr};
+use alloc::{vec, vec::Vec};
+use memchr::{memchr};
+use crate::{
+    result::{Result},
+    lmem,
+    fd::{FdContainer},
+    file::{File, OpenOptions},
+    pipe::{pipe},
+    process::{Child, wait_pid, WAIT_EXITED},
+    signal::{self, SigSet},
+    util::{
+        retry::{retry},
+        error::{self, Errno},
+        kstr::{KStr},
+    },
+};
+use crate::kty::{
+    c_int, c_char, c_ulong, c_void, pid_t, uid_t, gid_t, sigaction, _NSIG,
+    AT_FDCWD, CLONE_VM, CLONE_VFORK, SIGCHLD, SIGKILL, SIGSTOP, SIG_DFL, SIG_IGN,
+    SIG_SETMASK,
+};
+use crate::syscall::{
+    clone_fn, execveat, read, write, chdir, dup3, fcntl_dupfd_cloexec,
+    fcntl_setfd, setpgid, setresuid, setresgid, setgroups, rt_sigaction, rt_sigprocmask,
+};
+
+/// The size of the stack on which the child runs until it calls `execve`.
+const STACK_SIZE: usize = 64 * 1024;
+
+/// The search path used if the environment of the child does not contain `PATH`.
+const DEFAULT_PATH: &[u8] = b"/usr/local/bin:/usr/bin:/bin";
+
+/// The configuration of one of the standard streams of a child.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Stdio {
+    /// The stream is inherited from the parent.
+    Inherit,
+    /// The stream is connected to `/dev/null`.
+    Null,
+    /// The stream is connected to a pipe whose other end is stored in the `Child`.
+    Piped,
+    /// The stream is a copy of a file descriptor of the parent.
+    Fd(c_int),
+}
+
+/// A builder for spawning child processes.
+///
+/// = Remarks
+///
+/// The child is created with `CLONE_VM | CLONE_VFORK` and runs on a private stack until
+/// it calls `execveat`. All memory needed by the child is allocated before the child is
+/// created. Errors that occur in the child before or during `execveat` are reported to
+/// the parent via a close-on-exec pipe and returned from `spawn`.
+///
+/// If the program does not contain a slash, it is searched in the `PATH` of the child's
+/// environment.
+///
+/// The child inherits the environment of the parent unless `env_clear` is called. This
+/// requires the `std` feature. Otherwise the environment of the child consists only of
+/// the variables set via `env`.
+///
+/// = Examples
+///
+/// ----
+/// let mut child = Command::new("ls").arg("-l").cwd("/tmp").stdout(Stdio::Piped)
+///                                  .spawn()?;
+/// ----
+pub struct Command {
+    program: Vec<u8>,
+    args: Vec<Vec<u8>>,
+    env_clear: bool,
+    env: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+    cwd: Option<Vec<u8>>,
+    stdio: [Stdio; 3],
+    fds: Vec<(c_int, c_int)>,
+    uid: Option<uid_t>,
+    gid: Option<gid_t>,
+    groups: Option<Vec<gid_t>>,
+    pgid: Option<pid_t>,
+    reset_mask: bool,
+}
+
+impl Command {
+    /// Creates a new builder.
+    ///
+    /// [argument, program]
+    /// The program to execute.
+    ///
+    /// = Remarks
+    ///
+    /// The program is also the first argument passed to the child.
+    pub fn new<S: AsRef<[u8]>+?Sized>(program: &S) -> Command {
+        let program = program.as_ref().to_vec();
+        Command {
+            args: vec!(program.clone()),
+            program,
+            env_clear: false,
+            env: Vec::new(),
+            cwd: None,
+            stdio: [Stdio::Inherit; 3],
+            fds: Vec::new(),
+            uid: None,
+            gid: None,
+            groups: None,
+            pgid: None,
+            reset_mask: false,
+        }
+    }
+
+    /// Appends an argument.
+    ///
+    /// [argument, arg]
+    /// The argument.
+    pub fn arg<S: AsRef<[u8]>+?Sized>(&mut self, arg: &S) -> &mut Command {
+        self.args.push(arg.as_ref().to_vec());
+        self
+    }
+
+    /// Appends multiple arguments.
+    ///
+    /// [argument, args]
+    /// The arguments.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Command
+        where I: IntoIterator<Item = S>,
+              S: AsRef<[u8]>,
+    {
+        for arg in args {
+            self.args.push(arg.as_ref().to_vec());
+        }
+        self
+    }
+
+    /// Sets an environment variable.
+    ///
+    /// [argument, key]
+    /// The name of the variable.
+    ///
+    /// [argument, val]
+    /// The value of the variable.
+    pub fn env<K, V>(&mut self, key: &K, val: &V) -> &mut Command
+        where K: AsRef<[u8]>+?Sized,
+              V: AsRef<[u8]>+?Sized,
+    {
+        self.set_env(key.as_ref(), Some(val.as_ref().to_vec()));
+        self
+    }
+
+    /// Removes an environment variable.
+    ///
+    /// [argument, key]
+    /// The name of the variable.
+    pub fn env_remove<K: AsRef<[u8]>+?Sized>(&mut self, key: &K) -> &mut Command {
+        self.set_env(key.as_ref(), None);
+        self
+    }
+
+    /// Removes all environment variables, including those inherited from the parent.
+    pub fn env_clear(&mut self) -> &mut Command {
+        self.env_clear = true;
+        self.env.clear();
+        self
+    }
+
+    fn set_env(&mut self, key: &[u8], val: Option<Vec<u8>>) {
+        match self.env.iter_mut().find(|e| e.0 == key) {
+            Some(e) => e.1 = val,
+            _ => self.env.push((key.to_vec(), val)),
+        }
+    }
+
+    /// Sets the working directory of the child.
+    ///
+    /// [argument, dir]
+    /// The working directory.
+    pub fn cwd<S: AsRef<[u8]>+?Sized>(&mut self, dir: &S) -> &mut Command {
+        self.cwd = Some(dir.as_ref().to_vec());
+        self
+    }
+
+    /// Configures the standard input of the child.
+    ///
+    /// [argument, cfg]
+    /// The configuration.
+    pub fn stdin(&mut self, cfg: Stdio) -> &mut Command {
+        self.stdio[0] = cfg;
+        self
+    }
+
+    /// Configures the standard output of the child.
+    ///
+    /// [argument, cfg]
+    /// The configuration.
+    pub fn stdout(&mut self, cfg: Stdio) -> &mut Command {
+        self.stdio[1] = cfg;
+        self
+    }
+
+    /// Configures the standard error of the child.
+    ///
+    /// [argument, cfg]
+    /// The configuration.
+    pub fn stderr(&mut self, cfg: Stdio) -> &mut Command {
+        self.stdio[2] = cfg;
+        self
+    }
+
+    /// Makes a file descriptor of the parent available in the child.
+    ///
+    /// [argument, child]
+    /// The number of the file descriptor in the child.
+    ///
+    /// [argument, parent]
+    /// The file descriptor in the parent.
+    ///
+    /// = Remarks
+    ///
+    /// The file descriptor in the child does not have the close-on-exec flag set. This
+    /// overrides the configuration of the standard streams if `child` is `0`, `1`, or
+    /// `2`.
+    pub fn fd(&mut self, child: c_int, parent: c_int) -> &mut Command {
+        self.fds.retain(|f| f.0 != child);
+        self.fds.push((child, parent));
+        self
+    }
+
+    /// Sets the user id of the child.
+    ///
+    /// [argument, uid]
+    /// The real, effective, and saved user id of the child.
+    ///
+    /// = Remarks
+    ///
+    /// Unless `groups` has been called, the supplementary groups of the child are
+    /// replaced by the group id set via `gid` or cleared if no group id has been set.
+    /// This requires the `CAP_SETGID` capability.
+    pub fn uid(&mut self, uid: uid_t) -> &mut Command {
+        self.uid = Some(uid);
+        self
+    }
+
+    /// Sets the group id of the child.
+    ///
+    /// [argument, gid]
+    /// The real, effective, and saved group id of the child.
+    ///
+    /// = Remarks
+    ///
+    /// Unless `groups` has been called, the supplementary groups of the child are
+    /// replaced by this group id. This requires the `CAP_SETGID` capability.
+    pub fn gid(&mut self, gid: gid_t) -> &mut Command {
+        self.gid = Some(gid);
+        self
+    }
+
+    /// Sets the supplementary groups of the child.
+    ///
+    /// [argument, groups]
+    /// The supplementary groups of the child.
+    ///
+    /// = Remarks
+    ///
+    /// This requires the `CAP_SETGID` capability.
+    ///
+    /// = See also
+    ///
+    /// * link:man:setgroups(2)
+    pub fn groups(&mut self, groups: &[gid_t]) -> &mut Command {
+        self.groups = Some(groups.to_vec());
+        self
+    }
+
+    /// Moves the child into a process group.
+    ///
+    /// [argument, pgid]
+    /// The process group or `0` to create a new process group whose id is the process
+    /// id of the child.
+    pub fn process_group(&mut self, pgid: pid_t) -> &mut Command {
+        self.pgid = Some(pgid);
+        self
+    }
+
+    /// Sets whether the signal mask of the child is cleared.
+    ///
+    /// [argument, val]
+    /// Whether the child starts with an empty signal mask instead of the signal mask
+    /// of the calling thread.
+    pub fn reset_signal_mask(&mut self, val: bool) -> &mut Command {
+        self.reset_mask = val;
+        self
+    }
+
+    /// Spawns the child.
+    ///
+    /// [return_value]
+    /// Returns a handle to the child.
+    ///
+    /// = Remarks
+    ///
+    /// If an argument, an environment variable, or a path contains a null byte,
+    /// `EINVAL` is returned.
+    pub fn spawn(&self) -> Result<Child> {
+        let env = self.build_env()?;
+        let argv = self.args.iter().map(|a| c_string(a)).collect::<Result<Vec<_>>>()?;
+        let candidates = self.candidates(&env)?;
+        let cwd = match self.cwd {
+            Some(ref cwd) => Some(c_string(cwd)?),
+            _ => None,
+        };
+        let argv_ptrs = c_string_ptrs(&argv);
+        let env_ptrs = c_string_ptrs(&env);
+
+        // File descriptors in `child_ends` are closed in the parent once the child has called
+        // `execveat`.
+        let mut child_ends = Vec::new();
+        let (mut stdin, mut stdout, mut stderr) = (None, None, None);
+        let mut fds = Vec::with_capacity(self.fds.len() + 3);
+        for (i, cfg) in self.stdio.iter().enumerate() {
+            let fd = i as c_int;
+            if self.fds.iter().any(|f| f.0 == fd) {
+                continue;
+            }
+            match *cfg {
+                Stdio::Inherit => { },
+                Stdio::Null => {
+                    let file = OpenOptions::new().read(true).write(true)
+                                                 .open("/dev/null")?;
+                    fds.push((fd, file.borrow()));
+                    child_ends.push(file);
+                },
+                Stdio::Piped if i == 0 => {
+                    let (read, write) = pipe()?;
+                    fds.push((fd, read.borrow()));
+                    child_ends.push(File::from_owned(read.into()));
+                    stdin = Some(write);
+                },
+                Stdio::Piped => {
+                    let (read, write) = pipe()?;
+                    fds.push((fd, write.borrow()));
+                    child_ends.push(File::from_owned(write.into()));
+                    match i {
+                        1 => stdout = Some(read),
+                        _ => stderr = Some(read),
+                    }
+                },
+                Stdio::Fd(parent) => fds.push((fd, parent)),
+            }
+        }
+        fds.extend_from_slice(&self.fds);
+        let mut tmp = vec!(0; fds.len());
+
+        // The supplementary groups of the parent must not leak into a child that runs
+        // with different credentials.
+        let default_groups: Vec<gid_t> = self.gid.into_iter().collect();
+        let groups = match self.groups {
+            Some(ref groups) => Some(&groups[..]),
+            _ if self.uid.is_some() || self.gid.is_some() => Some(&default_groups[..]),
+            _ => None,
+        };
+
+        let (err_read, err_write) = pipe()?;
+        let mut stack = vec!(0u8; STACK_SIZE);
+
+        let mut setup = Setup {
+            candidates: &candidates,
+            argv: argv_ptrs.as_ptr(),
+            envp: env_ptrs.as_ptr(),
+            cwd: cwd.as_ref().map(|c| c.as_ptr() as *const c_char),
+            fds: &fds,
+            tmp: &mut tmp,
+            err: err_write.borrow(),
+            uid: self.uid,
+            gid: self.gid,
+            groups,
+            pgid: self.pgid,
+            mask: SigSet::empty(),
+        };
+
+        // Block all signals so that no handler of the parent runs in the child before
+        // the child has reset all handlers.
+        let old_mask = signal::set_mask(&SigSet::full())?;
+        if !self.reset_mask {
+            setup.mask = old_mask;
+        }
+        let pid = unsafe {
+            let top = stack.as_mut_ptr().add(stack.len());
+            clone_fn((CLONE_VM | CLONE_VFORK | SIGCHLD) as c_ulong, top, child_main,
+                     &mut setup as *mut Setup as *mut c_void)
+        };
+        let _ = signal::set_mask(&old_mask);
+        let pid = rv!(pid, -> pid_t)?;
+
+        drop(err_write);
+        drop(child_ends);
+
+        let mut errno: c_int = 0;
+        let n = retry(|| read(err_read.borrow(), lmem::as_mut_data(&mut errno)))?;
+        if n as usize == mem::size_of::<c_int>() {
+            let _ = wait_pid(pid, WAIT_EXITED, None);
+            return Err(Errno(errno));
+        }
+
+        let mut child = Child::from_pid(pid);
+        child.stdin = stdin;
+        child.stdout = stdout;
+        child.stderr = stderr;
+        Ok(child)
+    }
+
+    /// Creates the environment of the child.
+    fn build_env(&self) -> Result<Vec<Vec<u8>>> {
+        let mut vars = match self.env_clear {
+            true => Vec::new(),
+            false => inherited_env(),
+        };
+        for &(ref key, ref val) in &self.env {
+            vars.retain(|v: &(Vec<u8>, Vec<u8>)| v.0 != *key);
+            if let Some(ref val) = *val {
+                vars.push((key.clone(), val.clone()));
+            }
+        }
+        let mut env = Vec::with_capacity(vars.len());
+        for (key, val) in vars {
+            if memchr(b'=', &key).is_some() {
+                return Err(error::InvalidArgument);
+            }
+            let mut var = key;
+            var.push(b'=');
+            var.extend_from_slice(&val);
+            env.push(c_string(&var)?);
+        }
+        Ok(env)
+    }
+
+    /// Creates the paths that the child tries to execute.
+    fn candidates(&self, env: &[Vec<u8>]) -> Result<Vec<Vec<u8>>> {
+        if self.program.is_empty() {
+            return Err(error::DoesNotExist);
+        }
+        if memchr(b'/', &self.program).is_some() {
+            return Ok(vec!(c_string(&self.program)?));
+        }
+        let path = env.iter().find(|v| v.starts_with(b"PATH="))
+                      .map(|v| &v[5..v.len() - 1])
+                      .unwrap_or(DEFAULT_PATH);
+        let mut candidates = Vec::new();
+        for dir in path.split(|&b| b == b':') {
+            let dir = if dir.is_empty() { &b"."[..] } else { dir };
+            let mut file = dir.to_vec();
+            file.push(b'/');
+            file.extend_from_slice(&self.program);
+            candidates.push(c_string(&file)?);
+        }
+        Ok(candidates)
+    }
+}
+
+/// The state shared between the parent and the child.
+struct Setup<'a> {
+    candidates: &'a [Vec<u8>],
+    argv: *const *const c_char,
+    envp: *const *const c_char,
+    cwd: Option<*const c_char>,
+    fds: &'a [(c_int, c_int)],
+    tmp: &'a mut [c_int],
+    err: c_int,
+    uid: Option<uid_t>,
+    gid: Option<gid_t>,
+    groups: Option<&'a [gid_t]>,
+    pgid: Option<pid_t>,
+    mask: SigSet,
+}
+
+/// The entry point of the child.
+///
+/// = Remarks
+///
+/// This function runs in the memory of the parent and must not allocate.
+extern "C" fn child_main(arg: *mut c_void) -> c_int {
+    let setup = unsafe { &mut *(arg as *mut Setup) };
+    if let Err(e) = child_exec(setup) {
+        write(setup.err, lmem::as_data(&e.0));
+    }
+    127
+}
+
+/// Prepares the child and executes the program.
+///
+/// [return_value]
+/// Only returns if an error occurred.
+fn child_exec(setup: &mut Setup) -> Result {
+    // Handlers of the parent must not run in the child since they might modify the
+    // memory of the parent.
+    let dfl: sigaction = lmem::zeroed();
+    for sig in 1..(_NSIG as c_int + 1) {
+        if sig == SIGKILL || sig == SIGSTOP {
+            continue;
+        }
+        let mut old: sigaction = lmem::zeroed();
+        if rt_sigaction(sig, None, Some(&mut old)) == 0 &&
+                old.sa_handler != SIG_DFL && old.sa_handler != SIG_IGN {
+            rt_sigaction(sig, Some(&dfl), None);
+        }
+    }
+
+    if let Some(pgid) = setup.pgid {
+        rv!(setpgid(0, pgid))?;
+    }
+    if let Some(groups) = setup.groups {
+        rv!(setgroups(groups))?;
+    }
+    if let Some(gid) = setup.gid {
+        rv!(setresgid(gid, gid, gid))?;
+    }
+    if let Some(uid) = setup.uid {
+        rv!(setresuid(uid, uid, uid))?;
+    }
+    if let Some(cwd) = setup.cwd {
+        rv!(chdir(unsafe { KStr::new(cwd) }))?;
+    }
+
+    // Move all file descriptors out of the way before placing them at their targets
+    // so that no target overwrites a source or the error pipe.
+    let min = setup.fds.iter().map(|f| f.0).max().unwrap_or(-1) + 1;
+    if min > 0 {
+        setup.err = rv!(fcntl_dupfd_cloexec(setup.err, min), -> c_int)?;
+    }
+    for (i, &(_, src)) in setup.fds.iter().enumerate() {
+        setup.tmp[i] = rv!(fcntl_dupfd_cloexec(src, min), -> c_int)?;
+    }
+    for (i, &(dst, _)) in setup.fds.iter().enumerate() {
+        rv!(dup3(setup.tmp[i], dst, 0))?;
+        rv!(fcntl_setfd(dst, 0))?;
+    }
+
+    rv!(rt_sigprocmask(SIG_SETMASK, Some(setup.mask.as_raw()), None))?;
+
+    let mut denied = false;
+    for file in setup.candidates {
+        let file = unsafe { KStr::new(file.as_ptr() as *const c_char) };
+        match rv!(execveat(AT_FDCWD, file, setup.argv, setup.envp, 0)) {
+            Err(error::DoesNotExist) | Err(error::NotADirectory) => { },
+            Err(error::AccessDenied) => denied = true,
+            Err(e) => return Err(e),
+            Ok(()) => { },
+        }
+    }
+    match denied {
+        true => Err(error::AccessDenied),
+        false => Err(error::DoesNotExist),
+    }
+}
+
+/// Copies bytes and appends a null byte.
+fn c_string(bytes: &[u8]) -> Result<Vec<u8>> {
+    if memchr(0, bytes).is_some() {
+        return Err(error::InvalidArgument);
+    }
+    let mut s = Vec::with_capacity(bytes.len() + 1);
+    s.extend_from_slice(bytes);
+    s.push(0);
+    Ok(s)
+}
+
+/// Creates a null-terminated array of pointers to null-terminated strings.
+fn c_string_ptrs(strings: &[Vec<u8>]) -> Vec<*const c_char> {
+    let mut ptrs: Vec<_> = strings.iter().map(|s| s.as_ptr() as *const c_char).collect();
+    ptrs.push(ptr::null());
+    ptrs
+}
+
+#[cfg(feature = "std")]
+fn inherited_env() -> Vec<(Vec<u8>, Vec<u8>)> {
+    use std::os::unix::ffi::{OsStringExt};
+    std::env::vars_os().map(|(k, v)| (k.into_vec(), v.into_vec())).collect()
+}
+
+#[cfg(not(feature = "std"))]
+fn inherited_env() -> Vec<(Vec<u8>, Vec<u8>)> {
+    Vec::new()
+}
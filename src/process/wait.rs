@@ -0,0 +1,339 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::ops::{BitOr, BitAnd, Not};
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    lmem,
+    signal::{Signal},
+    time::{Time},
+    util::{
+        retry::{retry},
+        error::{self},
+    },
+};
+use crate::kty::{
+    c_int, pid_t, uid_t, siginfo_t, rusage, timeval, P_ALL, P_PID, P_PGID, WEXITED,
+    WSTOPPED, WCONTINUED, WNOWAIT, WNOHANG, CLD_EXITED, CLD_KILLED, CLD_DUMPED,
+    CLD_TRAPPED, CLD_STOPPED, CLD_CONTINUED,
+};
+use crate::syscall::{waitid};
+
+/// The state change of a child.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// The child exited with an exit code.
+    Exited(c_int),
+    /// The child was killed by a signal.
+    ///
+    /// [field, 2]
+    /// Whether the child dumped core.
+    Killed(Signal, bool),
+    /// The child was stopped by a signal.
+    Stopped(Signal),
+    /// The child was continued by `SIGCONT`.
+    Continued,
+}
+
+impl ExitStatus {
+    /// Returns whether the child exited with exit code `0`.
+    pub fn success(self) -> bool {
+        self == ExitStatus::Exited(0)
+    }
+}
+
+/// A state change of a child returned by the wait functions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WaitStatus {
+    /// The process id of the child.
+    pub pid: pid_t,
+    /// The real user id of the child.
+    pub uid: uid_t,
+    /// The state change.
+    pub status: ExitStatus,
+}
+
+/// The children a wait function waits for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WaitId {
+    /// The child with the process id.
+    Pid(pid_t),
+    /// Any child in the process group.
+    Pgid(pid_t),
+    /// Any child.
+    Any,
+}
+
+/// Resource usage of a child.
+///
+/// = See also
+///
+/// * link:man:getrusage(2)
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct Usage {
+    usage: rusage,
+}
+
+fn time_from_timeval(t: timeval) -> Time {
+    Time {
+        seconds: t.tv_sec,
+        nanoseconds: t.tv_usec * 1000,
+    }
+}
+
+impl Usage {
+    /// Creates a new object with all fields set to zero.
+    pub fn new() -> Usage {
+        lmem::zeroed()
+    }
+
+    /// Returns the CPU time spent in user mode.
+    pub fn user_time(&self) -> Time {
+        time_from_timeval(self.usage.ru_utime)
+    }
+
+    /// Returns the CPU time spent in kernel mode.
+    pub fn system_time(&self) -> Time {
+        time_from_timeval(self.usage.ru_stime)
+    }
+
+    /// Returns the maximum resident set size in kilobytes.
+    pub fn max_rss(&self) -> u64 {
+        self.usage.ru_maxrss as u64
+    }
+
+    /// Returns the number of page faults that did not require I/O.
+    pub fn minor_faults(&self) -> u64 {
+        self.usage.ru_minflt as u64
+    }
+
+    /// Returns the number of page faults that required I/O.
+    pub fn major_faults(&self) -> u64 {
+        self.usage.ru_majflt as u64
+    }
+
+    /// Returns the number of blocks read from the file system.
+    pub fn blocks_in(&self) -> u64 {
+        self.usage.ru_inblock as u64
+    }
+
+    /// Returns the number of blocks written to the file system.
+    pub fn blocks_out(&self) -> u64 {
+        self.usage.ru_oublock as u64
+    }
+
+    /// Returns the number of voluntary context switches.
+    pub fn voluntary_switches(&self) -> u64 {
+        self.usage.ru_nvcsw as u64
+    }
+
+    /// Returns the number of involuntary context switches.
+    pub fn involuntary_switches(&self) -> u64 {
+        self.usage.ru_nivcsw as u64
+    }
+}
+
+impl Debug for Usage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Usage")
+            .field("user_time", &self.user_time())
+            .field("system_time", &self.system_time())
+            .field("max_rss", &self.max_rss())
+            .field("minor_faults", &self.minor_faults())
+            .field("major_faults", &self.major_faults())
+            .field("blocks_in", &self.blocks_in())
+            .field("blocks_out", &self.blocks_out())
+            .field("voluntary_switches", &self.voluntary_switches())
+            .field("involuntary_switches", &self.involuntary_switches())
+            .finish()
+    }
+}
+
+/// Flags for waiting for children.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct WaitFlags(pub c_int);
+
+impl BitOr for WaitFlags {
+    type Output = WaitFlags;
+    fn bitor(self, other: WaitFlags) -> WaitFlags {
+        WaitFlags(self.0 | other.0)
+    }
+}
+
+impl BitAnd for WaitFlags {
+    type Output = WaitFlags;
+    fn bitand(self, other: WaitFlags) -> WaitFlags {
+        WaitFlags(self.0 & other.0)
+    }
+}
+
+impl Not for WaitFlags {
+    type Output = WaitFlags;
+    fn not(self) -> WaitFlags {
+        WaitFlags(!self.0)
+    }
+}
+
+macro_rules! create_flags {
+    ($($(#[$meta:meta])* flag $name:ident = $val:expr;)*) => {
+        $($(#[$meta])* pub const $name: WaitFlags = WaitFlags($val);)*
+
+        impl Debug for WaitFlags {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let mut first = true;
+                $(
+                    if self.0 & $val != 0 {
+                        if !first { core::write!(f, "|")?; }
+                        first = false;
+                        core::write!(f, stringify!($name))?;
+                    }
+                )*
+                if first {
+                    core::write!(f, "0")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+create_flags! {
+    #[doc = "Wait for children that have terminated.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:waitid(2) and WEXITED therein"]
+    flag WAIT_EXITED = WEXITED;
+
+    #[doc = "Wait for children that have been stopped by a signal.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:waitid(2) and WSTOPPED therein"]
+    flag WAIT_STOPPED = WSTOPPED;
+
+    #[doc = "Wait for children that have been continued by `SIGCONT`.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:waitid(2) and WCONTINUED therein"]
+    flag WAIT_CONTINUED = WCONTINUED;
+
+    #[doc = "Leave the child in a waitable state.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "A later call can retrieve the same state change again. In particular, a \
+             terminated child is not reaped.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:waitid(2) and WNOWAIT therein"]
+    flag WAIT_NO_WAIT = WNOWAIT;
+}
+
+/// Waits for a state change of a specific child.
+///
+/// [argument, pid]
+/// The process id of the child.
+///
+/// [argument, flags]
+/// The state changes to wait for.
+///
+/// [argument, usage]
+/// An optional place where the resource usage of the child will be stored.
+///
+/// = Remarks
+///
+/// `flags` must contain at least one of `WAIT_EXITED`, `WAIT_STOPPED`, and
+/// `WAIT_CONTINUED`.
+///
+/// = See also
+///
+/// * link:man:waitid(2)
+pub fn wait_pid(pid: pid_t, flags: WaitFlags,
+                usage: Option<&mut Usage>) -> Result<WaitStatus> {
+    wait(P_PID, pid, flags.0, usage).and_then(|s| s.ok_or(error::InvalidArgument))
+}
+
+/// Waits for a state change of any child.
+///
+/// [argument, flags]
+/// The state changes to wait for.
+///
+/// [argument, usage]
+/// An optional place where the resource usage of the child will be stored.
+///
+/// = See also
+///
+/// * link:man:waitid(2)
+/// * link:lrs::process::wait_pid
+pub fn wait_any(flags: WaitFlags, usage: Option<&mut Usage>) -> Result<WaitStatus> {
+    wait(P_ALL, 0, flags.0, usage).and_then(|s| s.ok_or(error::InvalidArgument))
+}
+
+/// Waits for a state change of any child in a process group.
+///
+/// [argument, pgid]
+/// The process group.
+///
+/// [argument, flags]
+/// The state changes to wait for.
+///
+/// [argument, usage]
+/// An optional place where the resource usage of the child will be stored.
+///
+/// = See also
+///
+/// * link:man:waitid(2)
+/// * link:lrs::process::wait_pid
+pub fn wait_pgid(pgid: pid_t, flags: WaitFlags,
+                 usage: Option<&mut Usage>) -> Result<WaitStatus> {
+    wait(P_PGID, pgid, flags.0, usage).and_then(|s| s.ok_or(error::InvalidArgument))
+}
+
+/// Retrieves a state change of a child without blocking.
+///
+/// [argument, id]
+/// The children whose state changes will be retrieved.
+///
+/// [argument, flags]
+/// The state changes to retrieve.
+///
+/// [argument, usage]
+/// An optional place where the resource usage of the child will be stored.
+///
+/// [return_value]
+/// Returns the state change or `None` if no child has changed its state.
+///
+/// = See also
+///
+/// * link:man:waitid(2) and WNOHANG therein
+/// * link:lrs::process::wait_pid
+pub fn try_wait(id: WaitId, flags: WaitFlags,
+                usage: Option<&mut Usage>) -> Result<Option<WaitStatus>> {
+    let (which, id) = match id {
+        WaitId::Pid(pid) => (P_PID, pid),
+        WaitId::Pgid(pgid) => (P_PGID, pgid),
+        WaitId::Any => (P_ALL, 0),
+    };
+    wait(which, id, flags.0 | WNOHANG, usage)
+}
+
+fn wait(which: c_int, id: pid_t, flags: c_int,
+        mut usage: Option<&mut Usage>) -> Result<Option<WaitStatus>> {
+    let mut info: siginfo_t = lmem::zeroed();
+    retry(|| waitid(which, id, &mut info, flags,
+                    usage.as_mut().map(|u| &mut u.usage)))?;
+    let pid = unsafe { info.si_pid() };
+    if pid == 0 {
+        return Ok(None);
+    }
+    let (code, status) = (info.si_code(), unsafe { info.si_status() });
+    let signal = || Signal::from_raw(status).ok_or(error::InvalidArgument);
+    let status = match code {
+        CLD_EXITED => ExitStatus::Exited(status),
+        CLD_KILLED => ExitStatus::Killed(signal()?, false),
+        CLD_DUMPED => ExitStatus::Killed(signal()?, true),
+        CLD_STOPPED | CLD_TRAPPED => ExitStatus::Stopped(signal()?),
+        CLD_CONTINUED => ExitStatus::Continued,
+        _ => return Err(error::InvalidArgument),
+    };
+    Ok(Some(WaitStatus {
+        pid,
+        uid: unsafe { info.si_uid() },
+        status,
+    }))
+}
@@ -0,0 +1,440 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+pub use self::info::{SignalInfo};
+pub use self::signalfd::{SignalFd};
+pub use self::handler::{
+    Handler, HandlerFn, Action, Foreign, set_handler, get_handler,
+};
+
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    lmem,
+    time::{Time, time_to_timespec},
+    util::error::{self},
+};
+use crate::kty::{
+    c_int, sigset_t, siginfo_t, _NSIG, _NSIG_BPW, SIG_BLOCK, SIG_UNBLOCK, SIG_SETMASK,
+    SIGHUP, SIGINT, SIGQUIT, SIGILL, SIGTRAP, SIGABRT, SIGBUS, SIGFPE, SIGKILL, SIGUSR1,
+    SIGSEGV, SIGUSR2, SIGPIPE, SIGALRM, SIGTERM, SIGSTKFLT, SIGCHLD, SIGCONT, SIGSTOP,
+    SIGTSTP, SIGTTIN, SIGTTOU, SIGURG, SIGXCPU, SIGXFSZ, SIGVTALRM, SIGPROF, SIGWINCH,
+    SIGIO, SIGPWR, SIGSYS, SIGRTMIN, SIGRTMAX,
+};
+use crate::syscall::{rt_sigprocmask, rt_sigpending, rt_sigsuspend, rt_sigtimedwait};
+
+mod info;
+mod signalfd;
+mod handler;
+
+/// A signal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// Hangup of the controlling terminal (`SIGHUP`).
+    Hup,
+    /// Interrupt from the keyboard (`SIGINT`).
+    Int,
+    /// Quit from the keyboard (`SIGQUIT`).
+    Quit,
+    /// Illegal instruction (`SIGILL`).
+    Ill,
+    /// Breakpoint or trace trap (`SIGTRAP`).
+    Trap,
+    /// Abort (`SIGABRT`).
+    Abrt,
+    /// Bus error (`SIGBUS`).
+    Bus,
+    /// Arithmetic exception (`SIGFPE`).
+    Fpe,
+    /// Kill (`SIGKILL`).
+    Kill,
+    /// First user-defined signal (`SIGUSR1`).
+    Usr1,
+    /// Invalid memory reference (`SIGSEGV`).
+    Segv,
+    /// Second user-defined signal (`SIGUSR2`).
+    Usr2,
+    /// Write to a pipe without readers (`SIGPIPE`).
+    Pipe,
+    /// Timer from `alarm` (`SIGALRM`).
+    Alrm,
+    /// Termination request (`SIGTERM`).
+    Term,
+    /// Coprocessor stack fault (`SIGSTKFLT`).
+    StkFlt,
+    /// Child stopped or terminated (`SIGCHLD`).
+    Chld,
+    /// Continue if stopped (`SIGCONT`).
+    Cont,
+    /// Stop (`SIGSTOP`).
+    Stop,
+    /// Stop from the terminal (`SIGTSTP`).
+    Tstp,
+    /// Terminal input for a background process (`SIGTTIN`).
+    Ttin,
+    /// Terminal output for a background process (`SIGTTOU`).
+    Ttou,
+    /// Urgent condition on a socket (`SIGURG`).
+    Urg,
+    /// CPU time limit exceeded (`SIGXCPU`).
+    Xcpu,
+    /// File size limit exceeded (`SIGXFSZ`).
+    Xfsz,
+    /// Virtual alarm clock (`SIGVTALRM`).
+    Vtalrm,
+    /// Profiling timer expired (`SIGPROF`).
+    Prof,
+    /// Window size change (`SIGWINCH`).
+    Winch,
+    /// I/O is possible (`SIGIO`).
+    Io,
+    /// Power failure (`SIGPWR`).
+    Pwr,
+    /// Bad system call (`SIGSYS`).
+    Sys,
+    /// A real-time signal.
+    ///
+    /// [field, 1]
+    /// The offset from `SIGRTMIN`.
+    ///
+    /// = Remarks
+    ///
+    /// Note that libc implementations reserve the first few real-time signals for
+    /// internal use.
+    Realtime(u8),
+}
+
+impl Signal {
+    /// Creates a signal from a signal number.
+    ///
+    /// [argument, sig]
+    /// The signal number.
+    ///
+    /// [return_value]
+    /// Returns the signal or `None` if the number is not a valid signal number.
+    pub fn from_raw(sig: c_int) -> Option<Signal> {
+        let sig = match sig {
+            SIGHUP    => Signal::Hup,
+            SIGINT    => Signal::Int,
+            SIGQUIT   => Signal::Quit,
+            SIGILL    => Signal::Ill,
+            SIGTRAP   => Signal::Trap,
+            SIGABRT   => Signal::Abrt,
+            SIGBUS    => Signal::Bus,
+            SIGFPE    => Signal::Fpe,
+            SIGKILL   => Signal::Kill,
+            SIGUSR1   => Signal::Usr1,
+            SIGSEGV   => Signal::Segv,
+            SIGUSR2   => Signal::Usr2,
+            SIGPIPE   => Signal::Pipe,
+            SIGALRM   => Signal::Alrm,
+            SIGTERM   => Signal::Term,
+            SIGSTKFLT => Signal::StkFlt,
+            SIGCHLD   => Signal::Chld,
+            SIGCONT   => Signal::Cont,
+            SIGSTOP   => Signal::Stop,
+            SIGTSTP   => Signal::Tstp,
+            SIGTTIN   => Signal::Ttin,
+            SIGTTOU   => Signal::Ttou,
+            SIGURG    => Signal::Urg,
+            SIGXCPU   => Signal::Xcpu,
+            SIGXFSZ   => Signal::Xfsz,
+            SIGVTALRM => Signal::Vtalrm,
+            SIGPROF   => Signal::Prof,
+            SIGWINCH  => Signal::Winch,
+            SIGIO     => Signal::Io,
+            SIGPWR    => Signal::Pwr,
+            SIGSYS    => Signal::Sys,
+            _ if sig >= SIGRTMIN && sig <= SIGRTMAX => {
+                Signal::Realtime((sig - SIGRTMIN) as u8)
+            },
+            _ => return None,
+        };
+        Some(sig)
+    }
+
+    /// Returns the signal number of the signal.
+    ///
+    /// = Remarks
+    ///
+    /// For `Realtime` signals with an offset that is out of range, the returned number
+    /// is not a valid signal number and will be rejected by the kernel.
+    pub fn to_raw(self) -> c_int {
+        match self {
+            Signal::Hup         => SIGHUP,
+            Signal::Int         => SIGINT,
+            Signal::Quit        => SIGQUIT,
+            Signal::Ill         => SIGILL,
+            Signal::Trap        => SIGTRAP,
+            Signal::Abrt        => SIGABRT,
+            Signal::Bus         => SIGBUS,
+            Signal::Fpe         => SIGFPE,
+            Signal::Kill        => SIGKILL,
+            Signal::Usr1        => SIGUSR1,
+            Signal::Segv        => SIGSEGV,
+            Signal::Usr2        => SIGUSR2,
+            Signal::Pipe        => SIGPIPE,
+            Signal::Alrm        => SIGALRM,
+            Signal::Term        => SIGTERM,
+            Signal::StkFlt      => SIGSTKFLT,
+            Signal::Chld        => SIGCHLD,
+            Signal::Cont        => SIGCONT,
+            Signal::Stop        => SIGSTOP,
+            Signal::Tstp        => SIGTSTP,
+            Signal::Ttin        => SIGTTIN,
+            Signal::Ttou        => SIGTTOU,
+            Signal::Urg         => SIGURG,
+            Signal::Xcpu        => SIGXCPU,
+            Signal::Xfsz        => SIGXFSZ,
+            Signal::Vtalrm      => SIGVTALRM,
+            Signal::Prof        => SIGPROF,
+            Signal::Winch       => SIGWINCH,
+            Signal::Io          => SIGIO,
+            Signal::Pwr         => SIGPWR,
+            Signal::Sys         => SIGSYS,
+            Signal::Realtime(n) => SIGRTMIN + n as c_int,
+        }
+    }
+}
+
+/// A set of signals.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct SigSet {
+    set: sigset_t,
+}
+
+impl SigSet {
+    /// Creates an empty set.
+    pub fn empty() -> SigSet {
+        SigSet { set: lmem::zeroed() }
+    }
+
+    /// Creates a set containing all signals.
+    pub fn full() -> SigSet {
+        let mut set = SigSet::empty();
+        for word in &mut set.set.sig[..] {
+            *word = !0;
+        }
+        set
+    }
+
+    /// Creates a set from a kernel `sigset_t`.
+    ///
+    /// [argument, set]
+    /// The kernel set.
+    pub fn from_raw(set: sigset_t) -> SigSet {
+        SigSet { set }
+    }
+
+    /// Returns the underlying kernel `sigset_t`.
+    pub fn as_raw(&self) -> &sigset_t {
+        &self.set
+    }
+
+    fn pos(sig: Signal) -> Option<(usize, usize)> {
+        let bit = (sig.to_raw() - 1) as usize;
+        match bit < _NSIG {
+            true => Some((bit / _NSIG_BPW, bit % _NSIG_BPW)),
+            false => None,
+        }
+    }
+
+    /// Adds a signal to the set.
+    ///
+    /// [argument, sig]
+    /// The signal to add.
+    pub fn add(&mut self, sig: Signal) {
+        if let Some((word, bit)) = SigSet::pos(sig) {
+            self.set.sig[word] |= 1 << bit;
+        }
+    }
+
+    /// Removes a signal from the set.
+    ///
+    /// [argument, sig]
+    /// The signal to remove.
+    pub fn remove(&mut self, sig: Signal) {
+        if let Some((word, bit)) = SigSet::pos(sig) {
+            self.set.sig[word] &= !(1 << bit);
+        }
+    }
+
+    /// Returns whether the set contains a signal.
+    ///
+    /// [argument, sig]
+    /// The signal to check.
+    pub fn contains(&self, sig: Signal) -> bool {
+        match SigSet::pos(sig) {
+            Some((word, bit)) => self.set.sig[word] & (1 << bit) != 0,
+            _ => false,
+        }
+    }
+
+    /// Returns whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.set.sig.iter().all(|&w| w == 0)
+    }
+
+    /// Returns an iterator over the signals in the set.
+    pub fn iter(&self) -> SigSetIter {
+        SigSetIter { set: *self, next: 1 }
+    }
+}
+
+impl<'a> IntoIterator for &'a SigSet {
+    type Item = Signal;
+    type IntoIter = SigSetIter;
+
+    fn into_iter(self) -> SigSetIter {
+        self.iter()
+    }
+}
+
+impl Debug for SigSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the signals in a `SigSet`.
+pub struct SigSetIter {
+    set: SigSet,
+    next: c_int,
+}
+
+impl Iterator for SigSetIter {
+    type Item = Signal;
+
+    fn next(&mut self) -> Option<Signal> {
+        while self.next as usize <= _NSIG {
+            let sig = Signal::from_raw(self.next);
+            self.next += 1;
+            if let Some(sig) = sig {
+                if self.set.contains(sig) {
+                    return Some(sig);
+                }
+            }
+        }
+        None
+    }
+}
+
+fn sigprocmask(how: c_int, set: &SigSet) -> Result<SigSet> {
+    let mut old = SigSet::empty();
+    rv!(rt_sigprocmask(how, Some(&set.set), Some(&mut old.set)))?;
+    Ok(old)
+}
+
+/// Adds signals to the signal mask of the calling thread.
+///
+/// [argument, set]
+/// The signals to block.
+///
+/// [return_value]
+/// Returns the previous signal mask.
+///
+/// = Remarks
+///
+/// `SIGKILL` and `SIGSTOP` cannot be blocked and are silently ignored.
+///
+/// = See also
+///
+/// * link:man:rt_sigprocmask(2) and SIG_BLOCK therein
+pub fn block(set: &SigSet) -> Result<SigSet> {
+    sigprocmask(SIG_BLOCK, set)
+}
+
+/// Removes signals from the signal mask of the calling thread.
+///
+/// [argument, set]
+/// The signals to unblock.
+///
+/// [return_value]
+/// Returns the previous signal mask.
+///
+/// = See also
+///
+/// * link:man:rt_sigprocmask(2) and SIG_UNBLOCK therein
+pub fn unblock(set: &SigSet) -> Result<SigSet> {
+    sigprocmask(SIG_UNBLOCK, set)
+}
+
+/// Replaces the signal mask of the calling thread.
+///
+/// [argument, set]
+/// The new signal mask.
+///
+/// [return_value]
+/// Returns the previous signal mask.
+///
+/// = See also
+///
+/// * link:man:rt_sigprocmask(2) and SIG_SETMASK therein
+pub fn set_mask(set: &SigSet) -> Result<SigSet> {
+    sigprocmask(SIG_SETMASK, set)
+}
+
+/// Returns the signal mask of the calling thread.
+///
+/// = See also
+///
+/// * link:man:rt_sigprocmask(2)
+pub fn mask() -> Result<SigSet> {
+    let mut cur = SigSet::empty();
+    rv!(rt_sigprocmask(SIG_BLOCK, None, Some(&mut cur.set)))?;
+    Ok(cur)
+}
+
+/// Returns the signals that are pending for the calling thread.
+///
+/// = See also
+///
+/// * link:man:rt_sigpending(2)
+pub fn pending() -> Result<SigSet> {
+    let mut set = SigSet::empty();
+    rv!(rt_sigpending(&mut set.set))?;
+    Ok(set)
+}
+
+/// Temporarily replaces the signal mask and waits for a signal handler to run.
+///
+/// [argument, set]
+/// The temporary signal mask.
+///
+/// = Remarks
+///
+/// On success, this function returns `EINTR` after a signal handler has returned.
+///
+/// = See also
+///
+/// * link:man:rt_sigsuspend(2)
+pub fn suspend(set: &SigSet) -> Result {
+    rv!(rt_sigsuspend(&set.set))
+}
+
+/// Waits for one of a set of signals to become pending and accepts it.
+///
+/// [argument, set]
+/// The signals to wait for.
+///
+/// [argument, timeout]
+/// The maximum time to wait or `None` to wait indefinitely.
+///
+/// [return_value]
+/// Returns the accepted signal.
+///
+/// = Remarks
+///
+/// The signals should be blocked before calling this function. If the timeout
+/// expires, `EAGAIN` is returned.
+///
+/// = See also
+///
+/// * link:man:rt_sigtimedwait(2)
+pub fn wait(set: &SigSet, timeout: Option<Time>) -> Result<Signal> {
+    let mut info: siginfo_t = lmem::zeroed();
+    let timeout = timeout.map(time_to_timespec);
+    let sig = rv!(rt_sigtimedwait(&set.set, &mut info, timeout.as_ref()), -> c_int)?;
+    Signal::from_raw(sig).ok_or(error::InvalidArgument)
+}
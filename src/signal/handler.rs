@@ -0,0 +1,367 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use core::fmt::{self, Debug, Formatter};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use crate::{
+    result::{Result},
+    lmem,
+    signal::{Signal, SigSet, SignalInfo},
+    util::error::{self},
+};
+use crate::kty::{
+    c_int, c_ulong, c_void, sigaction, siginfo_t, _NSIG, SIG_DFL, SIG_IGN, SA_SIGINFO,
+    SA_RESTORER, SA_RESTART, SA_ONSTACK, SA_NODEFER, SA_RESETHAND,
+};
+use crate::syscall::{rt_sigaction, rt_sigreturn_restorer};
+
+/// A function that handles a signal.
+///
+/// = Remarks
+///
+/// The function runs asynchronously with respect to the interrupted code and must only
+/// perform async-signal-safe operations. In particular, it must not allocate memory or
+/// acquire locks that might be held by the interrupted code.
+pub type HandlerFn = fn(&SignalInfo);
+
+/// The functions installed via `set_handler`, indexed by signal number minus one.
+const NO_HANDLER: AtomicUsize = AtomicUsize::new(0);
+static HANDLERS: [AtomicUsize; _NSIG] = [NO_HANDLER; _NSIG];
+
+/// The action performed when a signal is delivered.
+#[derive(Copy, Clone)]
+pub enum Action {
+    /// The default action of the signal.
+    Default,
+    /// The signal is discarded.
+    Ignore,
+    /// The signal is passed to a function.
+    Call(HandlerFn),
+    /// A handler that was not installed via this module.
+    Foreign(Foreign),
+}
+
+// Implemented by hand since `HandlerFn` is a higher-ranked function pointer.
+
+impl PartialEq for Action {
+    fn eq(&self, other: &Action) -> bool {
+        match (*self, *other) {
+            (Action::Default, Action::Default) => true,
+            (Action::Ignore, Action::Ignore) => true,
+            (Action::Call(a), Action::Call(b)) => a as usize == b as usize,
+            (Action::Foreign(a), Action::Foreign(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Action { }
+
+impl Debug for Action {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Action::Default => f.write_str("Default"),
+            Action::Ignore => f.write_str("Ignore"),
+            Action::Call(h) => core::write!(f, "Call({:#x})", h as usize),
+            Action::Foreign(ref h) => f.debug_tuple("Foreign").field(h).finish(),
+        }
+    }
+}
+
+/// A signal handler that was not installed via this module.
+///
+/// = Remarks
+///
+/// Such a handler can only be obtained from `set_handler` or `get_handler`. It can be
+/// re-installed by passing it back to `set_handler`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Foreign {
+    handler: usize,
+    restorer: usize,
+    flags: c_int,
+}
+
+impl Foreign {
+    /// Returns the address of the handler.
+    pub fn address(&self) -> usize {
+        self.handler
+    }
+}
+
+/// The disposition of a signal.
+///
+/// = Remarks
+///
+/// This type is a builder for the arguments of `set_handler`. By default, no flags are
+/// set and no additional signals are blocked while the handler runs.
+///
+/// = Examples
+///
+/// ----
+/// fn on_int(info: &SignalInfo) {
+///     // ...
+/// }
+///
+/// let mut handler = Handler::new(Action::Call(on_int));
+/// handler.restart(true);
+/// let old = set_handler(Signal::Int, handler)?;
+/// ----
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Handler {
+    action: Action,
+    flags: c_int,
+    mask: SigSet,
+}
+
+impl Handler {
+    /// Creates a new disposition.
+    ///
+    /// [argument, action]
+    /// The action to perform when the signal is delivered.
+    pub fn new(action: Action) -> Handler {
+        Handler {
+            action,
+            flags: 0,
+            mask: SigSet::empty(),
+        }
+    }
+
+    fn from_raw(act: &sigaction, func: usize) -> Handler {
+        let flags = act.sa_flags as c_int;
+        let action = match act.sa_handler {
+            SIG_DFL => Action::Default,
+            SIG_IGN => Action::Ignore,
+            h if h == dispatch_addr() && func != 0 => {
+                Action::Call(unsafe { mem::transmute::<usize, HandlerFn>(func) })
+            },
+            h => Action::Foreign(Foreign {
+                handler: h,
+                restorer: act.sa_restorer,
+                flags: flags & (SA_SIGINFO | SA_RESTORER),
+            }),
+        };
+        Handler {
+            action,
+            flags: flags & !(SA_SIGINFO | SA_RESTORER),
+            mask: SigSet::from_raw(act.sa_mask),
+        }
+    }
+
+    fn set_flag(&mut self, flag: c_int, val: bool) -> &mut Handler {
+        if val {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+        self
+    }
+
+    /// Returns the action of the disposition.
+    pub fn action(&self) -> Action {
+        self.action
+    }
+
+    /// Sets the action of the disposition.
+    ///
+    /// [argument, action]
+    /// The action to perform when the signal is delivered.
+    pub fn set_action(&mut self, action: Action) -> &mut Handler {
+        self.action = action;
+        self
+    }
+
+    /// Sets whether interrupted system calls are restarted.
+    ///
+    /// [argument, val]
+    /// Whether system calls are restarted after the handler returns.
+    ///
+    /// = See also
+    ///
+    /// * link:man:signal(7) and the section on system calls therein
+    pub fn restart(&mut self, val: bool) -> &mut Handler {
+        self.set_flag(SA_RESTART, val)
+    }
+
+    /// Returns whether interrupted system calls are restarted.
+    pub fn is_restart(&self) -> bool {
+        self.flags & SA_RESTART != 0
+    }
+
+    /// Sets whether the handler runs on the alternate signal stack.
+    ///
+    /// [argument, val]
+    /// Whether the handler runs on the alternate signal stack.
+    ///
+    /// = Remarks
+    ///
+    /// If no alternate signal stack has been installed, the handler runs on the
+    /// regular stack.
+    ///
+    /// = See also
+    ///
+    /// * link:man:sigaltstack(2)
+    pub fn on_stack(&mut self, val: bool) -> &mut Handler {
+        self.set_flag(SA_ONSTACK, val)
+    }
+
+    /// Returns whether the handler runs on the alternate signal stack.
+    pub fn is_on_stack(&self) -> bool {
+        self.flags & SA_ONSTACK != 0
+    }
+
+    /// Sets whether the signal is not blocked while its handler runs.
+    ///
+    /// [argument, val]
+    /// Whether the handler can be interrupted by the same signal.
+    pub fn no_defer(&mut self, val: bool) -> &mut Handler {
+        self.set_flag(SA_NODEFER, val)
+    }
+
+    /// Returns whether the signal is not blocked while its handler runs.
+    pub fn is_no_defer(&self) -> bool {
+        self.flags & SA_NODEFER != 0
+    }
+
+    /// Sets whether the disposition is reset to the default before the handler runs.
+    ///
+    /// [argument, val]
+    /// Whether the handler runs at most once.
+    pub fn reset_hand(&mut self, val: bool) -> &mut Handler {
+        self.set_flag(SA_RESETHAND, val)
+    }
+
+    /// Returns whether the disposition is reset to the default before the handler
+    /// runs.
+    pub fn is_reset_hand(&self) -> bool {
+        self.flags & SA_RESETHAND != 0
+    }
+
+    /// Sets the signals that are blocked while the handler runs.
+    ///
+    /// [argument, set]
+    /// The signals to block in addition to the signal itself.
+    pub fn block(&mut self, set: &SigSet) -> &mut Handler {
+        self.mask = *set;
+        self
+    }
+
+    /// Returns the signals that are blocked while the handler runs.
+    pub fn blocked(&self) -> SigSet {
+        self.mask
+    }
+}
+
+impl Debug for Handler {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Handler")
+            .field("action", &self.action)
+            .field("restart", &self.is_restart())
+            .field("on_stack", &self.is_on_stack())
+            .field("no_defer", &self.is_no_defer())
+            .field("reset_hand", &self.is_reset_hand())
+            .field("mask", &self.mask)
+            .finish()
+    }
+}
+
+/// The function that is installed in the kernel for all `Action::Call` handlers.
+extern "C" fn dispatch(sig: c_int, info: *mut siginfo_t, _ctx: *mut c_void) {
+    let func = match HANDLERS.get((sig - 1) as usize) {
+        Some(f) => f.load(Ordering::SeqCst),
+        _ => return,
+    };
+    if func == 0 || info.is_null() {
+        return;
+    }
+    let func: HandlerFn = unsafe { mem::transmute(func) };
+    if let Some(info) = SignalInfo::from_siginfo(unsafe { &*info }) {
+        func(&info);
+    }
+}
+
+fn dispatch_addr() -> usize {
+    let f: extern "C" fn(c_int, *mut siginfo_t, *mut c_void) = dispatch;
+    f as usize
+}
+
+fn handler_slot(sig: Signal) -> Result<&'static AtomicUsize> {
+    let sig = sig.to_raw();
+    if sig < 1 {
+        return Err(error::InvalidArgument);
+    }
+    HANDLERS.get((sig - 1) as usize).ok_or(error::InvalidArgument)
+}
+
+/// Changes the disposition of a signal.
+///
+/// [argument, sig]
+/// The signal whose disposition will be changed.
+///
+/// [argument, handler]
+/// The new disposition.
+///
+/// [return_value]
+/// Returns the previous disposition.
+///
+/// = Remarks
+///
+/// The disposition of `SIGKILL` and `SIGSTOP` cannot be changed.
+///
+/// Handlers always receive the decoded `siginfo_t` of the signal. They return via a
+/// restorer provided by this crate, i.e., `SA_RESTORER` is always set.
+///
+/// = See also
+///
+/// * link:man:rt_sigaction(2)
+pub fn set_handler(sig: Signal, handler: Handler) -> Result<Handler> {
+    let slot = handler_slot(sig)?;
+
+    let mut act: sigaction = lmem::zeroed();
+    let mut flags = handler.flags | SA_RESTORER;
+    act.sa_restorer = rt_sigreturn_restorer();
+    act.sa_mask = *handler.mask.as_raw();
+
+    let prev = slot.load(Ordering::SeqCst);
+    let mut func = prev;
+    act.sa_handler = match handler.action {
+        Action::Default => SIG_DFL,
+        Action::Ignore => SIG_IGN,
+        Action::Call(f) => {
+            func = f as usize;
+            flags |= SA_SIGINFO;
+            dispatch_addr()
+        },
+        Action::Foreign(f) => {
+            flags = (flags & !(SA_SIGINFO | SA_RESTORER)) | f.flags;
+            act.sa_restorer = f.restorer;
+            f.handler
+        },
+    };
+    act.sa_flags = flags as u32 as c_ulong;
+
+    // The new function has to be visible before the kernel can invoke `dispatch`.
+    slot.store(func, Ordering::SeqCst);
+    let mut old: sigaction = lmem::zeroed();
+    if let Err(e) = rv!(rt_sigaction(sig.to_raw(), Some(&act), Some(&mut old))) {
+        slot.store(prev, Ordering::SeqCst);
+        return Err(e);
+    }
+    Ok(Handler::from_raw(&old, prev))
+}
+
+/// Returns the disposition of a signal.
+///
+/// [argument, sig]
+/// The signal whose disposition will be returned.
+///
+/// = See also
+///
+/// * link:man:rt_sigaction(2)
+pub fn get_handler(sig: Signal) -> Result<Handler> {
+    let slot = handler_slot(sig)?;
+    let mut old: sigaction = lmem::zeroed();
+    rv!(rt_sigaction(sig.to_raw(), None, Some(&mut old)))?;
+    Ok(Handler::from_raw(&old, slot.load(Ordering::SeqCst)))
+}
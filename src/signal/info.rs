@@ -0,0 +1,138 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    signal::{Signal},
+};
+use crate::kty::{
+    c_int, pid_t, uid_t, signalfd_siginfo, siginfo_t, SI_QUEUE, SI_TIMER, SI_MESGQ,
+    SIGCHLD, SIGSEGV, SIGBUS, SIGILL, SIGFPE, SIGTRAP, SIGIO,
+};
+
+/// Information about a signal.
+///
+/// = Remarks
+///
+/// Which fields are meaningful depends on the signal and on `code`. Fields that are
+/// not meaningful are `0`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SignalInfo {
+    /// The signal.
+    pub signal: Signal,
+    /// The origin of the signal.
+    ///
+    /// = Remarks
+    ///
+    /// One of the `SI_*` constants or, for some signals, a signal-specific constant
+    /// such as `CLD_EXITED`.
+    pub code: c_int,
+    /// The errno value associated with the signal.
+    pub errno: c_int,
+    /// The process id of the sender.
+    pub pid: pid_t,
+    /// The real user id of the sender.
+    pub uid: uid_t,
+    /// The exit status or signal of a child (`SIGCHLD`).
+    pub status: c_int,
+    /// The user CPU time consumed by a child in clock ticks (`SIGCHLD`).
+    pub utime: u64,
+    /// The system CPU time consumed by a child in clock ticks (`SIGCHLD`).
+    pub stime: u64,
+    /// The integer value sent with `sigqueue`.
+    pub int: c_int,
+    /// The pointer value sent with `sigqueue`.
+    pub ptr: u64,
+    /// The address that caused the fault (`SIGSEGV`, `SIGBUS`, ...).
+    pub addr: u64,
+    /// The file descriptor for which the event occurred (`SIGIO`).
+    pub fd: c_int,
+}
+
+impl SignalInfo {
+    /// Decodes the structure read from a signalfd.
+    ///
+    /// [argument, info]
+    /// The structure.
+    ///
+    /// [return_value]
+    /// Returns the decoded information or `None` if the signal number is invalid.
+    pub fn from_raw(info: &signalfd_siginfo) -> Option<SignalInfo> {
+        Some(SignalInfo {
+            signal: Signal::from_raw(info.ssi_signo as c_int)?,
+            code: info.ssi_code,
+            errno: info.ssi_errno,
+            pid: info.ssi_pid as pid_t,
+            uid: info.ssi_uid as uid_t,
+            status: info.ssi_status,
+            utime: info.ssi_utime,
+            stime: info.ssi_stime,
+            int: info.ssi_int,
+            ptr: info.ssi_ptr,
+            addr: info.ssi_addr,
+            fd: info.ssi_fd,
+        })
+    }
+
+    /// Decodes the structure passed to a signal handler.
+    ///
+    /// [argument, info]
+    /// The structure.
+    ///
+    /// [return_value]
+    /// Returns the decoded information or `None` if the signal number is invalid.
+    ///
+    /// = Remarks
+    ///
+    /// `siginfo_t` contains a union whose active member depends on the signal and on
+    /// the code. Only the fields of the active member are decoded.
+    pub fn from_siginfo(info: &siginfo_t) -> Option<SignalInfo> {
+        let signo = info.si_signo();
+        let code = info.si_code();
+        let mut res = SignalInfo {
+            signal: Signal::from_raw(signo)?,
+            code,
+            errno: info.si_errno(),
+            pid: 0,
+            uid: 0,
+            status: 0,
+            utime: 0,
+            stime: 0,
+            int: 0,
+            ptr: 0,
+            addr: 0,
+            fd: 0,
+        };
+        unsafe {
+            if code <= 0 {
+                // Sent by a process via kill, sigqueue, etc.
+                if code != SI_TIMER {
+                    res.pid = info.si_pid();
+                    res.uid = info.si_uid();
+                }
+                if code == SI_QUEUE || code == SI_TIMER || code == SI_MESGQ {
+                    res.int = info.si_int();
+                    res.ptr = info.si_ptr() as u64;
+                }
+                return Some(res);
+            }
+            match signo {
+                SIGCHLD => {
+                    res.pid = info.si_pid();
+                    res.uid = info.si_uid();
+                    res.status = info.si_status();
+                    res.utime = info.si_utime() as u64;
+                    res.stime = info.si_stime() as u64;
+                },
+                SIGSEGV | SIGBUS | SIGILL | SIGFPE | SIGTRAP => {
+                    res.addr = info.si_addr() as u64;
+                },
+                SIGIO => {
+                    res.fd = info.si_fd();
+                },
+                _ => { },
+            }
+        }
+        Some(res)
+    }
+}
@@ -0,0 +1,108 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use crate::{
+    result::{Result},
+    lmem,
+    fd::{FdContainer},
+    signal::{SigSet, SignalInfo},
+    util::{
+        retry::{retry},
+        error::{self},
+    },
+};
+use crate::kty::{c_int, signalfd_siginfo, SFD_NONBLOCK};
+use crate::syscall::{signalfd4, read, close};
+
+/// A signalfd.
+///
+/// = Remarks
+///
+/// Signals in the mask of the signalfd should be blocked in all threads. Otherwise
+/// they are delivered via their default disposition.
+///
+/// = See also
+///
+/// * link:man:signalfd(2)
+pub struct SignalFd {
+    fd: c_int,
+    owned: bool,
+}
+
+impl SignalFd {
+    /// Creates a new signalfd.
+    ///
+    /// [argument, set]
+    /// The signals to accept via the signalfd.
+    pub fn new(set: &SigSet) -> Result<SignalFd> {
+        let fd = rv!(signalfd4(-1, set.as_raw(), 0), -> c_int)?;
+        Ok(SignalFd::from_owned(fd))
+    }
+
+    /// Creates a new non-blocking signalfd.
+    ///
+    /// [argument, set]
+    /// The signals to accept via the signalfd.
+    pub fn new_non_blocking(set: &SigSet) -> Result<SignalFd> {
+        let fd = rv!(signalfd4(-1, set.as_raw(), SFD_NONBLOCK), -> c_int)?;
+        Ok(SignalFd::from_owned(fd))
+    }
+
+    /// Replaces the set of signals accepted via the signalfd.
+    ///
+    /// [argument, set]
+    /// The new set of signals.
+    pub fn set_mask(&self, set: &SigSet) -> Result {
+        rv!(signalfd4(self.fd, set.as_raw(), 0))
+    }
+
+    /// Reads a signal from the signalfd.
+    ///
+    /// = Remarks
+    ///
+    /// If the signalfd is non-blocking and no signal is pending, `EAGAIN` is returned.
+    pub fn read(&self) -> Result<SignalInfo> {
+        let mut info: signalfd_siginfo = lmem::zeroed();
+        let n = retry(|| read(self.fd, lmem::as_mut_data(&mut info)))? as usize;
+        if n != mem::size_of::<signalfd_siginfo>() {
+            return Err(error::InvalidArgument);
+        }
+        SignalInfo::from_raw(&info).ok_or(error::InvalidArgument)
+    }
+}
+
+impl Drop for SignalFd {
+    fn drop(&mut self) {
+        if self.owned {
+            close(self.fd);
+        }
+    }
+}
+
+impl From<SignalFd> for c_int {
+    fn from(sfd: SignalFd) -> Self {
+        let fd = sfd.fd;
+        mem::forget(sfd);
+        fd
+    }
+}
+
+impl FdContainer for SignalFd {
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    fn borrow(&self) -> c_int {
+        self.fd
+    }
+
+    fn from_owned(fd: c_int) -> SignalFd {
+        SignalFd { fd, owned: true }
+    }
+
+    fn from_borrowed(fd: c_int) -> SignalFd {
+        SignalFd { fd, owned: false }
+    }
+}
@@ -0,0 +1,325 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+pub use self::addr::{SockAddr, SockAddrBuf, IpAddr, Ipv4Addr, Ipv6Addr, UnixPath};
+pub use self::flags::{
+    MsgFlags, MSG_NONE, MSG_OUT_OF_BAND, MSG_PEEK, MSG_DONT_ROUTE, MSG_TRUNCATE,
+    MSG_DONT_WAIT, MSG_END_OF_RECORD, MSG_WAIT_ALL, MSG_CONFIRM, MSG_ERR_QUEUE,
+    MSG_NO_SIGNAL, MSG_MORE, MSG_WAIT_FOR_ONE,
+};
+pub use self::cmsg::{
+    CmsgBuffer, Cmsgs, Cmsg, Rights, Credentials, PktInfo, cmsg_space,
+};
+pub use self::msg::{send_msg, recv_msg};
+#[cfg(feature = "alloc")]
+pub use self::batch::{MsgBatch, Received};
+pub use self::common::{Shutdown};
+pub use self::opt::{SockOpt, GetSockOpt, SetSockOpt, get_opt, set_opt};
+pub use self::tcp_info::{TcpInfo, TcpState};
+pub use self::tcp::{TcpListener, TcpStream};
+pub use self::udp::{UdpSocket};
+pub use self::unix::{
+    UnixListener, UnixStream, UnixDatagram, UnixSeqpacketListener, UnixSeqpacket,
+};
+pub use self::netlink::{NetlinkSocket};
+
+/// Implements ownership, `FdContainer`, and the methods shared by all sockets.
+///
+/// = Remarks
+///
+/// The `@base` form omits `local_addr` for sockets whose addresses cannot be
+/// represented by `SockAddr`.
+macro_rules! impl_socket {
+    ($ty:ident) => {
+        impl $ty {
+            /// Returns the address the socket is bound to.
+            ///
+            /// = See also
+            ///
+            /// * link:man:getsockname(2)
+            pub fn local_addr(&self) -> Result<SockAddr> {
+                common::local_addr(self.fd)
+            }
+        }
+
+        impl_socket!(@base $ty);
+    };
+    (@base $ty:ident) => {
+        impl $ty {
+            /// Enables or disables non-blocking mode.
+            ///
+            /// [argument, val]
+            /// Whether operations on the socket return `EAGAIN` instead of blocking.
+            pub fn set_non_blocking(&self, val: bool) -> Result {
+                common::set_non_blocking(self.fd, val)
+            }
+
+            /// Returns whether the socket is in non-blocking mode.
+            pub fn is_non_blocking(&self) -> Result<bool> {
+                common::is_non_blocking(self.fd)
+            }
+
+            /// Retrieves a socket option.
+            ///
+            /// = See also
+            ///
+            /// * link:lrs::socket::get_opt
+            pub fn get_opt<O: GetSockOpt>(&self) -> Result<O::Value> {
+                get_opt::<O>(self.fd)
+            }
+
+            /// Sets a socket option.
+            ///
+            /// [argument, val]
+            /// The new value of the option.
+            ///
+            /// = See also
+            ///
+            /// * link:lrs::socket::set_opt
+            pub fn set_opt<O: SetSockOpt>(&self, val: O::Value) -> Result {
+                set_opt::<O>(self.fd, val)
+            }
+        }
+
+        impl Drop for $ty {
+            fn drop(&mut self) {
+                if self.owned {
+                    close(self.fd);
+                }
+            }
+        }
+
+        impl From<$ty> for c_int {
+            fn from(sock: $ty) -> Self {
+                let fd = sock.fd;
+                core::mem::forget(sock);
+                fd
+            }
+        }
+
+        impl FdContainer for $ty {
+            fn is_owned(&self) -> bool {
+                self.owned
+            }
+
+            fn borrow(&self) -> c_int {
+                self.fd
+            }
+
+            fn from_owned(fd: c_int) -> $ty {
+                $ty { fd, owned: true }
+            }
+
+            fn from_borrowed(fd: c_int) -> $ty {
+                $ty { fd, owned: false }
+            }
+        }
+    };
+}
+
+/// Implements the methods of listening sockets.
+macro_rules! impl_listener {
+    ($ty:ident, $stream:ident) => {
+        impl $ty {
+            /// Accepts a connection.
+            ///
+            /// [return_value]
+            /// Returns the connected socket and the address of the peer.
+            ///
+            /// = See also
+            ///
+            /// * link:man:accept4(2)
+            pub fn accept(&self) -> Result<($stream, SockAddr)> {
+                let (fd, addr) = common::accept(self.fd)?;
+                Ok(($stream::from_owned(fd), addr))
+            }
+        }
+    }
+}
+
+/// Implements the methods of connected sockets and `Read`/`Write`.
+///
+/// = Remarks
+///
+/// Writes never raise `SIGPIPE`. Instead, `EPIPE` is returned.
+macro_rules! impl_connected {
+    ($ty:ident) => {
+        impl $ty {
+            /// Returns the address of the peer.
+            ///
+            /// = See also
+            ///
+            /// * link:man:getpeername(2)
+            pub fn peer_addr(&self) -> Result<SockAddr> {
+                common::peer_addr(self.fd)
+            }
+
+            /// Shuts down one or both directions of the connection.
+            ///
+            /// [argument, how]
+            /// The directions to shut down.
+            ///
+            /// = See also
+            ///
+            /// * link:man:shutdown(2)
+            pub fn shutdown(&self, how: Shutdown) -> Result {
+                common::shutdown(self.fd, how)
+            }
+        }
+
+        impl_connected!(@io $ty);
+        impl_connected!(@io &$ty);
+    };
+    (@io $ty:ty) => {
+        impl Read for $ty {
+            fn scatter_read(&mut self, bufs: &mut [&mut [d8]]) -> Result<usize> {
+                retry(|| readv(self.fd, bufs)).map(|r| r as usize)
+            }
+
+            fn read(&mut self, buf: &mut [d8]) -> Result<usize> {
+                common::recv(self.fd, buf, MSG_NONE)
+            }
+        }
+
+        impl_std_read!($ty);
+
+        impl Write for $ty {
+            fn gather_write(&mut self, bufs: &[&[u8]]) -> Result<usize> {
+                send_msg(self.fd, d8::from_byte_slice_slice(bufs), None, None,
+                         MSG_NO_SIGNAL)
+            }
+
+            fn write(&mut self, buf: &[u8]) -> Result<usize> {
+                common::send_to(self.fd, buf, None, MSG_NO_SIGNAL)
+            }
+
+            fn flush(&mut self) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        impl_std_write!($ty);
+    };
+}
+
+/// Implements the methods of datagram sockets and `Read`/`Write`.
+///
+/// = Remarks
+///
+/// `Read` and `Write` receive and send single datagrams and require the socket to be
+/// connected.
+macro_rules! impl_datagram {
+    ($ty:ident) => {
+        impl $ty {
+            /// Sets the default destination and limits incoming datagrams to it.
+            ///
+            /// [argument, addr]
+            /// The peer.
+            ///
+            /// = See also
+            ///
+            /// * link:man:connect(2)
+            pub fn connect(&self, addr: &SockAddr) -> Result {
+                common::connect(self.fd, addr)
+            }
+
+            /// Returns the address of the peer the socket is connected to.
+            ///
+            /// = See also
+            ///
+            /// * link:man:getpeername(2)
+            pub fn peer_addr(&self) -> Result<SockAddr> {
+                common::peer_addr(self.fd)
+            }
+
+            /// Sends a datagram to the connected peer.
+            ///
+            /// [argument, buf]
+            /// The payload.
+            ///
+            /// [return_value]
+            /// Returns the number of bytes sent.
+            ///
+            /// = See also
+            ///
+            /// * link:man:send(2)
+            pub fn send(&self, buf: &[u8]) -> Result<usize> {
+                common::send_to(self.fd, buf, None, MSG_NO_SIGNAL)
+            }
+
+            /// Sends a datagram to an address.
+            ///
+            /// [argument, buf]
+            /// The payload.
+            ///
+            /// [argument, addr]
+            /// The destination.
+            ///
+            /// [return_value]
+            /// Returns the number of bytes sent.
+            ///
+            /// = See also
+            ///
+            /// * link:man:sendto(2)
+            pub fn send_to(&self, buf: &[u8], addr: &SockAddr) -> Result<usize> {
+                common::send_to(self.fd, buf, Some(addr), MSG_NO_SIGNAL)
+            }
+
+            /// Receives a datagram.
+            ///
+            /// [argument, buf]
+            /// The buffer into which the payload is received.
+            ///
+            /// [return_value]
+            /// Returns the number of bytes received.
+            ///
+            /// = Remarks
+            ///
+            /// If the datagram is longer than the buffer, the excess bytes are
+            /// discarded.
+            ///
+            /// = See also
+            ///
+            /// * link:man:recv(2)
+            pub fn recv(&self, buf: &mut [d8]) -> Result<usize> {
+                common::recv(self.fd, buf, MSG_NONE)
+            }
+
+            /// Receives a datagram and its source address.
+            ///
+            /// [argument, buf]
+            /// The buffer into which the payload is received.
+            ///
+            /// [return_value]
+            /// Returns the number of bytes received and the source of the datagram.
+            ///
+            /// = See also
+            ///
+            /// * link:man:recvfrom(2)
+            pub fn recv_from(&self, buf: &mut [d8]) -> Result<(usize, SockAddr)> {
+                common::recv_from(self.fd, buf, MSG_NONE)
+            }
+        }
+
+        impl_connected!(@io $ty);
+        impl_connected!(@io &$ty);
+    }
+}
+
+pub mod opt;
+#[cfg(feature = "alloc")]
+pub mod rtnl;
+
+mod addr;
+mod flags;
+mod cmsg;
+mod msg;
+#[cfg(feature = "alloc")]
+mod batch;
+mod common;
+mod tcp_info;
+mod tcp;
+mod udp;
+mod unix;
+mod netlink;
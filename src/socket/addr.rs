@@ -0,0 +1,777 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem, str, cmp};
+use core::hash::{Hash, Hasher};
+use core::fmt::{self, Debug, Display, Formatter};
+use memchr::{memchr};
+use crate::{
+    result::{Result},
+    lmem,
+    parse::{Parsable},
+    util::{
+        data::{d8},
+        error::{self},
+    },
+};
+use crate::kty::{
+    c_int, c_char, sa_family_t, sockaddr_in, sockaddr_in6, sockaddr_un, in_addr, in6_addr,
+    __kernel_sockaddr_storage, AF_UNIX, AF_INET, AF_INET6, UNIX_PATH_MAX,
+};
+
+/// An IPv4 address.
+///
+/// [field, 1]
+/// The bytes of the address in network order.
+#[derive(Pod, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ipv4Addr(pub [u8; 4]);
+
+impl Ipv4Addr {
+    /// Creates a new address.
+    pub fn new(a: u8, b: u8, c: u8, d: u8) -> Ipv4Addr {
+        Ipv4Addr([a, b, c, d])
+    }
+
+    /// Returns the unspecified address `0.0.0.0`.
+    pub fn any() -> Ipv4Addr {
+        Ipv4Addr([0, 0, 0, 0])
+    }
+
+    /// Returns the loopback address `127.0.0.1`.
+    pub fn localhost() -> Ipv4Addr {
+        Ipv4Addr([127, 0, 0, 1])
+    }
+
+    /// Returns the broadcast address `255.255.255.255`.
+    pub fn broadcast() -> Ipv4Addr {
+        Ipv4Addr([255, 255, 255, 255])
+    }
+
+    /// Returns whether this is the unspecified address.
+    pub fn is_unspecified(&self) -> bool {
+        self.0 == [0, 0, 0, 0]
+    }
+
+    /// Returns whether this is a loopback address (`127.0.0.0/8`).
+    pub fn is_loopback(&self) -> bool {
+        self.0[0] == 127
+    }
+
+    /// Returns whether this is a multicast address (`224.0.0.0/4`).
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0xf0 == 224
+    }
+
+    /// Returns the IPv4-mapped IPv6 address of this address.
+    pub fn to_ipv6_mapped(&self) -> Ipv6Addr {
+        let [a, b, c, d] = self.0;
+        Ipv6Addr([0, 0, 0, 0, 0, 0xffff, (a as u16) << 8 | b as u16,
+                  (c as u16) << 8 | d as u16])
+    }
+
+    pub(crate) fn from_raw(addr: in_addr) -> Ipv4Addr {
+        Ipv4Addr(addr.s_addr.to_ne_bytes())
+    }
+
+    pub(crate) fn to_raw(self) -> in_addr {
+        in_addr { s_addr: u32::from_ne_bytes(self.0) }
+    }
+}
+
+impl Display for Ipv4Addr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let [a, b, c, d] = self.0;
+        core::write!(f, "{}.{}.{}.{}", a, b, c, d)
+    }
+}
+
+impl Debug for Ipv4Addr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Parsable for Ipv4Addr {
+    fn parse_bytes_init(bytes: &[u8]) -> Result<(Ipv4Addr, usize)> {
+        let mut addr = [0; 4];
+        let mut pos = 0;
+        for i in 0..4 {
+            if i > 0 {
+                if bytes.get(pos) != Some(&b'.') {
+                    return Err(error::InvalidArgument);
+                }
+                pos += 1;
+            }
+            let (n, len) = parse_dec(&bytes[pos..], 3)?;
+            if n > 255 {
+                return Err(error::InvalidArgument);
+            }
+            addr[i] = n as u8;
+            pos += len;
+        }
+        Ok((Ipv4Addr(addr), pos))
+    }
+}
+
+/// An IPv6 address.
+///
+/// [field, 1]
+/// The 16-bit groups of the address in host order.
+#[derive(Pod, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ipv6Addr(pub [u16; 8]);
+
+impl Ipv6Addr {
+    /// Creates a new address.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(a: u16, b: u16, c: u16, d: u16, e: u16, f: u16, g: u16,
+               h: u16) -> Ipv6Addr {
+        Ipv6Addr([a, b, c, d, e, f, g, h])
+    }
+
+    /// Returns the unspecified address `::`.
+    pub fn any() -> Ipv6Addr {
+        Ipv6Addr([0; 8])
+    }
+
+    /// Returns the loopback address `::1`.
+    pub fn localhost() -> Ipv6Addr {
+        Ipv6Addr([0, 0, 0, 0, 0, 0, 0, 1])
+    }
+
+    /// Returns whether this is the unspecified address.
+    pub fn is_unspecified(&self) -> bool {
+        self.0 == [0; 8]
+    }
+
+    /// Returns whether this is the loopback address.
+    pub fn is_loopback(&self) -> bool {
+        self.0 == [0, 0, 0, 0, 0, 0, 0, 1]
+    }
+
+    /// Returns whether this is a multicast address (`ff00::/8`).
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0xff00 == 0xff00
+    }
+
+    /// Returns the IPv4 address if this is an IPv4-mapped address.
+    pub fn to_ipv4_mapped(&self) -> Option<Ipv4Addr> {
+        match self.0 {
+            [0, 0, 0, 0, 0, 0xffff, g, h] => {
+                Some(Ipv4Addr([(g >> 8) as u8, g as u8, (h >> 8) as u8, h as u8]))
+            },
+            _ => None,
+        }
+    }
+
+    fn from_raw(addr: in6_addr) -> Ipv6Addr {
+        let mut groups = addr.u6_addr16;
+        for g in &mut groups {
+            *g = u16::from_be(*g);
+        }
+        Ipv6Addr(groups)
+    }
+
+    fn to_raw(self) -> in6_addr {
+        let mut groups = self.0;
+        for g in &mut groups {
+            *g = g.to_be();
+        }
+        in6_addr { u6_addr16: groups }
+    }
+}
+
+impl Display for Ipv6Addr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if let Some(v4) = self.to_ipv4_mapped() {
+            return core::write!(f, "::ffff:{}", v4);
+        }
+
+        // Find the longest run of at least two zero groups. (RFC 5952)
+        let (mut start, mut len) = (0, 0);
+        let mut i = 0;
+        while i < 8 {
+            let mut j = i;
+            while j < 8 && self.0[j] == 0 {
+                j += 1;
+            }
+            if j - i > len {
+                start = i;
+                len = j - i;
+            }
+            i = j + 1;
+        }
+
+        if len < 2 {
+            return fmt_groups(f, &self.0);
+        }
+        fmt_groups(f, &self.0[..start])?;
+        core::write!(f, "::")?;
+        fmt_groups(f, &self.0[start + len..])
+    }
+}
+
+fn fmt_groups(f: &mut Formatter, groups: &[u16]) -> fmt::Result {
+    for (i, g) in groups.iter().enumerate() {
+        if i > 0 {
+            core::write!(f, ":")?;
+        }
+        core::write!(f, "{:x}", g)?;
+    }
+    Ok(())
+}
+
+impl Debug for Ipv6Addr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Parsable for Ipv6Addr {
+    fn parse_bytes_init(bytes: &[u8]) -> Result<(Ipv6Addr, usize)> {
+        let mut groups = [0u16; 8];
+        let mut n = 0;
+        let mut gap = None;
+        let mut pos = 0;
+
+        if bytes.starts_with(b"::") {
+            gap = Some(0);
+            pos = 2;
+        }
+
+        // Whether the next group is optional, i.e., whether we're at the start of the
+        // address or directly after `::`.
+        let mut optional = true;
+        while n < 8 {
+            if n <= 6 {
+                if let Ok((v4, len)) = Ipv4Addr::parse_bytes_init(&bytes[pos..]) {
+                    let [a, b, c, d] = v4.0;
+                    groups[n] = (a as u16) << 8 | b as u16;
+                    groups[n + 1] = (c as u16) << 8 | d as u16;
+                    n += 2;
+                    pos += len;
+                    break;
+                }
+            }
+            let (g, len) = match parse_hex(&bytes[pos..], 4) {
+                Ok(g) => g,
+                Err(_) if optional && (n > 0 || gap.is_some()) => break,
+                Err(e) => return Err(e),
+            };
+            groups[n] = g as u16;
+            n += 1;
+            pos += len;
+            if bytes[pos..].starts_with(b"::") {
+                if gap.is_some() {
+                    return Err(error::InvalidArgument);
+                }
+                gap = Some(n);
+                pos += 2;
+                optional = true;
+            } else if bytes[pos..].starts_with(b":") {
+                pos += 1;
+                optional = false;
+            } else {
+                break;
+            }
+        }
+
+        match gap {
+            Some(gap) => {
+                if n == 8 {
+                    return Err(error::InvalidArgument);
+                }
+                let tail = n - gap;
+                for i in 0..tail {
+                    groups[7 - i] = groups[n - 1 - i];
+                    groups[n - 1 - i] = 0;
+                }
+                // `groups[gap..8-tail]` is now zero because every moved group was
+                // replaced by zero and the rest was never written.
+            },
+            None => {
+                if n != 8 {
+                    return Err(error::InvalidArgument);
+                }
+            },
+        }
+
+        Ok((Ipv6Addr(groups), pos))
+    }
+}
+
+/// An IPv4 or IPv6 address.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IpAddr {
+    /// An IPv4 address.
+    V4(Ipv4Addr),
+    /// An IPv6 address.
+    V6(Ipv6Addr),
+}
+
+impl IpAddr {
+    /// Returns the address family of the address.
+    pub fn family(&self) -> c_int {
+        match *self {
+            IpAddr::V4(..) => AF_INET,
+            IpAddr::V6(..) => AF_INET6,
+        }
+    }
+
+    /// Decodes an address stored in network order.
+    ///
+    /// [argument, bytes]
+    /// The 4 or 16 bytes of the address.
+    ///
+    /// = Remarks
+    ///
+    /// If the slice has any other length, `EINVAL` is returned.
+    pub fn from_bytes(bytes: &[u8]) -> Result<IpAddr> {
+        match bytes.len() {
+            4 => Ok(IpAddr::V4(Ipv4Addr([bytes[0], bytes[1], bytes[2], bytes[3]]))),
+            16 => {
+                let mut groups = [0u16; 8];
+                for (g, b) in groups.iter_mut().zip(bytes.chunks(2)) {
+                    *g = (b[0] as u16) << 8 | b[1] as u16;
+                }
+                Ok(IpAddr::V6(Ipv6Addr(groups)))
+            },
+            _ => Err(error::InvalidArgument),
+        }
+    }
+
+    /// Encodes the address in network order.
+    ///
+    /// [argument, buf]
+    /// The buffer in which the address will be stored.
+    ///
+    /// [return_value]
+    /// Returns the initial part of the buffer that was written.
+    pub fn to_bytes<'a>(&self, buf: &'a mut [u8; 16]) -> &'a [u8] {
+        match *self {
+            IpAddr::V4(addr) => {
+                buf[..4].copy_from_slice(&addr.0);
+                &buf[..4]
+            },
+            IpAddr::V6(addr) => {
+                for (b, g) in buf.chunks_mut(2).zip(addr.0.iter()) {
+                    b[0] = (g >> 8) as u8;
+                    b[1] = *g as u8;
+                }
+                &buf[..]
+            },
+        }
+    }
+}
+
+impl Display for IpAddr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            IpAddr::V4(ref addr) => Display::fmt(addr, f),
+            IpAddr::V6(ref addr) => Display::fmt(addr, f),
+        }
+    }
+}
+
+impl Debug for IpAddr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+/// The path or name of a Unix domain socket.
+///
+/// = Remarks
+///
+/// This is a byte string of at most `UNIX_PATH_MAX` bytes that is stored inline.
+#[derive(Copy, Clone)]
+pub struct UnixPath {
+    buf: [u8; UNIX_PATH_MAX],
+    len: usize,
+}
+
+impl UnixPath {
+    fn new(bytes: &[u8]) -> Result<UnixPath> {
+        if bytes.len() > UNIX_PATH_MAX {
+            return Err(error::PathTooLong);
+        }
+        let mut path = UnixPath { buf: [0; UNIX_PATH_MAX], len: bytes.len() };
+        lmem::copy(&mut path.buf, bytes);
+        Ok(path)
+    }
+
+    /// Returns the bytes of the path.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl PartialEq for UnixPath {
+    fn eq(&self, other: &UnixPath) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for UnixPath { }
+
+impl Hash for UnixPath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl Display for UnixPath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut bytes = self.as_bytes();
+        while !bytes.is_empty() {
+            match str::from_utf8(bytes) {
+                Ok(s) => return f.write_str(s),
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    f.write_str(unsafe { str::from_utf8_unchecked(&bytes[..valid]) })?;
+                    core::write!(f, "\\x{:02x}", bytes[valid])?;
+                    bytes = &bytes[valid + 1..];
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Debug for UnixPath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        core::write!(f, "\"{}\"", self)
+    }
+}
+
+/// A socket address.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SockAddr {
+    /// An IPv4 address and a port.
+    Inet(Ipv4Addr, u16),
+    /// An IPv6 address, a port, a flow label, and a scope id.
+    Inet6(Ipv6Addr, u16, u32, u32),
+    /// A Unix domain socket bound to a path in the file system.
+    Unix(UnixPath),
+    /// A Unix domain socket bound to a name in the abstract namespace.
+    ///
+    /// = Remarks
+    ///
+    /// The name does not include the leading null byte.
+    UnixAbstract(UnixPath),
+    /// An unbound Unix domain socket.
+    Unnamed,
+}
+
+impl SockAddr {
+    /// Creates a Unix domain socket address in the file system.
+    ///
+    /// [argument, path]
+    /// The path of the socket.
+    ///
+    /// = Remarks
+    ///
+    /// If the path contains a null byte, `EINVAL` is returned. If the path is longer
+    /// than `UNIX_PATH_MAX`, `ENAMETOOLONG` is returned.
+    pub fn unix<P: AsRef<[u8]>+?Sized>(path: &P) -> Result<SockAddr> {
+        let path = path.as_ref();
+        if memchr(0, path).is_some() {
+            return Err(error::InvalidArgument);
+        }
+        Ok(SockAddr::Unix(UnixPath::new(path)?))
+    }
+
+    /// Creates a Unix domain socket address in the abstract namespace.
+    ///
+    /// [argument, name]
+    /// The name of the socket without the leading null byte.
+    ///
+    /// = Remarks
+    ///
+    /// If the name is longer than `UNIX_PATH_MAX - 1`, `ENAMETOOLONG` is returned.
+    pub fn unix_abstract<P: AsRef<[u8]>+?Sized>(name: &P) -> Result<SockAddr> {
+        let name = name.as_ref();
+        if name.len() >= UNIX_PATH_MAX {
+            return Err(error::PathTooLong);
+        }
+        Ok(SockAddr::UnixAbstract(UnixPath::new(name)?))
+    }
+
+    /// Returns the address family of the address.
+    pub fn family(&self) -> c_int {
+        match *self {
+            SockAddr::Inet(..) => AF_INET,
+            SockAddr::Inet6(..) => AF_INET6,
+            _ => AF_UNIX,
+        }
+    }
+
+    /// Decodes an address returned by the kernel.
+    ///
+    /// [argument, bytes]
+    /// The `sockaddr_*` structure truncated to the length reported by the kernel.
+    ///
+    /// = Remarks
+    ///
+    /// An empty address, as reported for unbound Unix domain peers, is decoded as
+    /// `Unnamed`. If the address family is not supported or the structure is too
+    /// short, `EINVAL` is returned.
+    pub fn from_bytes(bytes: &[u8]) -> Result<SockAddr> {
+        if bytes.is_empty() {
+            return Ok(SockAddr::Unnamed);
+        }
+        let mut family: sa_family_t = 0;
+        if bytes.len() < mem::size_of_val(&family) {
+            return Err(error::InvalidArgument);
+        }
+        lmem::copy(lmem::as_mut_data(&mut family), d8::from_byte_slice(bytes));
+        match family as c_int {
+            AF_INET => {
+                if bytes.len() < mem::size_of::<sockaddr_in>() {
+                    return Err(error::InvalidArgument);
+                }
+                let mut sin: sockaddr_in = lmem::zeroed();
+                lmem::copy(lmem::as_mut_data(&mut sin), d8::from_byte_slice(bytes));
+                Ok(SockAddr::Inet(Ipv4Addr::from_raw(sin.sin_addr),
+                                  u16::from_be(sin.sin_port)))
+            },
+            AF_INET6 => {
+                if bytes.len() < mem::size_of::<sockaddr_in6>() {
+                    return Err(error::InvalidArgument);
+                }
+                let mut sin6: sockaddr_in6 = lmem::zeroed();
+                lmem::copy(lmem::as_mut_data(&mut sin6), d8::from_byte_slice(bytes));
+                Ok(SockAddr::Inet6(Ipv6Addr::from_raw(sin6.sin6_addr),
+                                   u16::from_be(sin6.sin6_port),
+                                   u32::from_be(sin6.sin6_flowinfo),
+                                   sin6.sin6_scope_id))
+            },
+            AF_UNIX => {
+                let path = &bytes[mem::size_of::<sa_family_t>()..];
+                let path = &path[..cmp::min(path.len(), UNIX_PATH_MAX)];
+                if path.is_empty() {
+                    Ok(SockAddr::Unnamed)
+                } else if path[0] == 0 {
+                    Ok(SockAddr::UnixAbstract(UnixPath::new(&path[1..])?))
+                } else {
+                    let len = memchr(0, path).unwrap_or(path.len());
+                    Ok(SockAddr::Unix(UnixPath::new(&path[..len])?))
+                }
+            },
+            _ => Err(error::InvalidArgument),
+        }
+    }
+
+    /// Encodes the address as a `sockaddr_*` structure.
+    pub fn encode(&self) -> SockAddrBuf {
+        let mut buf = SockAddrBuf::new();
+        let len = match *self {
+            SockAddr::Inet(addr, port) => {
+                let sin = sockaddr_in {
+                    sin_family: AF_INET as sa_family_t,
+                    sin_port: port.to_be(),
+                    sin_addr: addr.to_raw(),
+                    .. lmem::zeroed()
+                };
+                lmem::copy(buf.as_mut_data(), lmem::as_data(&sin))
+            },
+            SockAddr::Inet6(addr, port, flowinfo, scope_id) => {
+                let sin6 = sockaddr_in6 {
+                    sin6_family: AF_INET6 as sa_family_t,
+                    sin6_port: port.to_be(),
+                    sin6_flowinfo: flowinfo.to_be(),
+                    sin6_addr: addr.to_raw(),
+                    sin6_scope_id: scope_id,
+                };
+                lmem::copy(buf.as_mut_data(), lmem::as_data(&sin6))
+            },
+            SockAddr::Unix(ref path) | SockAddr::UnixAbstract(ref path) => {
+                let mut sun: sockaddr_un = lmem::zeroed();
+                sun.sun_family = AF_UNIX as sa_family_t;
+                let offset = match *self {
+                    SockAddr::UnixAbstract(..) => 1,
+                    _ => 0,
+                };
+                for (dst, &b) in sun.sun_path[offset..].iter_mut().zip(path.as_bytes()) {
+                    *dst = b as c_char;
+                }
+                let len = mem::size_of::<sa_family_t>() + offset + path.len;
+                // Include the terminating null byte if there is space for it.
+                let len = match offset {
+                    0 => cmp::min(len + 1, mem::size_of::<sockaddr_un>()),
+                    _ => len,
+                };
+                lmem::copy(buf.as_mut_data(), lmem::as_data(&sun));
+                len
+            },
+            SockAddr::Unnamed => {
+                let family = AF_UNIX as sa_family_t;
+                lmem::copy(buf.as_mut_data(), lmem::as_data(&family))
+            },
+        };
+        buf.set_len(len);
+        buf
+    }
+}
+
+impl Display for SockAddr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            SockAddr::Inet(addr, port) => core::write!(f, "{}:{}", addr, port),
+            SockAddr::Inet6(addr, port, _, 0) => core::write!(f, "[{}]:{}", addr, port),
+            SockAddr::Inet6(addr, port, _, scope) => {
+                core::write!(f, "[{}%{}]:{}", addr, scope, port)
+            },
+            SockAddr::Unix(ref path) => Display::fmt(path, f),
+            SockAddr::UnixAbstract(ref name) => core::write!(f, "@{}", name),
+            SockAddr::Unnamed => core::write!(f, "(unnamed)"),
+        }
+    }
+}
+
+impl Debug for SockAddr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            SockAddr::Inet(..) => core::write!(f, "Inet({})", self),
+            SockAddr::Inet6(_, _, flowinfo, _) => {
+                core::write!(f, "Inet6({}, flowinfo: {})", self, flowinfo)
+            },
+            SockAddr::Unix(ref path) => core::write!(f, "Unix({:?})", path),
+            SockAddr::UnixAbstract(ref name) => core::write!(f, "UnixAbstract({:?})", name),
+            SockAddr::Unnamed => core::write!(f, "Unnamed"),
+        }
+    }
+}
+
+impl Parsable for SockAddr {
+    /// Parses a socket address.
+    ///
+    /// = Remarks
+    ///
+    /// The following forms are accepted:
+    ///
+    /// * `127.0.0.1:80`
+    /// * `[::1]:443` and `[fe80::1%2]:443` where `2` is the scope id
+    /// * `/path/to/socket` (the rest of the input is the path)
+    /// * `@name` (the rest of the input is the name in the abstract namespace)
+    fn parse_bytes_init(bytes: &[u8]) -> Result<(SockAddr, usize)> {
+        match bytes.first() {
+            Some(&b'/') => return Ok((SockAddr::unix(bytes)?, bytes.len())),
+            Some(&b'@') => return Ok((SockAddr::unix_abstract(&bytes[1..])?, bytes.len())),
+            Some(&b'[') => { },
+            _ => {
+                let (addr, mut pos) = Ipv4Addr::parse_bytes_init(bytes)?;
+                let port = parse_port(&bytes[pos..])?;
+                pos += port.1;
+                return Ok((SockAddr::Inet(addr, port.0), pos));
+            },
+        }
+        let (addr, len) = Ipv6Addr::parse_bytes_init(&bytes[1..])?;
+        let mut pos = 1 + len;
+        let mut scope = 0;
+        if bytes.get(pos) == Some(&b'%') {
+            let (s, len) = parse_dec(&bytes[pos + 1..], 10)?;
+            if s > u32::max_value() as u64 {
+                return Err(error::InvalidArgument);
+            }
+            scope = s as u32;
+            pos += 1 + len;
+        }
+        if bytes.get(pos) != Some(&b']') {
+            return Err(error::InvalidArgument);
+        }
+        pos += 1;
+        let port = parse_port(&bytes[pos..])?;
+        pos += port.1;
+        Ok((SockAddr::Inet6(addr, port.0, 0, scope), pos))
+    }
+}
+
+/// Parses `:port`.
+fn parse_port(bytes: &[u8]) -> Result<(u16, usize)> {
+    if bytes.first() != Some(&b':') {
+        return Err(error::InvalidArgument);
+    }
+    let (port, len) = parse_dec(&bytes[1..], 5)?;
+    if port > u16::max_value() as u64 {
+        return Err(error::InvalidArgument);
+    }
+    Ok((port as u16, len + 1))
+}
+
+/// Parses a decimal number of at most `max` digits without leading zeros.
+fn parse_dec(bytes: &[u8], max: usize) -> Result<(u64, usize)> {
+    let len = bytes.iter().take(max + 1).take_while(|b| b.is_ascii_digit()).count();
+    if len == 0 || len > max || (len > 1 && bytes[0] == b'0') {
+        return Err(error::InvalidArgument);
+    }
+    let n = bytes[..len].iter().fold(0, |n, &b| n * 10 + (b - b'0') as u64);
+    Ok((n, len))
+}
+
+/// Parses a hexadecimal number of at most `max` digits.
+fn parse_hex(bytes: &[u8], max: usize) -> Result<(u64, usize)> {
+    let len = bytes.iter().take(max + 1).take_while(|b| b.is_ascii_hexdigit()).count();
+    if len == 0 || len > max {
+        return Err(error::InvalidArgument);
+    }
+    let n = bytes[..len].iter().fold(0, |n, &b| {
+        n * 16 + (b as char).to_digit(16).unwrap() as u64
+    });
+    Ok((n, len))
+}
+
+/// A buffer that holds an encoded socket address.
+///
+/// = Remarks
+///
+/// The buffer can hold addresses of all families. Buffers created with `new` have the
+/// maximum length and are suitable for receiving addresses from the kernel.
+#[derive(Pod, Copy, Clone)]
+pub struct SockAddrBuf {
+    storage: __kernel_sockaddr_storage,
+    len: usize,
+}
+
+impl SockAddrBuf {
+    /// Creates a new zeroed buffer of maximum length.
+    pub fn new() -> SockAddrBuf {
+        SockAddrBuf {
+            storage: lmem::zeroed(),
+            len: mem::size_of::<__kernel_sockaddr_storage>(),
+        }
+    }
+
+    /// Returns the length of the address.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Sets the length of the address.
+    ///
+    /// [argument, len]
+    /// The new length.
+    ///
+    /// = Remarks
+    ///
+    /// The length is truncated to the capacity of the buffer.
+    pub fn set_len(&mut self, len: usize) {
+        self.len = cmp::min(len, mem::size_of::<__kernel_sockaddr_storage>());
+    }
+
+    /// Returns the encoded address.
+    pub fn as_data(&self) -> &[d8] {
+        &lmem::as_data(&self.storage)[..self.len]
+    }
+
+    /// Returns the whole buffer.
+    pub fn as_mut_data(&mut self) -> &mut [d8] {
+        lmem::as_mut_data(&mut self.storage)
+    }
+
+    /// Decodes the address.
+    pub fn decode(&self) -> Result<SockAddr> {
+        let bytes = unsafe { mem::transmute::<&[d8], &[u8]>(self.as_data()) };
+        SockAddr::from_bytes(bytes)
+    }
+}
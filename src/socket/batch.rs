@@ -0,0 +1,265 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{cmp, ptr};
+use core::fmt::{self, Debug, Formatter};
+use alloc::{vec, vec::Vec};
+use crate::{
+    result::{Result},
+    lmem,
+    fd::{FdContainer},
+    time::{Time},
+    socket::{SockAddr, SockAddrBuf, MsgFlags, Cmsgs},
+    util::{
+        retry::{retry},
+    },
+};
+use crate::kty::{
+    c_int, c_uint, c_void, mmsghdr, iovec, timespec, user_size_t, MSG_CTRUNC,
+    MSG_CMSG_CLOEXEC,
+};
+use crate::syscall::{recvmmsg, sendmmsg};
+
+/// A reusable set of buffers for sending and receiving multiple datagrams with a
+/// single system call.
+///
+/// = Remarks
+///
+/// All memory is allocated when the batch is created. Sending and receiving does not
+/// allocate.
+///
+/// = Examples
+///
+/// ----
+/// let mut batch = MsgBatch::new(32, 2048, cmsg_space(mem::size_of::<in_pktinfo>()));
+/// for (payload, addr, cmsgs) in batch.recv_batch(&sock, MSG_NONE, None)? {
+///     // ...
+/// }
+/// ----
+pub struct MsgBatch {
+    hdrs: Vec<mmsghdr>,
+    iovs: Vec<iovec>,
+    addrs: Vec<SockAddrBuf>,
+    data: Vec<u8>,
+    // u64 so that every control buffer is aligned for `cmsghdr`.
+    control: Vec<u64>,
+    buf_size: usize,
+    control_words: usize,
+}
+
+impl MsgBatch {
+    /// Creates a new batch.
+    ///
+    /// [argument, count]
+    /// The maximum number of messages sent or received in one call.
+    ///
+    /// [argument, buf_size]
+    /// The size of the payload buffer of each message.
+    ///
+    /// [argument, control_size]
+    /// The size of the control buffer of each message.
+    ///
+    /// = See also
+    ///
+    /// * link:lrs::socket::cmsg_space
+    pub fn new(count: usize, buf_size: usize, control_size: usize) -> MsgBatch {
+        let control_words = control_size.div_ceil(8);
+        MsgBatch {
+            hdrs: vec!(lmem::zeroed(); count),
+            iovs: vec!(lmem::zeroed(); count),
+            addrs: vec!(SockAddrBuf::new(); count),
+            data: vec!(0; count * buf_size),
+            control: vec!(0; count * control_words),
+            buf_size,
+            control_words,
+        }
+    }
+
+    /// Returns the maximum number of messages sent or received in one call.
+    pub fn capacity(&self) -> usize {
+        self.hdrs.len()
+    }
+
+    /// Receives multiple datagrams.
+    ///
+    /// [argument, sock]
+    /// The socket.
+    ///
+    /// [argument, flags]
+    /// Flags used while receiving. `MSG_WAIT_FOR_ONE` returns as soon as one datagram
+    /// has been received.
+    ///
+    /// [argument, timeout]
+    /// An optional timeout after which no further datagrams are awaited.
+    ///
+    /// [return_value]
+    /// Returns an iterator over the received datagrams.
+    ///
+    /// = Remarks
+    ///
+    /// The iterator yields the payload, the source address, and the control messages
+    /// of each datagram. Payloads longer than the buffer size are truncated. Source
+    /// addresses of unsupported families are reported as `SockAddr::Unnamed`.
+    ///
+    /// The timeout is only checked after a datagram has been received. Received file
+    /// descriptors that are not retrieved are closed when the iterator is dropped.
+    ///
+    /// = See also
+    ///
+    /// * link:man:recvmmsg(2)
+    pub fn recv_batch<F>(&mut self, sock: &F, flags: MsgFlags,
+                         timeout: Option<Time>) -> Result<Received<'_>>
+        where F: FdContainer,
+    {
+        for i in 0..self.hdrs.len() {
+            self.iovs[i] = iovec {
+                iov_base: self.data[i * self.buf_size..].as_mut_ptr() as *mut c_void,
+                iov_len: self.buf_size as user_size_t,
+            };
+            self.addrs[i] = SockAddrBuf::new();
+            let addr = self.addrs[i].as_mut_data();
+            let control = &mut self.control[i * self.control_words..];
+            let hdr = &mut self.hdrs[i];
+            hdr.msg_len = 0;
+            hdr.msg_hdr.msg_name = addr.as_mut_ptr() as *mut c_void;
+            hdr.msg_hdr.msg_namelen = addr.len() as c_int;
+            hdr.msg_hdr.msg_iov = &mut self.iovs[i];
+            hdr.msg_hdr.msg_iovlen = 1;
+            hdr.msg_hdr.msg_control = match self.control_words {
+                0 => ptr::null_mut(),
+                _ => control.as_mut_ptr() as *mut c_void,
+            };
+            hdr.msg_hdr.msg_controllen = (self.control_words * 8) as user_size_t;
+            hdr.msg_hdr.msg_flags = 0;
+        }
+
+        let mut timeout = timeout.map(|t| timespec {
+            tv_sec: t.seconds as _,
+            tv_nsec: t.nanoseconds as _,
+        });
+        let flags = (flags.0 | MSG_CMSG_CLOEXEC) as c_uint;
+        let count = retry(|| recvmmsg(sock.borrow(), &mut self.hdrs, flags,
+                                      timeout.as_mut()))? as usize;
+
+        for i in 0..count {
+            self.addrs[i].set_len(self.hdrs[i].msg_hdr.msg_namelen as usize);
+        }
+
+        Ok(Received {
+            batch: self,
+            pos: 0,
+            count,
+        })
+    }
+
+    /// Sends multiple datagrams.
+    ///
+    /// [argument, sock]
+    /// The socket.
+    ///
+    /// [argument, msgs]
+    /// The destination and the payload buffers of each datagram. The destination can
+    /// be omitted if the socket is connected.
+    ///
+    /// [argument, flags]
+    /// Flags used while sending.
+    ///
+    /// [return_value]
+    /// Returns the number of datagrams sent.
+    ///
+    /// = Remarks
+    ///
+    /// At most `capacity` datagrams are sent. The payload buffers are not copied.
+    ///
+    /// = See also
+    ///
+    /// * link:man:sendmmsg(2)
+    pub fn send_batch<F>(&mut self, sock: &F, msgs: &[(Option<&SockAddr>, &[&[u8]])],
+                         flags: MsgFlags) -> Result<usize>
+        where F: FdContainer,
+    {
+        let count = cmp::min(msgs.len(), self.hdrs.len());
+        for (i, &(addr, bufs)) in msgs[..count].iter().enumerate() {
+            let hdr = &mut self.hdrs[i];
+            hdr.msg_hdr = lmem::zeroed();
+            hdr.msg_len = 0;
+            hdr.msg_hdr.msg_iov = bufs.as_ptr() as *mut iovec;
+            hdr.msg_hdr.msg_iovlen = bufs.len() as user_size_t;
+            if let Some(addr) = addr {
+                self.addrs[i] = addr.encode();
+                let addr = self.addrs[i].as_data();
+                hdr.msg_hdr.msg_name = addr.as_ptr() as *mut c_void;
+                hdr.msg_hdr.msg_namelen = addr.len() as c_int;
+            }
+        }
+        let hdrs = &self.hdrs[..count];
+        retry(|| sendmmsg(sock.borrow(), hdrs, flags.0 as c_uint)).map(|r| r as usize)
+    }
+}
+
+impl Debug for MsgBatch {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("MsgBatch")
+            .field("capacity", &self.capacity())
+            .field("buf_size", &self.buf_size)
+            .field("control_size", &(self.control_words * 8))
+            .finish()
+    }
+}
+
+/// An iterator over datagrams received by `MsgBatch::recv_batch`.
+pub struct Received<'a> {
+    batch: &'a MsgBatch,
+    pos: usize,
+    count: usize,
+}
+
+impl<'a> Received<'a> {
+    /// Returns the number of datagrams that have not been yielded yet.
+    pub fn len(&self) -> usize {
+        self.count - self.pos
+    }
+}
+
+impl<'a> Iterator for Received<'a> {
+    type Item = (&'a [u8], SockAddr, Cmsgs<'a>);
+
+    fn next(&mut self) -> Option<(&'a [u8], SockAddr, Cmsgs<'a>)> {
+        if self.pos == self.count {
+            return None;
+        }
+        let batch = self.batch;
+        let i = self.pos;
+        self.pos += 1;
+
+        let hdr = &batch.hdrs[i];
+        let start = i * batch.buf_size;
+        let len = cmp::min(hdr.msg_len as usize, batch.buf_size);
+        let payload = &batch.data[start..start + len];
+
+        let addr = batch.addrs[i].decode().unwrap_or(SockAddr::Unnamed);
+
+        let control = &batch.control[i * batch.control_words..
+                                     (i + 1) * batch.control_words];
+        let control_len = cmp::min(hdr.msg_hdr.msg_controllen as usize,
+                                   batch.control_words * 8);
+        let truncated = hdr.msg_hdr.msg_flags as c_int & MSG_CTRUNC != 0;
+        let cmsgs = unsafe {
+            Cmsgs::new(&lmem::as_data(control)[..control_len], truncated)
+        };
+
+        Some((payload, addr, cmsgs))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl<'a> Drop for Received<'a> {
+    fn drop(&mut self) {
+        // Dropping the control messages closes the file descriptors in them.
+        for _ in self.by_ref() { }
+    }
+}
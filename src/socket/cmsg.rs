@@ -0,0 +1,435 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem, cmp};
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    result::{Result},
+    lmem,
+    fd::{FdContainer},
+    file::{File},
+    time::{Time},
+    socket::{Ipv4Addr},
+    util::{
+        data::{d8, DataSlice},
+        error::{self},
+    },
+};
+use crate::kty::{
+    c_int, pid_t, uid_t, gid_t, user_size_t, cmsghdr, ucred, timespec, in_pktinfo,
+    SOL_SOCKET, SOL_IP, SCM_RIGHTS, SCM_CREDENTIALS, SCM_TIMESTAMPNS, IP_PKTINFO,
+};
+use crate::syscall::{close};
+
+fn cmsg_align(len: usize) -> usize {
+    let mask = mem::size_of::<user_size_t>() - 1;
+    (len + mask) & !mask
+}
+
+fn hdr_len() -> usize {
+    cmsg_align(mem::size_of::<cmsghdr>())
+}
+
+/// Returns the space a control message occupies in a control buffer.
+///
+/// [argument, data_len]
+/// The length of the payload of the message.
+///
+/// = Remarks
+///
+/// This can be used to compute the size of the buffer passed to `CmsgBuffer::new` or
+/// `recv_msg`. For example, a message carrying three file descriptors requires
+/// `cmsg_space(3 * mem::size_of::<c_int>())` bytes.
+///
+/// = See also
+///
+/// * link:man:cmsg(3) and CMSG_SPACE therein
+pub fn cmsg_space(data_len: usize) -> usize {
+    hdr_len() + cmsg_align(data_len)
+}
+
+/// The credentials of a process.
+///
+/// = See also
+///
+/// * link:man:unix(7) and SCM_CREDENTIALS therein
+#[derive(Pod, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Credentials {
+    /// The process id.
+    pub pid: pid_t,
+    /// The user id.
+    pub uid: uid_t,
+    /// The group id.
+    pub gid: gid_t,
+}
+
+/// Information about the destination of an incoming IPv4 packet or the source of an
+/// outgoing one.
+///
+/// = See also
+///
+/// * link:man:ip(7) and IP_PKTINFO therein
+#[derive(Pod, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PktInfo {
+    /// The index of the interface on which the packet was received or over which it
+    /// will be sent.
+    pub ifindex: c_int,
+    /// The local address of the packet.
+    pub spec_dst: Ipv4Addr,
+    /// The destination address in the header of a received packet.
+    pub addr: Ipv4Addr,
+}
+
+/// A writer for control messages sent with `send_msg`.
+///
+/// = Remarks
+///
+/// The messages are written into a buffer provided by the caller. The buffer is
+/// aligned automatically. Use `cmsg_space` to compute the required size.
+///
+/// = Examples
+///
+/// ----
+/// let mut storage = [d8::new(0); 64];
+/// let mut cmsgs = CmsgBuffer::new(&mut storage);
+/// cmsgs.rights(&[file.borrow()])?;
+/// send_msg(sock, &[b"x".as_ref()], None, Some(&cmsgs), MSG_NONE)?;
+/// ----
+pub struct CmsgBuffer<'a> {
+    buf: &'a mut [d8],
+    len: usize,
+}
+
+impl<'a> CmsgBuffer<'a> {
+    /// Creates a new, empty writer.
+    ///
+    /// [argument, buf]
+    /// The buffer in which the messages will be stored.
+    pub fn new(buf: &'a mut [d8]) -> CmsgBuffer<'a> {
+        CmsgBuffer {
+            buf: buf.align_for_mut::<cmsghdr>(),
+            len: 0,
+        }
+    }
+
+    /// Removes all messages from the buffer.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Returns the length of the written messages.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the written messages.
+    pub fn as_data(&self) -> &[d8] {
+        &self.buf[..self.len]
+    }
+
+    /// Appends a message with a raw payload.
+    ///
+    /// [argument, level]
+    /// The protocol level of the message, e.g., `SOL_SOCKET`.
+    ///
+    /// [argument, ty]
+    /// The type of the message, e.g., `SCM_RIGHTS`.
+    ///
+    /// [argument, data]
+    /// The payload of the message.
+    ///
+    /// = Remarks
+    ///
+    /// If the buffer is too small, `ENOMEM` is returned and the buffer is not
+    /// modified.
+    pub fn raw(&mut self, level: c_int, ty: c_int, data: &[d8]) -> Result {
+        let space = cmsg_space(data.len());
+        if self.buf.len() - self.len < space {
+            return Err(error::NoMemory);
+        }
+        let hdr = cmsghdr {
+            cmsg_len: (hdr_len() + data.len()) as user_size_t,
+            cmsg_level: level,
+            cmsg_type: ty,
+        };
+        let msg = &mut self.buf[self.len..self.len + space];
+        for b in msg.iter_mut() {
+            *b = d8::new(0);
+        }
+        lmem::copy(msg, lmem::as_data(&hdr));
+        lmem::copy(&mut msg[hdr_len()..], data);
+        self.len += space;
+        Ok(())
+    }
+
+    /// Appends a message that passes file descriptors.
+    ///
+    /// [argument, fds]
+    /// The file descriptors to pass.
+    ///
+    /// = Remarks
+    ///
+    /// This is only supported by Unix domain sockets. The receiver gets duplicates of
+    /// the file descriptors.
+    ///
+    /// = See also
+    ///
+    /// * link:man:unix(7) and SCM_RIGHTS therein
+    pub fn rights(&mut self, fds: &[c_int]) -> Result {
+        self.raw(SOL_SOCKET, SCM_RIGHTS, lmem::as_data(fds))
+    }
+
+    /// Appends a message that passes credentials.
+    ///
+    /// [argument, creds]
+    /// The credentials to pass.
+    ///
+    /// = Remarks
+    ///
+    /// This is only supported by Unix domain sockets. Unprivileged processes can only
+    /// pass their own ids. The receiver has to enable `SO_PASSCRED`.
+    ///
+    /// = See also
+    ///
+    /// * link:man:unix(7) and SCM_CREDENTIALS therein
+    pub fn credentials(&mut self, creds: Credentials) -> Result {
+        let creds = ucred {
+            pid: creds.pid as u32,
+            uid: creds.uid,
+            gid: creds.gid,
+        };
+        self.raw(SOL_SOCKET, SCM_CREDENTIALS, lmem::as_data(&creds))
+    }
+
+    /// Appends a message that selects the source of an outgoing IPv4 packet.
+    ///
+    /// [argument, info]
+    /// The interface and local address to use. The `addr` field is ignored.
+    ///
+    /// = See also
+    ///
+    /// * link:man:ip(7) and IP_PKTINFO therein
+    pub fn pkt_info(&mut self, info: &PktInfo) -> Result {
+        let info = in_pktinfo {
+            ipi_ifindex: info.ifindex,
+            ipi_spec_dst: info.spec_dst.to_raw(),
+            ipi_addr: info.addr.to_raw(),
+        };
+        self.raw(SOL_IP, IP_PKTINFO, lmem::as_data(&info))
+    }
+}
+
+impl<'a> Debug for CmsgBuffer<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("CmsgBuffer").field("len", &self.len).finish()
+    }
+}
+
+/// A received control message.
+pub enum Cmsg<'a> {
+    /// File descriptors passed via `SCM_RIGHTS`.
+    Rights(Rights<'a>),
+    /// Credentials passed via `SCM_CREDENTIALS`.
+    Credentials(Credentials),
+    /// The time at which a packet was received if `SO_TIMESTAMPNS` is enabled.
+    TimestampNs(Time),
+    /// Information about an IPv4 packet if `IP_PKTINFO` is enabled.
+    PktInfo(PktInfo),
+    /// A message not understood by this crate.
+    Other {
+        /// The protocol level of the message.
+        level: c_int,
+        /// The type of the message.
+        ty: c_int,
+        /// The payload of the message.
+        data: &'a [d8],
+    },
+}
+
+impl<'a> Debug for Cmsg<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Cmsg::Rights(ref r) => core::write!(f, "Rights({:?})", r),
+            Cmsg::Credentials(ref c) => core::write!(f, "Credentials({:?})", c),
+            Cmsg::TimestampNs(ref t) => core::write!(f, "TimestampNs({:?})", t),
+            Cmsg::PktInfo(ref p) => core::write!(f, "PktInfo({:?})", p),
+            Cmsg::Other { level, ty, data } => {
+                core::write!(f, "Other {{ level: {}, ty: {}, len: {} }}", level, ty,
+                             data.len())
+            },
+        }
+    }
+}
+
+/// File descriptors received via `SCM_RIGHTS`.
+///
+/// = Remarks
+///
+/// This is an iterator over the received file descriptors. The file descriptors are
+/// owned and have the close-on-exec flag set. File descriptors that have not been
+/// retrieved when this object is dropped are closed.
+pub struct Rights<'a> {
+    data: &'a [d8],
+}
+
+impl<'a> Rights<'a> {
+    /// Returns the number of remaining file descriptors.
+    pub fn len(&self) -> usize {
+        self.data.len() / mem::size_of::<c_int>()
+    }
+
+    /// Retrieves the next file descriptor as an arbitrary file descriptor container.
+    pub fn next_fd<F: FdContainer>(&mut self) -> Option<F> {
+        if self.len() == 0 {
+            return None;
+        }
+        let mut fd: c_int = 0;
+        lmem::copy(lmem::as_mut_data(&mut fd), self.data);
+        self.data = &self.data[mem::size_of::<c_int>()..];
+        Some(F::from_owned(fd))
+    }
+}
+
+impl<'a> Iterator for Rights<'a> {
+    type Item = File;
+
+    fn next(&mut self) -> Option<File> {
+        self.next_fd()
+    }
+}
+
+impl<'a> Drop for Rights<'a> {
+    fn drop(&mut self) {
+        while let Some(fd) = self.next_fd::<c_int>() {
+            close(fd);
+        }
+    }
+}
+
+impl<'a> Debug for Rights<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        core::write!(f, "[{} fds]", self.len())
+    }
+}
+
+/// An iterator over received control messages.
+///
+/// = Remarks
+///
+/// File descriptors in messages that have not been retrieved when this object is
+/// dropped are closed.
+pub struct Cmsgs<'a> {
+    buf: &'a [d8],
+    truncated: bool,
+}
+
+impl<'a> Cmsgs<'a> {
+    /// Creates an iterator over a buffer filled by the kernel.
+    ///
+    /// [argument, buf]
+    /// The control messages. This must be aligned for `cmsghdr`.
+    ///
+    /// [argument, truncated]
+    /// Whether the kernel reported `MSG_CTRUNC`.
+    ///
+    /// = Remarks
+    ///
+    /// The iterator takes ownership of all file descriptors in the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The buffer must contain the control messages written by the kernel during a
+    /// single `recvmsg` call. The `SCM_RIGHTS` messages in it must contain file
+    /// descriptors that are owned by the caller and not used elsewhere since they are
+    /// closed when the iterator is dropped.
+    pub unsafe fn new(buf: &'a [d8], truncated: bool) -> Cmsgs<'a> {
+        Cmsgs { buf, truncated }
+    }
+
+    /// Returns whether some control messages were discarded because the buffer was
+    /// too small.
+    ///
+    /// = Remarks
+    ///
+    /// The kernel closes file descriptors that do not fit into the buffer.
+    ///
+    /// = See also
+    ///
+    /// * link:man:recvmsg(2) and MSG_CTRUNC therein
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    fn next_raw(&mut self) -> Option<(c_int, c_int, &'a [d8])> {
+        if self.buf.len() < hdr_len() {
+            return None;
+        }
+        let mut hdr: cmsghdr = lmem::zeroed();
+        lmem::copy(lmem::as_mut_data(&mut hdr), self.buf);
+        let len = hdr.cmsg_len as usize;
+        if len < hdr_len() || len > self.buf.len() {
+            self.buf = &[];
+            return None;
+        }
+        let data = &self.buf[hdr_len()..len];
+        self.buf = &self.buf[cmp::min(cmsg_align(len), self.buf.len())..];
+        Some((hdr.cmsg_level, hdr.cmsg_type, data))
+    }
+}
+
+impl<'a> Iterator for Cmsgs<'a> {
+    type Item = Cmsg<'a>;
+
+    fn next(&mut self) -> Option<Cmsg<'a>> {
+        let (level, ty, data) = self.next_raw()?;
+        let msg = match (level, ty) {
+            (SOL_SOCKET, SCM_RIGHTS) => Cmsg::Rights(Rights { data }),
+            (SOL_SOCKET, SCM_CREDENTIALS) if data.len() >= mem::size_of::<ucred>() => {
+                let mut creds: ucred = lmem::zeroed();
+                lmem::copy(lmem::as_mut_data(&mut creds), data);
+                Cmsg::Credentials(Credentials {
+                    pid: creds.pid as pid_t,
+                    uid: creds.uid,
+                    gid: creds.gid,
+                })
+            },
+            (SOL_SOCKET, SCM_TIMESTAMPNS) if data.len() >= mem::size_of::<timespec>() => {
+                let mut ts: timespec = lmem::zeroed();
+                lmem::copy(lmem::as_mut_data(&mut ts), data);
+                Cmsg::TimestampNs(Time {
+                    seconds: ts.tv_sec,
+                    nanoseconds: ts.tv_nsec,
+                })
+            },
+            (SOL_IP, IP_PKTINFO) if data.len() >= mem::size_of::<in_pktinfo>() => {
+                let mut info: in_pktinfo = lmem::zeroed();
+                lmem::copy(lmem::as_mut_data(&mut info), data);
+                Cmsg::PktInfo(PktInfo {
+                    ifindex: info.ipi_ifindex,
+                    spec_dst: Ipv4Addr::from_raw(info.ipi_spec_dst),
+                    addr: Ipv4Addr::from_raw(info.ipi_addr),
+                })
+            },
+            _ => Cmsg::Other { level, ty, data },
+        };
+        Some(msg)
+    }
+}
+
+impl<'a> Drop for Cmsgs<'a> {
+    fn drop(&mut self) {
+        while let Some(msg) = self.next() {
+            drop(msg);
+        }
+    }
+}
+
+impl<'a> Debug for Cmsgs<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Cmsgs")
+            .field("len", &self.buf.len())
+            .field("truncated", &self.truncated)
+            .finish()
+    }
+}
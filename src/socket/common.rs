@@ -0,0 +1,153 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    result::{Result},
+    fd::{FdContainer},
+    fd::flags::{FD_DONT_BLOCK},
+    socket::{SockAddr, SockAddrBuf, MsgFlags},
+    util::{
+        retry::{retry},
+        data::{d8},
+        error::{self},
+    },
+};
+use crate::kty::{
+    c_int, SOCK_CLOEXEC, SHUT_RD, SHUT_WR, SHUT_RDWR, AF_UNIX, AF_INET,
+    AF_INET6,
+};
+use crate::syscall::{
+    self, accept4, getsockname, getpeername, sendto, recvfrom, socketpair,
+};
+
+/// The directions of a connection that are shut down.
+///
+/// = See also
+///
+/// * link:man:shutdown(2)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Shutdown {
+    /// Further receptions are disallowed.
+    Read,
+    /// Further transmissions are disallowed.
+    Write,
+    /// Further receptions and transmissions are disallowed.
+    Both,
+}
+
+/// The default backlog of listening sockets.
+pub const BACKLOG: u32 = 128;
+
+/// Returns `EAFNOSUPPORT` unless the address belongs to an IP family.
+pub fn check_inet(addr: &SockAddr) -> Result {
+    match addr.family() {
+        AF_INET | AF_INET6 => Ok(()),
+        _ => Err(error::AddrFamilyNotSupported),
+    }
+}
+
+/// Returns `EAFNOSUPPORT` unless the address is a Unix domain address.
+pub fn check_unix(addr: &SockAddr) -> Result {
+    match addr.family() {
+        AF_UNIX => Ok(()),
+        _ => Err(error::AddrFamilyNotSupported),
+    }
+}
+
+pub fn socket(domain: c_int, ty: c_int) -> Result<c_int> {
+    rv!(syscall::socket(domain, ty | SOCK_CLOEXEC, 0), -> c_int)
+}
+
+pub fn pair(ty: c_int) -> Result<(c_int, c_int)> {
+    let mut fds = [0; 2];
+    rv!(socketpair(AF_UNIX, ty | SOCK_CLOEXEC, 0, &mut fds))?;
+    Ok((fds[0], fds[1]))
+}
+
+pub fn bind(fd: c_int, addr: &SockAddr) -> Result {
+    rv!(syscall::bind(fd, addr.encode().as_data()))
+}
+
+pub fn listen(fd: c_int, backlog: u32) -> Result {
+    rv!(syscall::listen(fd, backlog))
+}
+
+pub fn connect(fd: c_int, addr: &SockAddr) -> Result {
+    rv!(syscall::connect(fd, addr.encode().as_data()))
+}
+
+pub fn accept(fd: c_int) -> Result<(c_int, SockAddr)> {
+    let mut buf = SockAddrBuf::new();
+    let mut len = 0;
+    let new = retry(|| accept4(fd, Some(buf.as_mut_data()), &mut len, SOCK_CLOEXEC))?;
+    buf.set_len(len);
+    match buf.decode() {
+        Ok(addr) => Ok((new, addr)),
+        Err(e) => {
+            syscall::close(new);
+            Err(e)
+        },
+    }
+}
+
+pub fn local_addr(fd: c_int) -> Result<SockAddr> {
+    let mut buf = SockAddrBuf::new();
+    let mut len = 0;
+    rv!(getsockname(fd, buf.as_mut_data(), &mut len))?;
+    buf.set_len(len);
+    buf.decode()
+}
+
+pub fn peer_addr(fd: c_int) -> Result<SockAddr> {
+    let mut buf = SockAddrBuf::new();
+    let mut len = 0;
+    rv!(getpeername(fd, buf.as_mut_data(), &mut len))?;
+    buf.set_len(len);
+    buf.decode()
+}
+
+pub fn shutdown(fd: c_int, how: Shutdown) -> Result {
+    let how = match how {
+        Shutdown::Read => SHUT_RD,
+        Shutdown::Write => SHUT_WR,
+        Shutdown::Both => SHUT_RDWR,
+    };
+    rv!(syscall::shutdown(fd, how))
+}
+
+pub fn set_non_blocking(fd: c_int, val: bool) -> Result {
+    let fd = fd.as_fdio();
+    let mut flags = fd.description_flags()?;
+    if val {
+        flags.set(FD_DONT_BLOCK);
+    } else {
+        flags.unset(FD_DONT_BLOCK);
+    }
+    fd.set_description_flags(flags)
+}
+
+pub fn is_non_blocking(fd: c_int) -> Result<bool> {
+    let flags = fd.as_fdio().description_flags()?;
+    Ok(flags.is_set(FD_DONT_BLOCK))
+}
+
+pub fn send_to(fd: c_int, buf: &[u8], addr: Option<&SockAddr>,
+               flags: MsgFlags) -> Result<usize> {
+    let addr = addr.map(|a| a.encode());
+    let addr = addr.as_ref().map(|a| a.as_data());
+    retry(|| sendto(fd, d8::from_byte_slice(buf), flags.0, addr)).map(|r| r as usize)
+}
+
+pub fn recv(fd: c_int, buf: &mut [d8], flags: MsgFlags) -> Result<usize> {
+    let mut len = 0;
+    retry(|| recvfrom(fd, buf, flags.0, None, &mut len)).map(|r| r as usize)
+}
+
+pub fn recv_from(fd: c_int, buf: &mut [d8], flags: MsgFlags) -> Result<(usize, SockAddr)> {
+    let mut addr = SockAddrBuf::new();
+    let mut len = 0;
+    let n = retry(|| recvfrom(fd, buf, flags.0, Some(addr.as_mut_data()), &mut len))?;
+    addr.set_len(len);
+    Ok((n as usize, addr.decode()?))
+}
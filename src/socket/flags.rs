@@ -0,0 +1,123 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::ops::{BitOr, BitAnd, Not};
+use core::fmt::{self, Debug, Formatter};
+use crate::kty::{self, c_int};
+
+/// Flags for sending and receiving messages.
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct MsgFlags(pub c_int);
+
+impl BitOr for MsgFlags {
+    type Output = MsgFlags;
+    fn bitor(self, other: MsgFlags) -> MsgFlags {
+        MsgFlags(self.0 | other.0)
+    }
+}
+
+impl BitAnd for MsgFlags {
+    type Output = MsgFlags;
+    fn bitand(self, other: MsgFlags) -> MsgFlags {
+        MsgFlags(self.0 & other.0)
+    }
+}
+
+impl Not for MsgFlags {
+    type Output = MsgFlags;
+    fn not(self) -> MsgFlags {
+        MsgFlags(!self.0)
+    }
+}
+
+/// Dummy flag with all flags unset.
+pub const MSG_NONE: MsgFlags = MsgFlags(0);
+
+macro_rules! create_flags {
+    ($($(#[$meta:meta])* flag $name:ident = $val:expr;)*) => {
+        $($(#[$meta])* pub const $name: MsgFlags = MsgFlags($val);)*
+
+        impl Debug for MsgFlags {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                let mut first = true;
+                $(
+                    if self.0 & $val != 0 {
+                        if !first { core::write!(f, "|")?; }
+                        first = false;
+                        core::write!(f, stringify!($name))?;
+                    }
+                )*
+                if first {
+                    core::write!(f, "MSG_NONE")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+create_flags! {
+    #[doc = "Send or receive out-of-band data.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:recv(2) and MSG_OOB therein"]
+    flag MSG_OUT_OF_BAND = kty::MSG_OOB;
+
+    #[doc = "Receive data without removing it from the queue.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:recv(2) and MSG_PEEK therein"]
+    flag MSG_PEEK = kty::MSG_PEEK;
+
+    #[doc = "Send the data only to directly connected hosts.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:send(2) and MSG_DONTROUTE therein"]
+    flag MSG_DONT_ROUTE = kty::MSG_DONTROUTE;
+
+    #[doc = "Return the real length of a datagram even if it was longer than the \
+             buffer.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:recv(2) and MSG_TRUNC therein"]
+    flag MSG_TRUNCATE = kty::MSG_TRUNC;
+
+    #[doc = "Perform the operation in non-blocking mode.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:recv(2) and MSG_DONTWAIT therein"]
+    flag MSG_DONT_WAIT = kty::MSG_DONTWAIT;
+
+    #[doc = "Terminate a record.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:send(2) and MSG_EOR therein"]
+    flag MSG_END_OF_RECORD = kty::MSG_EOR;
+
+    #[doc = "Block until the whole buffer has been filled.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:recv(2) and MSG_WAITALL therein"]
+    flag MSG_WAIT_ALL = kty::MSG_WAITALL;
+
+    #[doc = "Tell the link layer that the peer is reachable.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:send(2) and MSG_CONFIRM therein"]
+    flag MSG_CONFIRM = kty::MSG_CONFIRM;
+
+    #[doc = "Receive a queued error instead of data.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:recv(2) and MSG_ERRQUEUE therein"]
+    flag MSG_ERR_QUEUE = kty::MSG_ERRQUEUE;
+
+    #[doc = "Don't raise `SIGPIPE` if the peer has closed the connection.\n"]
+    #[doc = "= Remarks"]
+    #[doc = "`EPIPE` is still returned.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:send(2) and MSG_NOSIGNAL therein"]
+    flag MSG_NO_SIGNAL = kty::MSG_NOSIGNAL;
+
+    #[doc = "Hold back the data because more data will be sent.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:send(2) and MSG_MORE therein"]
+    flag MSG_MORE = kty::MSG_MORE;
+
+    #[doc = "Don't block after the first message has been received.\n"]
+    #[doc = "= See also"]
+    #[doc = "* link:man:recvmmsg(2) and MSG_WAITFORONE therein"]
+    flag MSG_WAIT_FOR_ONE = kty::MSG_WAITFORONE;
+}
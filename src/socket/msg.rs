@@ -0,0 +1,119 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    result::{Result},
+    lmem,
+    socket::{SockAddr, SockAddrBuf, MsgFlags, CmsgBuffer, Cmsgs},
+    util::{
+        retry::{retry},
+        data::{d8, DataSlice},
+    },
+};
+use crate::kty::{
+    c_int, c_void, msghdr, iovec, cmsghdr, user_size_t, MSG_CTRUNC, MSG_CMSG_CLOEXEC,
+};
+use crate::syscall::{sendmsg, recvmsg};
+
+/// Sends a message on a socket.
+///
+/// [argument, sockfd]
+/// The socket.
+///
+/// [argument, bufs]
+/// The buffers containing the data to send.
+///
+/// [argument, addr]
+/// The destination of the message if the socket is not connected.
+///
+/// [argument, control]
+/// Control messages to send with the data.
+///
+/// [return_value]
+/// Returns the number of bytes sent.
+///
+/// = See also
+///
+/// * link:man:sendmsg(2)
+/// * link:lrs::socket::CmsgBuffer
+pub fn send_msg(sockfd: c_int, bufs: &[&[d8]], addr: Option<&SockAddr>,
+                control: Option<&CmsgBuffer>, flags: MsgFlags) -> Result<usize> {
+    let mut msg: msghdr = lmem::zeroed();
+    msg.msg_iov = bufs.as_ptr() as *mut iovec;
+    msg.msg_iovlen = bufs.len() as user_size_t;
+    let addr = addr.map(|a| a.encode());
+    if let Some(ref addr) = addr {
+        msg.msg_name = addr.as_data().as_ptr() as *mut c_void;
+        msg.msg_namelen = addr.len() as c_int;
+    }
+    if let Some(control) = control {
+        let data = control.as_data();
+        if !data.is_empty() {
+            msg.msg_control = data.as_ptr() as *mut c_void;
+            msg.msg_controllen = data.len() as user_size_t;
+        }
+    }
+    retry(|| sendmsg(sockfd, &msg, flags.0)).map(|r| r as usize)
+}
+
+/// Receives a message from a socket.
+///
+/// [argument, sockfd]
+/// The socket.
+///
+/// [argument, bufs]
+/// The buffers into which the data will be received.
+///
+/// [argument, addr]
+/// An optional place where the source of the message will be stored.
+///
+/// [argument, control]
+/// The buffer into which control messages will be received. It is aligned
+/// automatically.
+///
+/// [argument, flags]
+/// Flags used while receiving.
+///
+/// [return_value]
+/// Returns the number of bytes received and the control messages.
+///
+/// = Remarks
+///
+/// Received file descriptors always have the close-on-exec flag set. If `control` is
+/// too small, the kernel discards the excess messages and closes the excess file
+/// descriptors. This is reported by `Cmsgs::is_truncated`.
+///
+/// = See also
+///
+/// * link:man:recvmsg(2)
+/// * link:lrs::socket::cmsg_space
+pub fn recv_msg<'a>(sockfd: c_int, bufs: &mut [&mut [d8]], addr: Option<&mut SockAddrBuf>,
+                    control: &'a mut [d8], flags: MsgFlags) -> Result<(usize, Cmsgs<'a>)> {
+    let control = control.align_for_mut::<cmsghdr>();
+    let mut msg: msghdr = lmem::zeroed();
+    msg.msg_iov = bufs.as_mut_ptr() as *mut iovec;
+    msg.msg_iovlen = bufs.len() as user_size_t;
+    if !control.is_empty() {
+        msg.msg_control = control.as_mut_ptr() as *mut c_void;
+        msg.msg_controllen = control.len() as user_size_t;
+    }
+    let addr = match addr {
+        Some(addr) => {
+            *addr = SockAddrBuf::new();
+            let data = addr.as_mut_data();
+            msg.msg_name = data.as_mut_ptr() as *mut c_void;
+            msg.msg_namelen = data.len() as c_int;
+            Some(addr)
+        },
+        _ => None,
+    };
+    let len = retry(|| recvmsg(sockfd, &mut msg, flags.0 | MSG_CMSG_CLOEXEC))? as usize;
+    if let Some(addr) = addr {
+        addr.set_len(msg.msg_namelen as usize);
+    }
+    let controllen = msg.msg_controllen as usize;
+    let truncated = msg.msg_flags as c_int & MSG_CTRUNC != 0;
+    let cmsgs = unsafe { Cmsgs::new(&control[..controllen], truncated) };
+    Ok((len, cmsgs))
+}
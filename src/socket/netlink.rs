@@ -0,0 +1,133 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use crate::{
+    result::{Result},
+    lmem,
+    fd::{FdContainer},
+    socket::{MsgFlags},
+    socket::{GetSockOpt, SetSockOpt, get_opt, set_opt},
+    socket::common::{self},
+    util::{
+        retry::{retry},
+        data::{d8},
+    },
+};
+use crate::kty::{
+    c_int, c_void, sa_family_t, msghdr, iovec, user_size_t, sockaddr_nl, AF_NETLINK,
+    SOCK_RAW,
+};
+use crate::syscall::{self, close, getsockname, sendmsg, recvmsg};
+
+/// A netlink socket.
+///
+/// = Remarks
+///
+/// The socket is bound to a port id chosen by the kernel and sends all messages to the
+/// kernel.
+///
+/// = See also
+///
+/// * link:man:netlink(7)
+/// * link:lrs::socket::rtnl
+pub struct NetlinkSocket {
+    fd: c_int,
+    owned: bool,
+}
+
+fn kernel_addr() -> sockaddr_nl {
+    sockaddr_nl {
+        nl_family: AF_NETLINK as sa_family_t,
+        .. lmem::zeroed()
+    }
+}
+
+impl NetlinkSocket {
+    /// Creates a netlink socket.
+    ///
+    /// [argument, protocol]
+    /// The netlink family to communicate with, e.g., `NETLINK_ROUTE`.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(2)
+    /// * link:man:bind(2)
+    pub fn new(protocol: c_int) -> Result<NetlinkSocket> {
+        let fd = rv!(syscall::socket(AF_NETLINK, SOCK_RAW, protocol), -> c_int)?;
+        let sock = NetlinkSocket::from_owned(fd);
+        rv!(syscall::bind(sock.fd, lmem::as_data(&kernel_addr())))?;
+        Ok(sock)
+    }
+
+    /// Returns the port id the socket is bound to.
+    ///
+    /// = See also
+    ///
+    /// * link:man:getsockname(2)
+    pub fn port_id(&self) -> Result<u32> {
+        let mut addr: sockaddr_nl = lmem::zeroed();
+        let mut len = 0;
+        rv!(getsockname(self.fd, lmem::as_mut_data(&mut addr), &mut len))?;
+        Ok(addr.nl_pid)
+    }
+
+    /// Sends a message to the kernel.
+    ///
+    /// [argument, bufs]
+    /// The buffers containing the message.
+    ///
+    /// [return_value]
+    /// Returns the number of bytes sent.
+    ///
+    /// = See also
+    ///
+    /// * link:man:sendmsg(2)
+    pub fn send(&self, bufs: &[&[u8]]) -> Result<usize> {
+        let addr = kernel_addr();
+        let mut msg: msghdr = lmem::zeroed();
+        msg.msg_name = &addr as *const _ as *mut c_void;
+        msg.msg_namelen = mem::size_of_val(&addr) as c_int;
+        msg.msg_iov = bufs.as_ptr() as *mut iovec;
+        msg.msg_iovlen = bufs.len() as user_size_t;
+        retry(|| sendmsg(self.fd, &msg, 0)).map(|r| r as usize)
+    }
+
+    /// Receives a message.
+    ///
+    /// [argument, buf]
+    /// The buffer into which the message is received.
+    ///
+    /// [argument, flags]
+    /// Flags used while receiving.
+    ///
+    /// [return_value]
+    /// Returns the length of the message and the port id of the sender.
+    ///
+    /// = Remarks
+    ///
+    /// Messages sent by the kernel have the port id `0`. If `MSG_TRUNCATE` is set, the
+    /// returned length is the real length of the message even if it is longer than the
+    /// buffer.
+    ///
+    /// = See also
+    ///
+    /// * link:man:recvmsg(2)
+    pub fn recv(&self, buf: &mut [d8], flags: MsgFlags) -> Result<(usize, u32)> {
+        let mut addr: sockaddr_nl = lmem::zeroed();
+        let mut iov = iovec {
+            iov_base: buf.as_mut_ptr() as *mut c_void,
+            iov_len: buf.len() as user_size_t,
+        };
+        let mut msg: msghdr = lmem::zeroed();
+        msg.msg_name = &mut addr as *mut _ as *mut c_void;
+        msg.msg_namelen = mem::size_of_val(&addr) as c_int;
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        let len = retry(|| recvmsg(self.fd, &mut msg, flags.0))? as usize;
+        Ok((len, addr.nl_pid))
+    }
+}
+
+impl_socket!(@base NetlinkSocket);
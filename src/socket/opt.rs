@@ -0,0 +1,462 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    result::{Result},
+    lmem,
+    time::{Time},
+    socket::{Credentials, TcpInfo},
+    util::{
+        pod::{Pod},
+        error::{Errno},
+    },
+};
+use crate::kty::{
+    c_int, linger, timeval, ucred, tcp_info, SOL_SOCKET, SOL_TCP, SOL_IP, SOL_IPV6,
+    SO_REUSEADDR, SO_REUSEPORT, SO_RCVBUF, SO_SNDBUF, SO_KEEPALIVE, SO_LINGER,
+    SO_RCVTIMEO, SO_SNDTIMEO, SO_PEERCRED, SO_ERROR, TCP_NODELAY, TCP_KEEPIDLE,
+    TCP_KEEPINTVL, TCP_KEEPCNT, TCP_INFO, IPV6_V6ONLY, IP_TTL,
+};
+use crate::syscall::{getsockopt, setsockopt};
+
+/// A socket option.
+///
+/// = Remarks
+///
+/// Each option in this module is a type that implements this trait and `GetSockOpt`
+/// and/or `SetSockOpt`. The options are used via `get_opt` and `set_opt` or the
+/// methods of the same name on the socket types.
+///
+/// = Examples
+///
+/// ----
+/// stream.set_opt::<TcpNoDelay>(true)?;
+/// let err = stream.get_opt::<Error>()?;
+/// ----
+pub trait SockOpt {
+    /// The protocol level of the option, e.g., `SOL_SOCKET`.
+    const LEVEL: c_int;
+    /// The name of the option, e.g., `SO_REUSEADDR`.
+    const NAME: c_int;
+    /// The representation of the option in the kernel.
+    type Raw: Pod;
+    /// The representation of the option in this crate.
+    type Value;
+}
+
+/// A socket option that can be retrieved.
+pub trait GetSockOpt: SockOpt {
+    /// Converts the kernel representation of the option.
+    fn from_raw(raw: Self::Raw) -> Self::Value;
+}
+
+/// A socket option that can be set.
+pub trait SetSockOpt: SockOpt {
+    /// Converts the option to its kernel representation.
+    fn to_raw(val: Self::Value) -> Self::Raw;
+}
+
+/// Retrieves a socket option.
+///
+/// [argument, sockfd]
+/// The socket.
+///
+/// = Remarks
+///
+/// If the kernel returns less data than the size of the raw option, the remaining
+/// bytes are zero.
+///
+/// = See also
+///
+/// * link:man:getsockopt(2)
+pub fn get_opt<O: GetSockOpt>(sockfd: c_int) -> Result<O::Value> {
+    let mut raw: O::Raw = lmem::zeroed();
+    let mut len = 0;
+    rv!(getsockopt(sockfd, O::LEVEL, O::NAME, lmem::as_mut_data(&mut raw), &mut len))?;
+    Ok(O::from_raw(raw))
+}
+
+/// Sets a socket option.
+///
+/// [argument, sockfd]
+/// The socket.
+///
+/// [argument, val]
+/// The new value of the option.
+///
+/// = See also
+///
+/// * link:man:setsockopt(2)
+pub fn set_opt<O: SetSockOpt>(sockfd: c_int, val: O::Value) -> Result {
+    let raw = O::to_raw(val);
+    rv!(setsockopt(sockfd, O::LEVEL, O::NAME, lmem::as_data(&raw)))
+}
+
+fn time_from_timeval(t: timeval) -> Time {
+    Time {
+        seconds: t.tv_sec,
+        nanoseconds: t.tv_usec * 1000,
+    }
+}
+
+fn time_to_timeval(t: Time) -> timeval {
+    timeval {
+        tv_sec: t.seconds as _,
+        tv_usec: (t.nanoseconds / 1000) as _,
+    }
+}
+
+fn time_from_secs(secs: c_int) -> Time {
+    Time {
+        seconds: secs as i64,
+        nanoseconds: 0,
+    }
+}
+
+fn time_to_secs(t: Time) -> c_int {
+    t.seconds as c_int
+}
+
+macro_rules! opt {
+    ($(#[$meta:meta])* $name:ident, $level:expr, $opt:expr, $raw:ty, $val:ty) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        pub struct $name;
+
+        impl SockOpt for $name {
+            const LEVEL: c_int = $level;
+            const NAME: c_int = $opt;
+            type Raw = $raw;
+            type Value = $val;
+        }
+    }
+}
+
+macro_rules! bool_opt {
+    ($(#[$meta:meta])* $name:ident, $level:expr, $opt:expr) => {
+        opt!($(#[$meta])* $name, $level, $opt, c_int, bool);
+
+        impl GetSockOpt for $name {
+            fn from_raw(raw: c_int) -> bool { raw != 0 }
+        }
+
+        impl SetSockOpt for $name {
+            fn to_raw(val: bool) -> c_int { val as c_int }
+        }
+    }
+}
+
+bool_opt! {
+    /// Whether the local address can be reused while old connections are in the
+    /// `TIME_WAIT` state.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(7) and SO_REUSEADDR therein
+    ReuseAddr, SOL_SOCKET, SO_REUSEADDR
+}
+
+bool_opt! {
+    /// Whether multiple sockets can be bound to the same address.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(7) and SO_REUSEPORT therein
+    ReusePort, SOL_SOCKET, SO_REUSEPORT
+}
+
+bool_opt! {
+    /// Whether keep-alive messages are sent on connection-oriented sockets.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(7) and SO_KEEPALIVE therein
+    KeepAlive, SOL_SOCKET, SO_KEEPALIVE
+}
+
+bool_opt! {
+    /// Whether segments are sent as soon as possible instead of being coalesced
+    /// (Nagle's algorithm).
+    ///
+    /// = See also
+    ///
+    /// * link:man:tcp(7) and TCP_NODELAY therein
+    TcpNoDelay, SOL_TCP, TCP_NODELAY
+}
+
+bool_opt! {
+    /// Whether an IPv6 socket is restricted to IPv6 communication.
+    ///
+    /// = Remarks
+    ///
+    /// This must be set before the socket is bound.
+    ///
+    /// = See also
+    ///
+    /// * link:man:ipv6(7) and IPV6_V6ONLY therein
+    Ipv6Only, SOL_IPV6, IPV6_V6ONLY
+}
+
+opt! {
+    /// The size of the receive buffer in bytes.
+    ///
+    /// = Remarks
+    ///
+    /// The kernel doubles the value that is set to allow space for bookkeeping. The
+    /// retrieved value is the doubled value.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(7) and SO_RCVBUF therein
+    RecvBuf, SOL_SOCKET, SO_RCVBUF, c_int, usize
+}
+
+opt! {
+    /// The size of the send buffer in bytes.
+    ///
+    /// = Remarks
+    ///
+    /// The kernel doubles the value that is set to allow space for bookkeeping. The
+    /// retrieved value is the doubled value.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(7) and SO_SNDBUF therein
+    SendBuf, SOL_SOCKET, SO_SNDBUF, c_int, usize
+}
+
+impl GetSockOpt for RecvBuf {
+    fn from_raw(raw: c_int) -> usize { raw as usize }
+}
+
+impl SetSockOpt for RecvBuf {
+    fn to_raw(val: usize) -> c_int { val.min(c_int::MAX as usize) as c_int }
+}
+
+impl GetSockOpt for SendBuf {
+    fn from_raw(raw: c_int) -> usize { raw as usize }
+}
+
+impl SetSockOpt for SendBuf {
+    fn to_raw(val: usize) -> c_int { val.min(c_int::MAX as usize) as c_int }
+}
+
+opt! {
+    /// How long closing a socket blocks while unsent data remains.
+    ///
+    /// = Remarks
+    ///
+    /// `None` means that `close` returns immediately and the data is sent in the
+    /// background. Only the seconds of the time are used.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(7) and SO_LINGER therein
+    Linger, SOL_SOCKET, SO_LINGER, linger, Option<Time>
+}
+
+impl GetSockOpt for Linger {
+    fn from_raw(raw: linger) -> Option<Time> {
+        match raw.l_onoff {
+            0 => None,
+            _ => Some(time_from_secs(raw.l_linger)),
+        }
+    }
+}
+
+impl SetSockOpt for Linger {
+    fn to_raw(val: Option<Time>) -> linger {
+        match val {
+            Some(t) => linger { l_onoff: 1, l_linger: time_to_secs(t) },
+            None => linger { l_onoff: 0, l_linger: 0 },
+        }
+    }
+}
+
+opt! {
+    /// The timeout of receive operations.
+    ///
+    /// = Remarks
+    ///
+    /// A zero time means that operations never time out. If an operation times out,
+    /// `EAGAIN` is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(7) and SO_RCVTIMEO therein
+    RecvTimeout, SOL_SOCKET, SO_RCVTIMEO, timeval, Time
+}
+
+opt! {
+    /// The timeout of send operations.
+    ///
+    /// = Remarks
+    ///
+    /// A zero time means that operations never time out. If an operation times out,
+    /// `EAGAIN` is returned.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(7) and SO_SNDTIMEO therein
+    SendTimeout, SOL_SOCKET, SO_SNDTIMEO, timeval, Time
+}
+
+impl GetSockOpt for RecvTimeout {
+    fn from_raw(raw: timeval) -> Time { time_from_timeval(raw) }
+}
+
+impl SetSockOpt for RecvTimeout {
+    fn to_raw(val: Time) -> timeval { time_to_timeval(val) }
+}
+
+impl GetSockOpt for SendTimeout {
+    fn from_raw(raw: timeval) -> Time { time_from_timeval(raw) }
+}
+
+impl SetSockOpt for SendTimeout {
+    fn to_raw(val: Time) -> timeval { time_to_timeval(val) }
+}
+
+opt! {
+    /// The credentials of the peer of a Unix domain socket.
+    ///
+    /// = Remarks
+    ///
+    /// The credentials are those that were in effect when the peer called `connect`
+    /// or `socketpair`. This option cannot be set.
+    ///
+    /// = See also
+    ///
+    /// * link:man:unix(7) and SO_PEERCRED therein
+    PeerCred, SOL_SOCKET, SO_PEERCRED, ucred, Credentials
+}
+
+impl GetSockOpt for PeerCred {
+    fn from_raw(raw: ucred) -> Credentials {
+        Credentials {
+            pid: raw.pid as _,
+            uid: raw.uid,
+            gid: raw.gid,
+        }
+    }
+}
+
+opt! {
+    /// The pending error of the socket.
+    ///
+    /// = Remarks
+    ///
+    /// Retrieving the option clears the error. This is commonly used to retrieve the
+    /// result of a non-blocking `connect`. This option cannot be set.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socket(7) and SO_ERROR therein
+    Error, SOL_SOCKET, SO_ERROR, c_int, Option<Errno>
+}
+
+impl GetSockOpt for Error {
+    fn from_raw(raw: c_int) -> Option<Errno> {
+        match raw {
+            0 => None,
+            e => Some(Errno(e)),
+        }
+    }
+}
+
+opt! {
+    /// The idle time after which TCP starts sending keep-alive probes.
+    ///
+    /// = Remarks
+    ///
+    /// Only the seconds of the time are used.
+    ///
+    /// = See also
+    ///
+    /// * link:man:tcp(7) and TCP_KEEPIDLE therein
+    TcpKeepIdle, SOL_TCP, TCP_KEEPIDLE, c_int, Time
+}
+
+opt! {
+    /// The time between TCP keep-alive probes.
+    ///
+    /// = Remarks
+    ///
+    /// Only the seconds of the time are used.
+    ///
+    /// = See also
+    ///
+    /// * link:man:tcp(7) and TCP_KEEPINTVL therein
+    TcpKeepInterval, SOL_TCP, TCP_KEEPINTVL, c_int, Time
+}
+
+impl GetSockOpt for TcpKeepIdle {
+    fn from_raw(raw: c_int) -> Time { time_from_secs(raw) }
+}
+
+impl SetSockOpt for TcpKeepIdle {
+    fn to_raw(val: Time) -> c_int { time_to_secs(val) }
+}
+
+impl GetSockOpt for TcpKeepInterval {
+    fn from_raw(raw: c_int) -> Time { time_from_secs(raw) }
+}
+
+impl SetSockOpt for TcpKeepInterval {
+    fn to_raw(val: Time) -> c_int { time_to_secs(val) }
+}
+
+opt! {
+    /// The number of unanswered TCP keep-alive probes after which the connection is
+    /// dropped.
+    ///
+    /// = See also
+    ///
+    /// * link:man:tcp(7) and TCP_KEEPCNT therein
+    TcpKeepCount, SOL_TCP, TCP_KEEPCNT, c_int, u32
+}
+
+impl GetSockOpt for TcpKeepCount {
+    fn from_raw(raw: c_int) -> u32 { raw as u32 }
+}
+
+impl SetSockOpt for TcpKeepCount {
+    fn to_raw(val: u32) -> c_int { val.min(c_int::MAX as u32) as c_int }
+}
+
+opt! {
+    /// Information about a TCP connection.
+    ///
+    /// = Remarks
+    ///
+    /// This option cannot be set.
+    ///
+    /// = See also
+    ///
+    /// * link:man:tcp(7) and TCP_INFO therein
+    TcpInfoOpt, SOL_TCP, TCP_INFO, tcp_info, TcpInfo
+}
+
+impl GetSockOpt for TcpInfoOpt {
+    fn from_raw(raw: tcp_info) -> TcpInfo {
+        TcpInfo::from_raw(raw)
+    }
+}
+
+opt! {
+    /// The time-to-live field of outgoing IPv4 packets.
+    ///
+    /// = See also
+    ///
+    /// * link:man:ip(7) and IP_TTL therein
+    IpTtl, SOL_IP, IP_TTL, c_int, u8
+}
+
+impl GetSockOpt for IpTtl {
+    fn from_raw(raw: c_int) -> u8 { raw as u8 }
+}
+
+impl SetSockOpt for IpTtl {
+    fn to_raw(val: u8) -> c_int { val as c_int }
+}
@@ -0,0 +1,741 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::{mem};
+use core::fmt::{self, Debug, Formatter};
+use alloc::{vec, vec::Vec};
+use memchr::{memchr};
+use crate::{
+    result::{Result},
+    lmem,
+    socket::{NetlinkSocket, IpAddr, MSG_NONE, MSG_PEEK, MSG_TRUNCATE},
+    string::{ByteStr},
+    util::{
+        data::{d8, DataSlice},
+        error::{self, Errno},
+        pod::{Pod},
+    },
+};
+use crate::kty::{
+    c_int, c_uint, c_ushort, nlmsghdr, rtattr, ifinfomsg, ifaddrmsg, rtmsg, NETLINK_ROUTE,
+    AF_UNSPEC, AF_INET, AF_INET6, NLM_F_REQUEST, NLM_F_MULTI, NLM_F_ACK, NLM_F_DUMP_INTR,
+    NLM_F_DUMP, NLM_F_CREATE, NLM_F_EXCL, NLMSG_NOOP, NLMSG_ERROR, NLMSG_DONE,
+    NLMSG_OVERRUN, NLA_TYPE_MASK, RTM_NEWLINK, RTM_GETLINK, RTM_NEWADDR, RTM_DELADDR,
+    RTM_GETADDR, RTM_NEWROUTE, RTM_GETROUTE, IFLA_ADDRESS, IFLA_IFNAME, IFLA_MTU,
+    IFA_ADDRESS, IFA_LOCAL, IFA_LABEL, IFA_FLAGS, RTA_DST, RTA_OIF, RTA_GATEWAY,
+    RTA_PRIORITY, RTA_PREFSRC, RTA_TABLE, IFF_UP, IFF_LOOPBACK, IFF_RUNNING, NLMSG_ALIGN,
+    NLMSG_LENGTH, RTA_ALIGN, RTA_LENGTH, RT_SCOPE_HOST, RT_SCOPE_UNIVERSE,
+};
+
+/// The initial size of the receive buffer. It grows if a larger message arrives.
+const BUF_SIZE: usize = 32768;
+
+fn read<T: Pod>(bytes: &[u8]) -> Option<T> {
+    if bytes.len() < mem::size_of::<T>() {
+        return None;
+    }
+    let mut val: T = lmem::zeroed();
+    lmem::copy(lmem::as_mut_data(&mut val), d8::from_byte_slice(bytes));
+    Some(val)
+}
+
+fn ip_attr(family: c_int, data: &[u8]) -> Option<IpAddr> {
+    match IpAddr::from_bytes(data) {
+        Ok(addr) if addr.family() == family => Some(addr),
+        _ => None,
+    }
+}
+
+/// A netlink request under construction.
+///
+/// = Remarks
+///
+/// The request consists of a `nlmsghdr`, a family-specific header, and a sequence of
+/// `rtattr` attributes. The length and sequence number of the message are filled in
+/// automatically.
+///
+/// = Examples
+///
+/// ----
+/// let hdr: ifinfomsg = lmem::zeroed();
+/// let mut req = Request::new(RTM_GETLINK, NLM_F_DUMP, &hdr);
+/// req.attr(IFLA_EXT_MASK, lmem::as_data(&mask))?;
+/// ----
+pub struct Request {
+    buf: Vec<u8>,
+}
+
+impl Request {
+    /// Creates a new request.
+    ///
+    /// [argument, ty]
+    /// The message type, e.g., `RTM_GETLINK`.
+    ///
+    /// [argument, flags]
+    /// The message flags. `NLM_F_REQUEST` is always added.
+    ///
+    /// [argument, header]
+    /// The family-specific header, e.g., `ifinfomsg`.
+    pub fn new<H: Pod>(ty: u16, flags: u16, header: &H) -> Request {
+        let hdr = nlmsghdr {
+            nlmsg_len: 0,
+            nlmsg_type: ty,
+            nlmsg_flags: flags | NLM_F_REQUEST,
+            nlmsg_seq: 0,
+            nlmsg_pid: 0,
+        };
+        let mut req = Request { buf: Vec::new() };
+        req.push(unsafe { lmem::as_data(&hdr).as_bytes() });
+        req.push(unsafe { lmem::as_data(header).as_bytes() });
+        req
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+        let len = NLMSG_ALIGN(self.buf.len() as c_uint) as usize;
+        self.buf.resize(len, 0);
+        let len = len as u32;
+        self.buf[..4].copy_from_slice(&len.to_ne_bytes());
+    }
+
+    /// Appends an attribute.
+    ///
+    /// [argument, ty]
+    /// The attribute type, e.g., `IFA_LOCAL`.
+    ///
+    /// [argument, data]
+    /// The payload of the attribute.
+    ///
+    /// = Remarks
+    ///
+    /// The payload must be shorter than 65532 bytes. Otherwise `EINVAL` is returned.
+    pub fn attr(&mut self, ty: u16, data: &[u8]) -> Result<&mut Request> {
+        if data.len() >= 65532 {
+            return Err(error::InvalidArgument);
+        }
+        let rta = rtattr {
+            rta_len: RTA_LENGTH(data.len() as c_uint) as c_ushort,
+            rta_type: ty,
+        };
+        self.buf.extend_from_slice(unsafe { lmem::as_data(&rta).as_bytes() });
+        self.push(data);
+        Ok(self)
+    }
+
+    fn flags(&self) -> u16 {
+        read::<nlmsghdr>(&self.buf).map(|h| h.nlmsg_flags).unwrap_or(0)
+    }
+
+    fn set_seq(&mut self, seq: u32) {
+        self.buf[8..12].copy_from_slice(&seq.to_ne_bytes());
+    }
+
+    /// Returns the encoded message.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+/// A netlink message.
+#[derive(Copy, Clone)]
+pub struct Message<'a> {
+    hdr: nlmsghdr,
+    payload: &'a [u8],
+}
+
+impl<'a> Message<'a> {
+    /// Returns the message type.
+    pub fn ty(&self) -> u16 {
+        self.hdr.nlmsg_type
+    }
+
+    /// Returns the message flags.
+    pub fn flags(&self) -> u16 {
+        self.hdr.nlmsg_flags
+    }
+
+    /// Returns the sequence number.
+    pub fn seq(&self) -> u32 {
+        self.hdr.nlmsg_seq
+    }
+
+    /// Returns the port id of the socket the message belongs to.
+    pub fn port_id(&self) -> u32 {
+        self.hdr.nlmsg_pid
+    }
+
+    /// Returns the payload following the `nlmsghdr`.
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// Returns a copy of the family-specific header.
+    ///
+    /// = Remarks
+    ///
+    /// If the payload is too short, `None` is returned.
+    pub fn header<H: Pod>(&self) -> Option<H> {
+        read(self.payload)
+    }
+
+    /// Returns an iterator over the attributes following the family-specific header.
+    pub fn attrs<H: Pod>(&self) -> Attrs<'a> {
+        let start = NLMSG_ALIGN(mem::size_of::<H>() as c_uint) as usize;
+        match self.payload.get(start..) {
+            Some(attrs) => Attrs::new(attrs),
+            _ => Attrs::new(&[]),
+        }
+    }
+}
+
+impl<'a> Debug for Message<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Message")
+            .field("ty", &self.ty())
+            .field("flags", &self.flags())
+            .field("seq", &self.seq())
+            .field("port_id", &self.port_id())
+            .field("len", &self.payload.len())
+            .finish()
+    }
+}
+
+/// An iterator over the netlink messages in a buffer.
+///
+/// = Remarks
+///
+/// If a message header is malformed, `EPROTO` is yielded and the iteration stops.
+pub struct Messages<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Messages<'a> {
+    /// Creates a new iterator.
+    ///
+    /// [argument, buf]
+    /// The buffer containing the messages, e.g., a datagram received from a netlink
+    /// socket.
+    pub fn new(buf: &'a [u8]) -> Messages<'a> {
+        Messages { buf }
+    }
+}
+
+impl<'a> Iterator for Messages<'a> {
+    type Item = Result<Message<'a>>;
+
+    fn next(&mut self) -> Option<Result<Message<'a>>> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let buf = self.buf;
+        let hdr = read::<nlmsghdr>(buf);
+        let len = hdr.map(|h| h.nlmsg_len as usize).unwrap_or(0);
+        let start = NLMSG_LENGTH(0) as usize;
+        if len < start || len > buf.len() {
+            self.buf = &[];
+            return Some(Err(error::ProtocolError));
+        }
+        let next = NLMSG_ALIGN(len as c_uint) as usize;
+        self.buf = buf.get(next..).unwrap_or(&[]);
+        Some(Ok(Message {
+            hdr: hdr.unwrap(),
+            payload: &buf[start..len],
+        }))
+    }
+}
+
+/// An iterator over the `rtattr` attributes in a buffer.
+///
+/// = Remarks
+///
+/// The iterator yields the type and the payload of each attribute. The
+/// `NLA_F_NESTED` and `NLA_F_NET_BYTEORDER` bits are removed from the type. The
+/// iteration stops at the first malformed attribute.
+pub struct Attrs<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Attrs<'a> {
+    /// Creates a new iterator.
+    ///
+    /// [argument, buf]
+    /// The buffer containing the attributes, e.g., the payload of a nested attribute.
+    pub fn new(buf: &'a [u8]) -> Attrs<'a> {
+        Attrs { buf }
+    }
+}
+
+impl<'a> Iterator for Attrs<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<(u16, &'a [u8])> {
+        let buf = self.buf;
+        let rta = read::<rtattr>(buf)?;
+        let len = rta.rta_len as usize;
+        let start = RTA_LENGTH(0) as usize;
+        if len < start || len > buf.len() {
+            self.buf = &[];
+            return None;
+        }
+        let next = RTA_ALIGN(len as c_uint) as usize;
+        self.buf = buf.get(next..).unwrap_or(&[]);
+        Some((rta.rta_type & NLA_TYPE_MASK as u16, &buf[start..len]))
+    }
+}
+
+/// A network interface.
+///
+/// = See also
+///
+/// * link:man:rtnetlink(7) and RTM_NEWLINK therein
+#[derive(Clone, PartialEq, Eq)]
+pub struct Link {
+    /// The interface index.
+    pub index: u32,
+    /// The device type, e.g., `ARPHRD_LOOPBACK`.
+    pub ty: u16,
+    /// The `IFF_*` flags of the interface.
+    pub flags: u32,
+    /// The name of the interface.
+    pub name: Vec<u8>,
+    /// The MTU of the interface.
+    pub mtu: Option<u32>,
+    /// The hardware address of the interface.
+    pub address: Vec<u8>,
+}
+
+impl Link {
+    fn from_message(msg: &Message) -> Result<Link> {
+        let hdr: ifinfomsg = msg.header().ok_or(error::ProtocolError)?;
+        let mut link = Link {
+            index: hdr.ifi_index as u32,
+            ty: hdr.ifi_type,
+            flags: hdr.ifi_flags,
+            name: Vec::new(),
+            mtu: None,
+            address: Vec::new(),
+        };
+        for (ty, data) in msg.attrs::<ifinfomsg>() {
+            match ty {
+                IFLA_IFNAME => {
+                    let len = memchr(0, data).unwrap_or(data.len());
+                    link.name = data[..len].to_vec();
+                },
+                IFLA_MTU => link.mtu = read(data),
+                IFLA_ADDRESS => link.address = data.to_vec(),
+                _ => { },
+            }
+        }
+        Ok(link)
+    }
+
+    /// Returns whether the interface is up.
+    pub fn is_up(&self) -> bool {
+        self.flags & IFF_UP as u32 != 0
+    }
+
+    /// Returns whether the interface is running.
+    pub fn is_running(&self) -> bool {
+        self.flags & IFF_RUNNING as u32 != 0
+    }
+
+    /// Returns whether the interface is a loopback interface.
+    pub fn is_loopback(&self) -> bool {
+        self.flags & IFF_LOOPBACK as u32 != 0
+    }
+}
+
+impl Debug for Link {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name: &ByteStr = self.name[..].as_ref();
+        f.debug_struct("Link")
+            .field("index", &self.index)
+            .field("ty", &self.ty)
+            .field("flags", &self.flags)
+            .field("name", &name)
+            .field("mtu", &self.mtu)
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+/// An address assigned to a network interface.
+///
+/// = See also
+///
+/// * link:man:rtnetlink(7) and RTM_NEWADDR therein
+#[derive(Clone, PartialEq, Eq)]
+pub struct Address {
+    /// The index of the interface.
+    pub index: u32,
+    /// The length of the prefix.
+    pub prefix_len: u8,
+    /// The scope of the address, e.g., `RT_SCOPE_HOST`.
+    pub scope: u8,
+    /// The `IFA_F_*` flags of the address.
+    pub flags: u32,
+    /// The address. On point-to-point interfaces, this is the address of the peer.
+    pub address: Option<IpAddr>,
+    /// The local address.
+    pub local: Option<IpAddr>,
+    /// The label of the address.
+    pub label: Vec<u8>,
+}
+
+impl Address {
+    fn from_message(msg: &Message) -> Result<Option<Address>> {
+        let hdr: ifaddrmsg = msg.header().ok_or(error::ProtocolError)?;
+        let family = hdr.ifa_family as c_int;
+        if family != AF_INET && family != AF_INET6 {
+            return Ok(None);
+        }
+        let mut addr = Address {
+            index: hdr.ifa_index,
+            prefix_len: hdr.ifa_prefixlen,
+            scope: hdr.ifa_scope,
+            flags: hdr.ifa_flags as u32,
+            address: None,
+            local: None,
+            label: Vec::new(),
+        };
+        for (ty, data) in msg.attrs::<ifaddrmsg>() {
+            match ty {
+                IFA_ADDRESS => addr.address = ip_attr(family, data),
+                IFA_LOCAL => addr.local = ip_attr(family, data),
+                IFA_LABEL => {
+                    let len = memchr(0, data).unwrap_or(data.len());
+                    addr.label = data[..len].to_vec();
+                },
+                IFA_FLAGS => addr.flags = read(data).unwrap_or(addr.flags),
+                _ => { },
+            }
+        }
+        Ok(Some(addr))
+    }
+}
+
+impl Debug for Address {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let label: &ByteStr = self.label[..].as_ref();
+        f.debug_struct("Address")
+            .field("index", &self.index)
+            .field("prefix_len", &self.prefix_len)
+            .field("scope", &self.scope)
+            .field("flags", &self.flags)
+            .field("address", &self.address)
+            .field("local", &self.local)
+            .field("label", &label)
+            .finish()
+    }
+}
+
+/// An entry in a routing table.
+///
+/// = See also
+///
+/// * link:man:rtnetlink(7) and RTM_NEWROUTE therein
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    /// The address family of the route.
+    pub family: c_int,
+    /// The length of the destination prefix.
+    pub dst_len: u8,
+    /// The length of the source prefix.
+    pub src_len: u8,
+    /// The TOS filter.
+    pub tos: u8,
+    /// The routing table, e.g., `RT_TABLE_MAIN`.
+    pub table: u32,
+    /// The origin of the route, e.g., `RTPROT_KERNEL`.
+    pub protocol: u8,
+    /// The scope of the route, e.g., `RT_SCOPE_LINK`.
+    pub scope: u8,
+    /// The type of the route, e.g., `RTN_UNICAST`.
+    pub ty: u8,
+    /// The destination prefix. `None` for the default route.
+    pub dst: Option<IpAddr>,
+    /// The gateway.
+    pub gateway: Option<IpAddr>,
+    /// The preferred source address.
+    pub prefsrc: Option<IpAddr>,
+    /// The index of the output interface.
+    pub oif: Option<u32>,
+    /// The priority of the route.
+    pub priority: Option<u32>,
+}
+
+impl Route {
+    fn from_message(msg: &Message) -> Result<Option<Route>> {
+        let hdr: rtmsg = msg.header().ok_or(error::ProtocolError)?;
+        let family = hdr.rtm_family as c_int;
+        if family != AF_INET && family != AF_INET6 {
+            return Ok(None);
+        }
+        let mut route = Route {
+            family,
+            dst_len: hdr.rtm_dst_len,
+            src_len: hdr.rtm_src_len,
+            tos: hdr.rtm_tos,
+            table: hdr.rtm_table as u32,
+            protocol: hdr.rtm_protocol,
+            scope: hdr.rtm_scope,
+            ty: hdr.rtm_type,
+            dst: None,
+            gateway: None,
+            prefsrc: None,
+            oif: None,
+            priority: None,
+        };
+        for (ty, data) in msg.attrs::<rtmsg>() {
+            match ty as c_int {
+                RTA_DST => route.dst = ip_attr(family, data),
+                RTA_GATEWAY => route.gateway = ip_attr(family, data),
+                RTA_PREFSRC => route.prefsrc = ip_attr(family, data),
+                RTA_OIF => route.oif = read(data),
+                RTA_PRIORITY => route.priority = read(data),
+                RTA_TABLE => route.table = read(data).unwrap_or(route.table),
+                _ => { },
+            }
+        }
+        Ok(Some(route))
+    }
+}
+
+/// A NETLINK_ROUTE client.
+///
+/// = Remarks
+///
+/// Errors reported by the kernel in `NLMSG_ERROR` messages are returned as `Errno`.
+/// Dumps that are interrupted by concurrent changes (`NLM_F_DUMP_INTR`) are restarted
+/// automatically.
+///
+/// = Examples
+///
+/// ----
+/// let mut rtnl = Rtnl::new()?;
+/// for link in rtnl.links()? {
+///     println!("{}: {:?}", link.index, link.name.as_ref() as &ByteStr);
+/// }
+/// ----
+///
+/// = See also
+///
+/// * link:man:rtnetlink(7)
+pub struct Rtnl {
+    sock: NetlinkSocket,
+    port_id: u32,
+    seq: u32,
+    buf: Vec<u8>,
+}
+
+impl Rtnl {
+    /// Creates a new client.
+    pub fn new() -> Result<Rtnl> {
+        let sock = NetlinkSocket::new(NETLINK_ROUTE)?;
+        let port_id = sock.port_id()?;
+        Ok(Rtnl {
+            sock,
+            port_id,
+            seq: 0,
+            buf: vec!(0; BUF_SIZE),
+        })
+    }
+
+    /// Returns the underlying socket.
+    pub fn socket(&self) -> &NetlinkSocket {
+        &self.sock
+    }
+
+    /// Sends a request and processes the responses.
+    ///
+    /// [argument, req]
+    /// The request. Its sequence number is overwritten.
+    ///
+    /// [argument, f]
+    /// A function called for every response that is not a control message.
+    ///
+    /// [return_value]
+    /// Returns whether a dump was interrupted by concurrent changes.
+    ///
+    /// = Remarks
+    ///
+    /// The function returns after the `NLMSG_DONE` message of a dump, after an
+    /// `NLMSG_ERROR` message, or after a response that is not part of a multipart
+    /// message. Responses to earlier requests are ignored.
+    pub fn execute<F>(&mut self, req: &mut Request, mut f: F) -> Result<bool>
+        where F: FnMut(&Message) -> Result,
+    {
+        self.seq = self.seq.wrapping_add(1);
+        let seq = self.seq;
+        req.set_seq(seq);
+        self.sock.send(&[req.as_bytes()])?;
+
+        let dump = req.flags() & NLM_F_DUMP == NLM_F_DUMP;
+        let mut interrupted = false;
+        loop {
+            let (len, _) = self.sock.recv(d8::from_byte_slice_mut(&mut self.buf),
+                                          MSG_PEEK | MSG_TRUNCATE)?;
+            if len > self.buf.len() {
+                self.buf.resize(len, 0);
+            }
+            let (len, sender) = self.sock.recv(d8::from_byte_slice_mut(&mut self.buf),
+                                               MSG_NONE)?;
+            if sender != 0 {
+                continue;
+            }
+            for msg in Messages::new(&self.buf[..len]) {
+                let msg = msg?;
+                if msg.port_id() != self.port_id || msg.seq() != seq {
+                    continue;
+                }
+                if msg.flags() & NLM_F_DUMP_INTR != 0 {
+                    interrupted = true;
+                }
+                match msg.ty() {
+                    NLMSG_NOOP => { },
+                    NLMSG_ERROR => {
+                        let err: c_int = msg.header().ok_or(error::ProtocolError)?;
+                        return match err {
+                            0 => Ok(interrupted),
+                            _ => Err(Errno(-err)),
+                        };
+                    },
+                    NLMSG_DONE => {
+                        // The kernel may report errors that occur during a dump here.
+                        return match msg.header::<c_int>() {
+                            Some(err) if err < 0 => Err(Errno(-err)),
+                            _ => Ok(interrupted),
+                        };
+                    },
+                    NLMSG_OVERRUN => return Err(error::KernelBuffersBusy),
+                    _ => {
+                        f(&msg)?;
+                        if !dump && msg.flags() & NLM_F_MULTI == 0
+                                && req.flags() & NLM_F_ACK == 0 {
+                            return Ok(interrupted);
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    fn dump<T, F>(&mut self, req: &mut Request, mut f: F) -> Result<Vec<T>>
+        where F: FnMut(&Message) -> Result<Option<T>>,
+    {
+        loop {
+            let mut res = Vec::new();
+            let interrupted = self.execute(req, |msg| {
+                if let Some(val) = f(msg)? {
+                    res.push(val);
+                }
+                Ok(())
+            })?;
+            if !interrupted {
+                return Ok(res);
+            }
+        }
+    }
+
+    /// Retrieves all network interfaces.
+    pub fn links(&mut self) -> Result<Vec<Link>> {
+        let hdr = ifinfomsg { ifi_family: AF_UNSPEC as u8, .. lmem::zeroed() };
+        let mut req = Request::new(RTM_GETLINK, NLM_F_DUMP, &hdr);
+        self.dump(&mut req, |msg| match msg.ty() {
+            RTM_NEWLINK => Link::from_message(msg).map(Some),
+            _ => Ok(None),
+        })
+    }
+
+    /// Retrieves all IPv4 and IPv6 addresses.
+    pub fn addresses(&mut self) -> Result<Vec<Address>> {
+        let hdr = ifaddrmsg { ifa_family: AF_UNSPEC as u8, .. lmem::zeroed() };
+        let mut req = Request::new(RTM_GETADDR, NLM_F_DUMP, &hdr);
+        self.dump(&mut req, |msg| match msg.ty() {
+            RTM_NEWADDR => Address::from_message(msg),
+            _ => Ok(None),
+        })
+    }
+
+    /// Retrieves all IPv4 and IPv6 routes.
+    pub fn routes(&mut self) -> Result<Vec<Route>> {
+        let hdr = rtmsg { rtm_family: AF_UNSPEC as u8, .. lmem::zeroed() };
+        let mut req = Request::new(RTM_GETROUTE, NLM_F_DUMP, &hdr);
+        self.dump(&mut req, |msg| match msg.ty() {
+            RTM_NEWROUTE => Route::from_message(msg),
+            _ => Ok(None),
+        })
+    }
+
+    fn address_request(ty: u16, flags: u16, index: u32, addr: IpAddr,
+                       prefix_len: u8) -> Result<Request> {
+        let scope = match addr {
+            IpAddr::V4(a) if a.is_loopback() => RT_SCOPE_HOST,
+            IpAddr::V6(a) if a.is_loopback() => RT_SCOPE_HOST,
+            _ => RT_SCOPE_UNIVERSE,
+        };
+        let hdr = ifaddrmsg {
+            ifa_family: addr.family() as u8,
+            ifa_prefixlen: prefix_len,
+            ifa_flags: 0,
+            ifa_scope: scope,
+            ifa_index: index,
+        };
+        let mut buf = [0; 16];
+        let bytes = addr.to_bytes(&mut buf);
+        let mut req = Request::new(ty, flags | NLM_F_ACK, &hdr);
+        req.attr(IFA_LOCAL, bytes)?.attr(IFA_ADDRESS, bytes)?;
+        Ok(req)
+    }
+
+    /// Adds an address to a network interface.
+    ///
+    /// [argument, index]
+    /// The index of the interface.
+    ///
+    /// [argument, addr]
+    /// The address.
+    ///
+    /// [argument, prefix_len]
+    /// The length of the network prefix.
+    ///
+    /// = Remarks
+    ///
+    /// Loopback addresses get the scope `RT_SCOPE_HOST`, all other addresses get the
+    /// scope `RT_SCOPE_UNIVERSE`. If the address already exists, `EEXIST` is returned.
+    /// This requires `CAP_NET_ADMIN`.
+    pub fn add_address(&mut self, index: u32, addr: IpAddr, prefix_len: u8) -> Result {
+        let mut req = Rtnl::address_request(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL,
+                                            index, addr, prefix_len)?;
+        self.execute(&mut req, |_| Ok(())).map(|_| ())
+    }
+
+    /// Removes an address from a network interface.
+    ///
+    /// [argument, index]
+    /// The index of the interface.
+    ///
+    /// [argument, addr]
+    /// The address.
+    ///
+    /// [argument, prefix_len]
+    /// The length of the network prefix.
+    ///
+    /// = Remarks
+    ///
+    /// This requires `CAP_NET_ADMIN`.
+    pub fn del_address(&mut self, index: u32, addr: IpAddr, prefix_len: u8) -> Result {
+        let mut req = Rtnl::address_request(RTM_DELADDR, 0, index, addr, prefix_len)?;
+        self.execute(&mut req, |_| Ok(())).map(|_| ())
+    }
+}
+
+impl Debug for Rtnl {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Rtnl")
+            .field("port_id", &self.port_id)
+            .field("seq", &self.seq)
+            .finish()
+    }
+}
@@ -0,0 +1,80 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    result::{Result},
+    fd::{FdContainer},
+    socket::{SockAddr, Shutdown, MSG_NONE, MSG_NO_SIGNAL, send_msg},
+    socket::{GetSockOpt, SetSockOpt, get_opt, set_opt},
+    socket::common::{self, BACKLOG},
+    socket::opt::{ReuseAddr},
+    util::{
+        retry::{retry},
+        data::{d8},
+        io::{Read, Write},
+    },
+};
+use crate::kty::{c_int, SOCK_STREAM};
+use crate::syscall::{close, readv};
+
+/// A TCP socket that accepts connections.
+pub struct TcpListener {
+    fd: c_int,
+    owned: bool,
+}
+
+impl TcpListener {
+    /// Creates a socket that listens on an address.
+    ///
+    /// [argument, addr]
+    /// The IPv4 or IPv6 address to listen on. If the port is `0`, the kernel picks an
+    /// unused port which can be retrieved with `local_addr`.
+    ///
+    /// = Remarks
+    ///
+    /// `SO_REUSEADDR` is set on the socket so that the address can be reused
+    /// immediately after the socket has been closed. The backlog is 128.
+    ///
+    /// = See also
+    ///
+    /// * link:man:bind(2)
+    /// * link:man:listen(2)
+    pub fn bind(addr: &SockAddr) -> Result<TcpListener> {
+        common::check_inet(addr)?;
+        let sock = TcpListener::from_owned(common::socket(addr.family(), SOCK_STREAM)?);
+        sock.set_opt::<ReuseAddr>(true)?;
+        common::bind(sock.fd, addr)?;
+        common::listen(sock.fd, BACKLOG)?;
+        Ok(sock)
+    }
+}
+
+impl_socket!(TcpListener);
+impl_listener!(TcpListener, TcpStream);
+
+/// A connected TCP socket.
+pub struct TcpStream {
+    fd: c_int,
+    owned: bool,
+}
+
+impl TcpStream {
+    /// Connects to an address.
+    ///
+    /// [argument, addr]
+    /// The IPv4 or IPv6 address to connect to.
+    ///
+    /// = See also
+    ///
+    /// * link:man:connect(2)
+    pub fn connect(addr: &SockAddr) -> Result<TcpStream> {
+        common::check_inet(addr)?;
+        let sock = TcpStream::from_owned(common::socket(addr.family(), SOCK_STREAM)?);
+        common::connect(sock.fd, addr)?;
+        Ok(sock)
+    }
+}
+
+impl_socket!(TcpStream);
+impl_connected!(TcpStream);
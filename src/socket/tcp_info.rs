@@ -0,0 +1,243 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use core::fmt::{self, Debug, Formatter};
+use crate::{
+    time::{Time},
+};
+use crate::kty::{
+    tcp_info, TCP_ESTABLISHED, TCP_SYN_SENT, TCP_SYN_RECV, TCP_FIN_WAIT1, TCP_FIN_WAIT2,
+    TCP_TIME_WAIT, TCP_CLOSE, TCP_CLOSE_WAIT, TCP_LAST_ACK, TCP_LISTEN, TCP_CLOSING,
+};
+
+/// The state of a TCP connection.
+///
+/// = See also
+///
+/// * RFC 793
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TcpState {
+    /// The connection is open.
+    Established,
+    /// A connection request has been sent.
+    SynSent,
+    /// A connection request has been received.
+    SynRecv,
+    /// The local end has been closed.
+    FinWait1,
+    /// The local end has been closed and acknowledged.
+    FinWait2,
+    /// Both ends have been closed and the socket waits for stray segments.
+    TimeWait,
+    /// The connection is closed.
+    Close,
+    /// The remote end has been closed.
+    CloseWait,
+    /// Both ends have been closed and the socket waits for the last acknowledgement.
+    LastAck,
+    /// The socket is listening.
+    Listen,
+    /// Both ends are being closed simultaneously.
+    Closing,
+    /// A state not known to this crate.
+    Unknown(u8),
+}
+
+impl TcpState {
+    fn from_raw(state: u8) -> TcpState {
+        match state {
+            TCP_ESTABLISHED => TcpState::Established,
+            TCP_SYN_SENT => TcpState::SynSent,
+            TCP_SYN_RECV => TcpState::SynRecv,
+            TCP_FIN_WAIT1 => TcpState::FinWait1,
+            TCP_FIN_WAIT2 => TcpState::FinWait2,
+            TCP_TIME_WAIT => TcpState::TimeWait,
+            TCP_CLOSE => TcpState::Close,
+            TCP_CLOSE_WAIT => TcpState::CloseWait,
+            TCP_LAST_ACK => TcpState::LastAck,
+            TCP_LISTEN => TcpState::Listen,
+            TCP_CLOSING => TcpState::Closing,
+            s => TcpState::Unknown(s),
+        }
+    }
+}
+
+/// Information about a TCP connection.
+///
+/// = See also
+///
+/// * link:lrs::socket::opt::TcpInfoOpt
+#[derive(Pod, Copy, Clone, PartialEq, Eq)]
+pub struct TcpInfo {
+    info: tcp_info,
+}
+
+fn time_from_us(us: u32) -> Time {
+    Time {
+        seconds: (us / 1_000_000) as i64,
+        nanoseconds: (us % 1_000_000) as i64 * 1000,
+    }
+}
+
+fn time_from_ms(ms: u32) -> Time {
+    Time {
+        seconds: (ms / 1000) as i64,
+        nanoseconds: (ms % 1000) as i64 * 1_000_000,
+    }
+}
+
+impl TcpInfo {
+    pub(crate) fn from_raw(info: tcp_info) -> TcpInfo {
+        TcpInfo { info }
+    }
+
+    /// Returns the state of the connection.
+    pub fn state(&self) -> TcpState {
+        TcpState::from_raw(self.info.tcpi_state)
+    }
+
+    /// Returns the number of unrecovered retransmission timeouts.
+    pub fn retransmits(&self) -> u8 {
+        self.info.tcpi_retransmits
+    }
+
+    /// Returns the number of unanswered zero-window probes.
+    pub fn probes(&self) -> u8 {
+        self.info.tcpi_probes
+    }
+
+    /// Returns the window scale announced by this host.
+    pub fn snd_wscale(&self) -> u8 {
+        self.info.tcpi_wscale & 0xf
+    }
+
+    /// Returns the window scale announced by the peer.
+    pub fn rcv_wscale(&self) -> u8 {
+        self.info.tcpi_wscale >> 4
+    }
+
+    /// Returns the retransmission timeout.
+    pub fn rto(&self) -> Time {
+        time_from_us(self.info.tcpi_rto)
+    }
+
+    /// Returns the delayed-acknowledgement timeout.
+    pub fn ato(&self) -> Time {
+        time_from_us(self.info.tcpi_ato)
+    }
+
+    /// Returns the maximum segment size for sending.
+    pub fn snd_mss(&self) -> u32 {
+        self.info.tcpi_snd_mss
+    }
+
+    /// Returns the maximum segment size for receiving.
+    pub fn rcv_mss(&self) -> u32 {
+        self.info.tcpi_rcv_mss
+    }
+
+    /// Returns the number of unacknowledged segments.
+    pub fn unacked(&self) -> u32 {
+        self.info.tcpi_unacked
+    }
+
+    /// Returns the number of selectively acknowledged segments.
+    pub fn sacked(&self) -> u32 {
+        self.info.tcpi_sacked
+    }
+
+    /// Returns the number of segments considered lost.
+    pub fn lost(&self) -> u32 {
+        self.info.tcpi_lost
+    }
+
+    /// Returns the number of segments being retransmitted.
+    pub fn retrans(&self) -> u32 {
+        self.info.tcpi_retrans
+    }
+
+    /// Returns the time since the last data was sent.
+    pub fn last_data_sent(&self) -> Time {
+        time_from_ms(self.info.tcpi_last_data_sent)
+    }
+
+    /// Returns the time since the last data was received.
+    pub fn last_data_recv(&self) -> Time {
+        time_from_ms(self.info.tcpi_last_data_recv)
+    }
+
+    /// Returns the time since the last acknowledgement was received.
+    pub fn last_ack_recv(&self) -> Time {
+        time_from_ms(self.info.tcpi_last_ack_recv)
+    }
+
+    /// Returns the path MTU.
+    pub fn pmtu(&self) -> u32 {
+        self.info.tcpi_pmtu
+    }
+
+    /// Returns the smoothed round-trip time.
+    pub fn rtt(&self) -> Time {
+        time_from_us(self.info.tcpi_rtt)
+    }
+
+    /// Returns the variance of the round-trip time.
+    pub fn rtt_var(&self) -> Time {
+        time_from_us(self.info.tcpi_rttvar)
+    }
+
+    /// Returns the slow-start threshold.
+    pub fn snd_ssthresh(&self) -> u32 {
+        self.info.tcpi_snd_ssthresh
+    }
+
+    /// Returns the congestion window in segments.
+    pub fn snd_cwnd(&self) -> u32 {
+        self.info.tcpi_snd_cwnd
+    }
+
+    /// Returns the advertised maximum segment size.
+    pub fn advmss(&self) -> u32 {
+        self.info.tcpi_advmss
+    }
+
+    /// Returns the reordering metric.
+    pub fn reordering(&self) -> u32 {
+        self.info.tcpi_reordering
+    }
+
+    /// Returns the estimated round-trip time of the receiver.
+    pub fn rcv_rtt(&self) -> Time {
+        time_from_us(self.info.tcpi_rcv_rtt)
+    }
+
+    /// Returns the receive-buffer space advertised to the peer.
+    pub fn rcv_space(&self) -> u32 {
+        self.info.tcpi_rcv_space
+    }
+
+    /// Returns the total number of retransmitted segments.
+    pub fn total_retrans(&self) -> u32 {
+        self.info.tcpi_total_retrans
+    }
+}
+
+impl Debug for TcpInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("TcpInfo")
+            .field("state", &self.state())
+            .field("retransmits", &self.retransmits())
+            .field("rto", &self.rto())
+            .field("snd_mss", &self.snd_mss())
+            .field("rcv_mss", &self.rcv_mss())
+            .field("unacked", &self.unacked())
+            .field("lost", &self.lost())
+            .field("pmtu", &self.pmtu())
+            .field("rtt", &self.rtt())
+            .field("rtt_var", &self.rtt_var())
+            .field("snd_cwnd", &self.snd_cwnd())
+            .field("total_retrans", &self.total_retrans())
+            .finish()
+    }
+}
@@ -0,0 +1,45 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    result::{Result},
+    fd::{FdContainer},
+    socket::{SockAddr, MSG_NONE, MSG_NO_SIGNAL, send_msg},
+    socket::{GetSockOpt, SetSockOpt, get_opt, set_opt},
+    socket::common::{self},
+    util::{
+        retry::{retry},
+        data::{d8},
+        io::{Read, Write},
+    },
+};
+use crate::kty::{c_int, SOCK_DGRAM};
+use crate::syscall::{close, readv};
+
+/// A UDP socket.
+pub struct UdpSocket {
+    fd: c_int,
+    owned: bool,
+}
+
+impl UdpSocket {
+    /// Creates a socket bound to an address.
+    ///
+    /// [argument, addr]
+    /// The IPv4 or IPv6 address to bind to. If the port is `0`, the kernel picks an
+    /// unused port which can be retrieved with `local_addr`.
+    ///
+    /// = See also
+    ///
+    /// * link:man:bind(2)
+    pub fn bind(addr: &SockAddr) -> Result<UdpSocket> {
+        common::check_inet(addr)?;
+        let sock = UdpSocket::from_owned(common::socket(addr.family(), SOCK_DGRAM)?);
+        common::bind(sock.fd, addr)?;
+        Ok(sock)
+    }
+}
+
+impl_socket!(UdpSocket);
+impl_datagram!(UdpSocket);
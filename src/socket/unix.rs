@@ -0,0 +1,198 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    result::{Result},
+    fd::{FdContainer},
+    socket::{SockAddr, Shutdown, MSG_NONE, MSG_NO_SIGNAL, send_msg},
+    socket::{GetSockOpt, SetSockOpt, get_opt, set_opt},
+    socket::common::{self, BACKLOG},
+    util::{
+        retry::{retry},
+        data::{d8},
+        io::{Read, Write},
+    },
+};
+use crate::kty::{c_int, AF_UNIX, SOCK_STREAM, SOCK_DGRAM, SOCK_SEQPACKET};
+use crate::syscall::{close, readv};
+
+fn listen<S: FdContainer>(addr: &SockAddr, ty: c_int) -> Result<S> {
+    common::check_unix(addr)?;
+    let sock = S::from_owned(common::socket(AF_UNIX, ty)?);
+    common::bind(sock.borrow(), addr)?;
+    common::listen(sock.borrow(), BACKLOG)?;
+    Ok(sock)
+}
+
+fn connect<S: FdContainer>(addr: &SockAddr, ty: c_int) -> Result<S> {
+    common::check_unix(addr)?;
+    let sock = S::from_owned(common::socket(AF_UNIX, ty)?);
+    common::connect(sock.borrow(), addr)?;
+    Ok(sock)
+}
+
+/// A Unix domain stream socket that accepts connections.
+pub struct UnixListener {
+    fd: c_int,
+    owned: bool,
+}
+
+impl UnixListener {
+    /// Creates a socket that listens on an address.
+    ///
+    /// [argument, addr]
+    /// The path or abstract name to listen on.
+    ///
+    /// = Remarks
+    ///
+    /// If a file already exists at the path, `EADDRINUSE` is returned. The file is not
+    /// removed when the socket is closed. The backlog is 128.
+    ///
+    /// = See also
+    ///
+    /// * link:man:bind(2)
+    /// * link:man:listen(2)
+    /// * link:man:unix(7)
+    pub fn bind(addr: &SockAddr) -> Result<UnixListener> {
+        listen(addr, SOCK_STREAM)
+    }
+}
+
+impl_socket!(UnixListener);
+impl_listener!(UnixListener, UnixStream);
+
+/// A connected Unix domain stream socket.
+pub struct UnixStream {
+    fd: c_int,
+    owned: bool,
+}
+
+impl UnixStream {
+    /// Connects to an address.
+    ///
+    /// [argument, addr]
+    /// The path or abstract name to connect to.
+    ///
+    /// = See also
+    ///
+    /// * link:man:connect(2)
+    pub fn connect(addr: &SockAddr) -> Result<UnixStream> {
+        connect(addr, SOCK_STREAM)
+    }
+
+    /// Creates a pair of connected sockets.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socketpair(2)
+    pub fn pair() -> Result<(UnixStream, UnixStream)> {
+        let (a, b) = common::pair(SOCK_STREAM)?;
+        Ok((UnixStream::from_owned(a), UnixStream::from_owned(b)))
+    }
+}
+
+impl_socket!(UnixStream);
+impl_connected!(UnixStream);
+
+/// A Unix domain datagram socket.
+pub struct UnixDatagram {
+    fd: c_int,
+    owned: bool,
+}
+
+impl UnixDatagram {
+    /// Creates a socket bound to an address.
+    ///
+    /// [argument, addr]
+    /// The path or abstract name to bind to.
+    ///
+    /// = See also
+    ///
+    /// * link:man:bind(2)
+    pub fn bind(addr: &SockAddr) -> Result<UnixDatagram> {
+        common::check_unix(addr)?;
+        let sock = UnixDatagram::unbound()?;
+        common::bind(sock.fd, addr)?;
+        Ok(sock)
+    }
+
+    /// Creates a socket that is not bound to an address.
+    pub fn unbound() -> Result<UnixDatagram> {
+        common::socket(AF_UNIX, SOCK_DGRAM).map(UnixDatagram::from_owned)
+    }
+
+    /// Creates a pair of connected sockets.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socketpair(2)
+    pub fn pair() -> Result<(UnixDatagram, UnixDatagram)> {
+        let (a, b) = common::pair(SOCK_DGRAM)?;
+        Ok((UnixDatagram::from_owned(a), UnixDatagram::from_owned(b)))
+    }
+}
+
+impl_socket!(UnixDatagram);
+impl_datagram!(UnixDatagram);
+
+/// A Unix domain sequenced-packet socket that accepts connections.
+pub struct UnixSeqpacketListener {
+    fd: c_int,
+    owned: bool,
+}
+
+impl UnixSeqpacketListener {
+    /// Creates a socket that listens on an address.
+    ///
+    /// [argument, addr]
+    /// The path or abstract name to listen on.
+    ///
+    /// = See also
+    ///
+    /// * link:lrs::socket::UnixListener::bind
+    pub fn bind(addr: &SockAddr) -> Result<UnixSeqpacketListener> {
+        listen(addr, SOCK_SEQPACKET)
+    }
+}
+
+impl_socket!(UnixSeqpacketListener);
+impl_listener!(UnixSeqpacketListener, UnixSeqpacket);
+
+/// A connected Unix domain sequenced-packet socket.
+///
+/// = Remarks
+///
+/// Each write sends a single packet with preserved boundaries and each read receives
+/// a single packet.
+pub struct UnixSeqpacket {
+    fd: c_int,
+    owned: bool,
+}
+
+impl UnixSeqpacket {
+    /// Connects to an address.
+    ///
+    /// [argument, addr]
+    /// The path or abstract name to connect to.
+    ///
+    /// = See also
+    ///
+    /// * link:man:connect(2)
+    pub fn connect(addr: &SockAddr) -> Result<UnixSeqpacket> {
+        connect(addr, SOCK_SEQPACKET)
+    }
+
+    /// Creates a pair of connected sockets.
+    ///
+    /// = See also
+    ///
+    /// * link:man:socketpair(2)
+    pub fn pair() -> Result<(UnixSeqpacket, UnixSeqpacket)> {
+        let (a, b) = common::pair(SOCK_SEQPACKET)?;
+        Ok((UnixSeqpacket::from_owned(a), UnixSeqpacket::from_owned(b)))
+    }
+}
+
+impl_socket!(UnixSeqpacket);
+impl_connected!(UnixSeqpacket);
@@ -18,7 +18,8 @@ use crate::kty::{
     F_SETFL, F_GETFD, F_SETFD, sockaddr, msghdr, mmsghdr, FUTEX_WAIT, FUTEX_WAKE,
     siginfo_t, rusage, SIOCGSTAMPNS, SIOCINQ, SIOCOUTQ, EPOLL_CLOEXEC, O_CLOEXEC,
     O_LARGEFILE, SOCK_CLOEXEC, MSG_CMSG_CLOEXEC, TFD_CLOEXEC, SFD_CLOEXEC, sigaction,
-    F_SETPIPE_SZ, F_GETPIPE_SZ, IN_CLOEXEC, tms, clock_t, MFD_CLOEXEC, F_ADD_SEALS,
+    F_SETPIPE_SZ, F_GETPIPE_SZ, IN_CLOEXEC, FAN_CLOEXEC, EFD_CLOEXEC, pollfd, tms,
+    clock_t, MFD_CLOEXEC, F_ADD_SEALS,
     F_GET_SEALS, PAGE_SIZE, TIOCGPTN, TIOCSPTLCK, TIOCGPTLCK, TIOCSIG, TIOCPKT, TIOCGPKT,
     TIOCSTI, winsize, TIOCGWINSZ, TIOCSWINSZ, TIOCSCTTY, TIOCNOTTY, TIOCGEXCL, TIOCNXCL,
     TIOCEXCL, TIOCCONS, TIOCGDEV, TIOCVHANGUP, TIOCSETD, TIOCGETD, TIOCGSID, TIOCSPGRP,
@@ -630,8 +631,8 @@ pub fn writev(fd: c_int, bufs: &[&[d8]]) -> ssize_t {
 ///
 /// * link:man:preadv(2)
 pub fn preadv(fd: c_int, bufs: &mut [&mut [d8]], offset: loff_t) -> ssize_t {
-    let lo = ((offset as u64) & 0xFFFF_FFFF) as k_ulong;
-    let hi = ((offset as u64) > 32) as k_ulong;
+    let lo = offset as u64 as k_ulong;
+    let hi = ((offset as u64) >> 32) as k_ulong;
     unsafe {
         r::preadv(fd as k_ulong, bufs.as_mut_ptr() as *mut _, bufs.len().saturating_cast(), lo, hi)
     }
@@ -655,8 +656,8 @@ pub fn preadv(fd: c_int, bufs: &mut [&mut [d8]], offset: loff_t) -> ssize_t {
 ///
 /// * link:man:pwritev(2)
 pub fn pwritev(fd: c_int, bufs: &[&[d8]], offset: loff_t) -> ssize_t {
-    let lo = ((offset as u64) & 0xFFFF_FFFF) as k_ulong;
-    let hi = ((offset as u64) > 32) as k_ulong;
+    let lo = offset as u64 as k_ulong;
+    let hi = ((offset as u64) >> 32) as k_ulong;
     unsafe {
         r::pwritev(fd as k_ulong, bufs.as_ptr() as *const _, bufs.len().saturating_cast(),
                    lo, hi)
@@ -1569,6 +1570,37 @@ pub fn epoll_pwait(epfd: c_int, events: &mut [epoll_event], timeout: c_int,
                            timeout, sigmask, mem::size_of::<sigset_t>() as size_t) }
 }
 
+/// Waits for events on a set of file descriptors.
+///
+/// [argument, fds]
+/// The file descriptors and the events to wait for.
+///
+/// [argument, timeout]
+/// The maximum time to wait or `None` to wait indefinitely.
+///
+/// [argument, sigmask]
+/// A set of signals that will be masked during the operation.
+///
+/// [return_value]
+/// Returns the number of file descriptors with events or an error value.
+///
+/// = See also
+///
+/// * link:man:ppoll(2)
+pub fn ppoll(fds: &mut [pollfd], timeout: Option<&timespec>,
+             sigmask: Option<&sigset_t>) -> c_int {
+    let timeout = match timeout {
+        Some(timeout) => timeout as *const _ as *mut _,
+        _ => 0 as *mut _,
+    };
+    let sigmask = match sigmask {
+        Some(sigmask) => sigmask as *const _,
+        _ => 0 as *const _,
+    };
+    unsafe { r::ppoll(fds.as_mut_ptr(), fds.len().saturating_cast(), timeout, sigmask,
+                      mem::size_of::<sigset_t>() as size_t) }
+}
+
 /// Retrieves a thread's CPU affinity mask.
 ///
 /// [argument, tid]
@@ -2619,9 +2651,57 @@ pub fn rt_sigaction(signum: c_int, act: Option<&sigaction>,
     unsafe { r::rt_sigaction(signum, act, old, mem::size_of::<sigset_t>() as size_t) }
 }
 
-// pub fn rt_sigreturn() {
-//     unsafe { r::rt_sigreturn() }
-// }
+/// Creates a new process that runs a function on a new stack.
+///
+/// [argument, flags]
+/// The flags of the `clone` call, including the termination signal.
+///
+/// [argument, stack]
+/// The top of the stack of the child.
+///
+/// [argument, f]
+/// The function that runs in the child.
+///
+/// [argument, arg]
+/// The argument passed to `f`.
+///
+/// [return_value]
+/// Returns the process id of the child or an error value.
+///
+/// = Remarks
+///
+/// The child exits with the return value of `f`. Unlike `clone`, this function never
+/// returns in the child, i.e., the caller does not have to deal with a function that
+/// returns twice.
+///
+/// If `flags` contains `CLONE_VM`, the child shares the memory of the caller and `f`
+/// must not modify any state that the caller relies on. `stack` must point to the top
+/// of a memory region that stays valid until the child exits or calls `execve`.
+///
+/// = See also
+///
+/// * link:man:clone(2)
+pub unsafe fn clone_fn(flags: c_ulong, stack: *mut u8,
+                       f: extern "C" fn(*mut c_void) -> c_int,
+                       arg: *mut c_void) -> pid_t {
+    r::clone_fn(flags as k_ulong, stack, f, arg) as pid_t
+}
+
+/// Returns the address of a function that calls `rt_sigreturn`.
+///
+/// = Remarks
+///
+/// `rt_sigreturn` cannot be called from Rust code since it has to run with the stack
+/// pointer pointing at the signal frame. Instead, this address should be stored in the
+/// `sa_restorer` field of a `sigaction` whose `sa_flags` contain `SA_RESTORER`. Signal
+/// handlers then return into this function.
+///
+/// = See also
+///
+/// * link:man:sigreturn(2)
+pub fn rt_sigreturn_restorer() -> usize {
+    r::rt_sigreturn_restorer()
+}
 
 /// Creates a new pipe.
 ///
@@ -2768,6 +2848,34 @@ pub fn splice(fd_in: c_int, mut off_in: Option<&mut u64>, fd_out: c_int,
     rv
 }
 
+/// Maps user memory into a pipe.
+///
+/// [argument, fd]
+/// The write end of a pipe.
+///
+/// [argument, bufs]
+/// The buffers to map.
+///
+/// [argument, flags]
+/// Flags to use while mapping.
+///
+/// [return_value]
+/// Returns the number of bytes mapped.
+///
+/// = Remarks
+///
+/// The pages of the buffers are referenced by the pipe until the data has been read.
+/// Modifications of the buffers during this time can be observed by the reader.
+///
+/// = See also
+///
+/// * link:man:vmsplice(2)
+pub fn vmsplice(fd: c_int, bufs: &[&[d8]], flags: c_uint) -> ssize_t {
+    unsafe {
+        r::vmsplice(fd, bufs.as_ptr() as *const _, bufs.len().saturating_cast(), flags)
+    }
+}
+
 /// Creates a new inotify object.
 ///
 /// [argument, flags]
@@ -2821,6 +2929,55 @@ pub fn inotify_rm_watch(fd: c_int, wd: c_int) -> c_int {
     unsafe { r::inotify_rm_watch(fd, wd) }
 }
 
+/// Creates a new fanotify group.
+///
+/// [argument, flags]
+/// Flags to use when creating the group.
+///
+/// [argument, event_flags]
+/// Flags of the file descriptions created for events.
+///
+/// = Remarks
+///
+/// Unless lrs was compiled with the `no-auto-cloexec` flag, this function automatically
+/// adds the `FAN_CLOEXEC` flag to `flags` and the `O_CLOEXEC` flag to `event_flags`.
+///
+/// = See also
+///
+/// * link:man:fanotify_init(2)
+pub fn fanotify_init(mut flags: c_uint, mut event_flags: c_int) -> c_int {
+    if cfg!(not(no_auto_cloexec)) {
+        flags |= FAN_CLOEXEC;
+        event_flags |= O_CLOEXEC;
+    }
+    unsafe { r::fanotify_init(flags, event_flags as k_uint) }
+}
+
+/// Adds, removes, or modifies an fanotify mark.
+///
+/// [argument, fd]
+/// The fd of the fanotify group.
+///
+/// [argument, flags]
+/// Flags that describe the operation.
+///
+/// [argument, mask]
+/// The events of the mark.
+///
+/// [argument, dir]
+/// The directory relative to which the path is interpreted.
+///
+/// [argument, path]
+/// The path of the object to mark.
+///
+/// = See also
+///
+/// * link:man:fanotify_mark(2)
+pub fn fanotify_mark(fd: c_int, flags: c_uint, mask: u64, dir: c_int,
+                     path: KStr) -> c_int {
+    unsafe { r::fanotify_mark(fd, flags, mask, dir, path.as_ptr()) }
+}
+
 /// Duplicates a file descriptor by replacing another one.
 ///
 /// [argument, oldfd]
@@ -2870,10 +3027,18 @@ pub fn umask(mode: umode_t) -> umode_t {
 /// [argument, flags]
 /// Flags to use when creating the new file descriptor.
 ///
+/// = Remarks
+///
+/// Unless lrs was compiled with the `no-auto-cloexec` flag, this function automatically
+/// adds the `EFD_CLOEXEC` flag.
+///
 /// = See also
 ///
 /// * link:man:eventfd2(2)
-pub fn eventfd2(init: c_uint, flags: c_int) -> c_int {
+pub fn eventfd2(init: c_uint, mut flags: c_int) -> c_int {
+    if cfg!(not(no_auto_cloexec)) {
+        flags |= EFD_CLOEXEC;
+    }
     unsafe { r::eventfd2(init, flags) }
 }
 
@@ -3017,9 +3182,18 @@ pub fn msync(addr: usize, len: usize, flags: c_int) -> c_int {
 /// [argument, advice]
 /// The advice given.
 ///
+/// = Remarks
+///
+/// == Kernel versions
+///
+/// Kernels that don't know the advice return `EINVAL`. `MADV_FREE` requires kernel
+/// version 4.5, `MADV_WIPEONFORK` and `MADV_KEEPONFORK` require 4.14, and `MADV_COLD`
+/// and `MADV_PAGEOUT` require 5.4.
+///
 /// = See also
 ///
 /// * link:man:madvise(2)
+/// * link:lrs::mmap::advise
 pub unsafe fn madvise(addr: usize, len: usize, advice: c_int) -> c_int {
     r::madvise(addr as k_ulong, len as k_ulong, advice)
 }
@@ -3108,7 +3282,7 @@ pub fn munlockall() -> c_int {
 ///
 /// * link:man:mincore(2)
 pub fn mincore(addr: usize, length: usize, buf: &mut [d8]) -> c_int {
-    let pages = (buf.len() + PAGE_SIZE - 1) / PAGE_SIZE;
+    let pages = (length + PAGE_SIZE - 1) / PAGE_SIZE;
     if pages > buf.len() {
         return -crate::kty::EINVAL;
     }
@@ -45,7 +45,7 @@ pub use crate::syscall::raw::arch::{
 };
 
 pub use crate::syscall::raw::arch::{
-    StatType, StatfsType,
+    StatType, StatfsType, rt_sigreturn_restorer, clone_fn,
 };
 
 macro_rules! call {
@@ -52,7 +52,7 @@ pub use crate::syscall::raw::common::{
 use crate::kty::{
     self,
     c_uint, k_int, k_long, k_ulong, c_char, k_uint, linux_dirent64, loff_t,
-    new_utsname, pid_t, rlimit64, size_t, ssize_t, stat, c_int,
+    new_utsname, pid_t, rlimit64, size_t, ssize_t, stat, c_int, c_void,
 
     __NR_iopl, __NR_mmap, __NR_arch_prctl, __NR_clone,
 };
@@ -139,6 +139,72 @@ mod asm {
     }
 }
 
+// The restorer of signal handlers installed with SA_RESTORER. Signal handlers return
+// into this function with the stack pointer pointing at the signal frame. It must not
+// touch the stack before calling rt_sigreturn (15) which restores the interrupted
+// context.
+global_asm!(r#"
+    .text
+    .globl __lrs_rt_restorer
+    .hidden __lrs_rt_restorer
+    .type __lrs_rt_restorer,@function
+__lrs_rt_restorer:
+    mov $15, %rax
+    syscall
+    .size __lrs_rt_restorer,.-__lrs_rt_restorer
+"#);
+
+// Calls clone (56) and runs `f(arg)` in the child on the stack whose top is `stack`.
+// The child exits (60) with the return value of `f`. `f` and `arg` are stored on the
+// new stack because the child cannot access the registers of the parent.
+//
+// rdi = flags, rsi = stack, rdx = f, rcx = arg
+global_asm!(r#"
+    .text
+    .globl __lrs_clone_fn
+    .hidden __lrs_clone_fn
+    .type __lrs_clone_fn,@function
+__lrs_clone_fn:
+    and $-16, %rsi
+    sub $16, %rsi
+    mov %rdx, (%rsi)
+    mov %rcx, 8(%rsi)
+    xor %edx, %edx
+    xor %r10d, %r10d
+    xor %r8d, %r8d
+    mov $56, %eax
+    syscall
+    test %rax, %rax
+    jnz 1f
+    xor %ebp, %ebp
+    pop %rax
+    pop %rdi
+    call *%rax
+    mov %eax, %edi
+    mov $60, %eax
+    syscall
+    hlt
+1:
+    ret
+    .size __lrs_clone_fn,.-__lrs_clone_fn
+"#);
+
+extern "C" {
+    fn __lrs_rt_restorer();
+    fn __lrs_clone_fn(flags: k_ulong, stack: *mut u8,
+                      f: extern "C" fn(*mut c_void) -> c_int, arg: *mut c_void) -> k_long;
+}
+
+pub unsafe fn clone_fn(flags: k_ulong, stack: *mut u8,
+                       f: extern "C" fn(*mut c_void) -> c_int,
+                       arg: *mut c_void) -> k_long {
+    __lrs_clone_fn(flags, stack, f, arg)
+}
+
+pub fn rt_sigreturn_restorer() -> usize {
+    __lrs_rt_restorer as unsafe extern "C" fn() as usize
+}
+
 // cross platform unification:
 
 pub type StatType = stat;
@@ -1,16 +1,165 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
 pub use linux_macros::{kstr_pub as kstr};
 
-use crate::kty::{c_char};
+use core::{marker::{PhantomData}};
+use memchr::{memchr};
+use crate::{
+    kty::{c_char},
+    lmem,
+    result::{Result},
+    string::{CStr, NoNullStr, ByteStr},
+    util::error::{self},
+};
 
+/// A null-terminated string that can be passed to the kernel.
+///
+/// = Remarks
+///
+/// Objects of this type can only be created from string literals via the `kstr!` macro
+/// or temporarily via the `ToKStr` trait.
 #[derive(Copy, Clone, PartialEq, Eq)]
-pub struct KStr(*const c_char);
+pub struct KStr<'a>(*const c_char, PhantomData<&'a [u8]>);
 
-impl KStr {
-    pub unsafe fn new(ptr: *const c_char) -> KStr {
-        KStr(ptr)
+impl<'a> KStr<'a> {
+    /// Creates a new `KStr` from a pointer.
+    ///
+    /// [argument, ptr]
+    /// A pointer to a null-terminated string.
+    ///
+    /// = Remarks
+    ///
+    /// If `ptr` is not a null terminated array of bytes that lives for `'a`, the behavior
+    /// is undefined.
+    pub unsafe fn new(ptr: *const c_char) -> KStr<'a> {
+        KStr(ptr, PhantomData)
     }
 
+    /// Returns a pointer to the first byte of the string.
     pub fn as_ptr(self) -> *const c_char {
         self.0
     }
 }
+
+/// The size of the stack buffer used by `ToKStr` implementations that copy.
+///
+/// = Remarks
+///
+/// Longer strings are copied to the heap if the `alloc` feature is enabled. Otherwise
+/// the conversion fails with `ENAMETOOLONG`.
+pub const KSTR_STACK_BUF: usize = 256;
+
+/// Objects that can be temporarily turned into a `KStr`.
+///
+/// = Remarks
+///
+/// This is how runtime strings, e.g., user-provided file names, are passed to functions
+/// that take paths. Implementations for types that are not null-terminated copy the
+/// string into a buffer and append a null byte.
+pub trait ToKStr {
+    /// Calls a function with a `KStr` representing this object.
+    ///
+    /// [argument, f]
+    /// The function that will be called.
+    ///
+    /// [return_value]
+    /// Returns the return value of `f`.
+    ///
+    /// = Remarks
+    ///
+    /// If the object contains a null byte, `f` is not called and `EINVAL` is returned.
+    fn with_kstr<T, F>(&self, f: F) -> Result<T>
+        where F: for<'a> FnOnce(KStr<'a>) -> Result<T>;
+}
+
+impl<'b> ToKStr for KStr<'b> {
+    fn with_kstr<T, F>(&self, f: F) -> Result<T>
+        where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+    {
+        f(*self)
+    }
+}
+
+impl ToKStr for CStr {
+    fn with_kstr<T, F>(&self, f: F) -> Result<T>
+        where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+    {
+        f(unsafe { KStr::new(self.as_ptr()) })
+    }
+}
+
+impl ToKStr for NoNullStr {
+    fn with_kstr<T, F>(&self, f: F) -> Result<T>
+        where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+    {
+        copy_to_kstr(self.as_ref(), f)
+    }
+}
+
+impl ToKStr for [u8] {
+    fn with_kstr<T, F>(&self, f: F) -> Result<T>
+        where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+    {
+        if memchr(0, self).is_some() {
+            return Err(error::InvalidArgument);
+        }
+        copy_to_kstr(self, f)
+    }
+}
+
+impl ToKStr for str {
+    fn with_kstr<T, F>(&self, f: F) -> Result<T>
+        where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+    {
+        self.as_bytes().with_kstr(f)
+    }
+}
+
+impl ToKStr for ByteStr {
+    fn with_kstr<T, F>(&self, f: F) -> Result<T>
+        where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+    {
+        let bytes: &[u8] = self.as_ref();
+        bytes.with_kstr(f)
+    }
+}
+
+impl<'b, S: ToKStr+?Sized> ToKStr for &'b S {
+    fn with_kstr<T, F>(&self, f: F) -> Result<T>
+        where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+    {
+        (**self).with_kstr(f)
+    }
+}
+
+/// Copies bytes that are known to contain no null byte and appends a null byte.
+fn copy_to_kstr<T, F>(bytes: &[u8], f: F) -> Result<T>
+    where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+{
+    if bytes.len() < KSTR_STACK_BUF {
+        let mut buf = [0u8; KSTR_STACK_BUF];
+        lmem::copy(&mut buf, bytes);
+        f(unsafe { KStr::new(buf.as_ptr() as *const c_char) })
+    } else {
+        copy_to_kstr_heap(bytes, f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn copy_to_kstr_heap<T, F>(bytes: &[u8], f: F) -> Result<T>
+    where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+{
+    let mut buf = alloc::vec::Vec::with_capacity(bytes.len() + 1);
+    buf.extend_from_slice(bytes);
+    buf.push(0u8);
+    f(unsafe { KStr::new(buf.as_ptr() as *const c_char) })
+}
+
+#[cfg(not(feature = "alloc"))]
+fn copy_to_kstr_heap<T, F>(_bytes: &[u8], _f: F) -> Result<T>
+    where F: for<'a> FnOnce(KStr<'a>) -> Result<T>,
+{
+    Err(error::PathTooLong)
+}
@@ -0,0 +1,36 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use linux::process::{Command, Stdio, ExitStatus};
+use linux::util::io::{Read};
+use linux::util::data::{d8};
+use linux::util::error;
+
+#[test]
+fn piped_stdout() {
+    let mut child = Command::new("echo").arg("hi").stdout(Stdio::Piped).spawn().unwrap();
+    let mut buf = [0u8; 16];
+    let mut len = 0;
+    loop {
+        let stdout = child.stdout.as_mut().unwrap();
+        match stdout.read(d8::from_byte_slice_mut(&mut buf[len..])).unwrap() {
+            0 => break,
+            n => len += n,
+        }
+    }
+    assert_eq!(&buf[..len], b"hi\n");
+    assert_eq!(child.wait().unwrap(), ExitStatus::Exited(0));
+}
+
+#[test]
+fn exit_status() {
+    let mut child = Command::new("sh").arg("-c").arg("exit 7").spawn().unwrap();
+    assert_eq!(child.wait().unwrap(), ExitStatus::Exited(7));
+}
+
+#[test]
+fn missing_program() {
+    let res = Command::new("linux-test-does-not-exist").spawn();
+    assert_eq!(res.err(), Some(error::DoesNotExist));
+}
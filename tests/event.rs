@@ -0,0 +1,36 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cell::{Cell};
+use linux::event::{EventLoop};
+use linux::eventfd::{EventFd, EVENTFD_NONE};
+use linux::poll::{EPOLL_IN};
+use linux::time::{Time};
+use linux::util::error;
+
+#[test]
+fn io_callback() {
+    let efd = EventFd::new(0, EVENTFD_NONE).unwrap();
+    let calls = Cell::new(0);
+    let mut el = EventLoop::new().unwrap();
+    let id = el.add_io(&efd, EPOLL_IN, |_, flags| {
+        assert!(flags.is_set(EPOLL_IN));
+        efd.take().unwrap();
+        calls.set(calls.get() + 1);
+    }).unwrap();
+
+    el.run_once(Some(Time::milliseconds(10))).unwrap();
+    assert_eq!(calls.get(), 0);
+
+    efd.add(1).unwrap();
+    el.run_once(Some(Time::seconds(1))).unwrap();
+    assert_eq!(calls.get(), 1);
+
+    el.remove_io(id).unwrap();
+    efd.add(1).unwrap();
+    el.run_once(Some(Time::milliseconds(10))).unwrap();
+    assert_eq!(calls.get(), 1);
+
+    assert_eq!(el.remove_io(id).err(), Some(error::InvalidArgument));
+}
@@ -0,0 +1,78 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::thread;
+use linux::heap::{MmapAlloc};
+
+#[global_allocator]
+static ALLOC: MmapAlloc = MmapAlloc::new();
+
+#[test]
+fn threads() {
+    let threads: Vec<_> = (0..8).map(|t| thread::spawn(move || {
+        let mut vecs: Vec<Vec<usize>> = Vec::new();
+        for i in 0..2000 {
+            let len = (i * 37 + t) % 3000;
+            vecs.push((0..len).collect());
+            if i % 3 == 0 {
+                let idx = i % vecs.len();
+                vecs.swap_remove(idx);
+            }
+            if let Some(last) = vecs.last_mut().filter(|_| i % 5 == 0) {
+                let len = last.len();
+                last.extend(len..len + 5000);
+                last.shrink_to_fit();
+            }
+        }
+        for v in &vecs {
+            for (i, &x) in v.iter().enumerate() {
+                assert_eq!(x, i);
+            }
+        }
+    })).collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+}
+
+#[test]
+fn over_aligned() {
+    unsafe {
+        for &(size, align) in &[(1, 8192), (10000, 65536), (100, 1 << 20)] {
+            let layout = Layout::from_size_align(size, align).unwrap();
+            let ptr = ALLOC.alloc_zeroed(layout);
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % align, 0);
+            assert_eq!(*ptr.add(size - 1), 0);
+            *ptr = 1;
+            let new = ALLOC.realloc(ptr, layout, size * 100);
+            assert_eq!(new as usize % align, 0);
+            assert_eq!(*new, 1);
+            ALLOC.dealloc(new, Layout::from_size_align(size * 100, align).unwrap());
+        }
+    }
+}
+
+#[test]
+fn large_realloc() {
+    unsafe {
+        let layout = Layout::from_size_align(5000, 8).unwrap();
+        let ptr = ALLOC.alloc(layout);
+        for i in 0..5000 {
+            *ptr.add(i) = i as u8;
+        }
+        let sizes = [1 << 20, 1 << 24, 3 << 20, 100];
+        let (mut ptr, mut size) = (ptr, 5000);
+        for &new_size in &sizes {
+            ptr = ALLOC.realloc(ptr, Layout::from_size_align(size, 8).unwrap(), new_size);
+            assert!(!ptr.is_null());
+            for i in 0..5000.min(new_size) {
+                assert_eq!(*ptr.add(i), i as u8);
+            }
+            size = new_size;
+        }
+        ALLOC.dealloc(ptr, Layout::from_size_align(size, 8).unwrap());
+    }
+}
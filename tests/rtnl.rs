@@ -0,0 +1,30 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use linux::socket::rtnl::{Rtnl, Request};
+use linux::kty::{ifaddrmsg, RTM_NEWADDR, IFA_LABEL};
+use linux::lmem;
+use linux::util::error;
+
+#[test]
+fn loopback() {
+    let mut rtnl = Rtnl::new().unwrap();
+    let links = rtnl.links().unwrap();
+    let lo = links.iter().find(|l| l.is_loopback()).unwrap();
+    assert_eq!(&lo.name[..], b"lo");
+    let addrs = rtnl.addresses().unwrap();
+    if lo.is_up() {
+        assert!(addrs.iter().any(|a| a.index == lo.index));
+    }
+}
+
+#[test]
+fn attr_too_long() {
+    let hdr: ifaddrmsg = lmem::zeroed();
+    let mut req = Request::new(RTM_NEWADDR, 0, &hdr);
+    let len = req.as_bytes().len();
+    assert_eq!(req.attr(IFA_LABEL, &[0; 65532]).err(), Some(error::InvalidArgument));
+    assert_eq!(req.as_bytes().len(), len);
+    assert!(req.attr(IFA_LABEL, &[0; 65531]).is_ok());
+}
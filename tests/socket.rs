@@ -0,0 +1,45 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use linux::socket::{SockAddr, TcpListener, TcpStream, UdpSocket, UnixStream, UnixDatagram};
+use linux::util::io::{Read, Write};
+use linux::util::data::{d8};
+use linux::parse::{Parse};
+
+fn round_trip<A: Read + Write, B: Read + Write>(a: &mut A, b: &mut B) {
+    let mut buf = [0u8; 8];
+    assert_eq!(a.write(b"ping").unwrap(), 4);
+    let n = b.read(d8::from_byte_slice_mut(&mut buf)).unwrap();
+    assert_eq!(&buf[..n], b"ping");
+    assert_eq!(b.write(b"pong").unwrap(), 4);
+    let n = a.read(d8::from_byte_slice_mut(&mut buf)).unwrap();
+    assert_eq!(&buf[..n], b"pong");
+}
+
+#[test]
+fn tcp() {
+    let addr: SockAddr = Parse::parse("127.0.0.1:0").unwrap();
+    let listener = TcpListener::bind(&addr).unwrap();
+    let mut client = TcpStream::connect(&listener.local_addr().unwrap()).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    round_trip(&mut client, &mut server);
+}
+
+#[test]
+fn udp() {
+    let addr: SockAddr = Parse::parse("127.0.0.1:0").unwrap();
+    let mut a = UdpSocket::bind(&addr).unwrap();
+    let mut b = UdpSocket::bind(&addr).unwrap();
+    a.connect(&b.local_addr().unwrap()).unwrap();
+    b.connect(&a.local_addr().unwrap()).unwrap();
+    round_trip(&mut a, &mut b);
+}
+
+#[test]
+fn unix() {
+    let (mut a, mut b) = UnixStream::pair().unwrap();
+    round_trip(&mut a, &mut b);
+    let (mut a, mut b) = UnixDatagram::pair().unwrap();
+    round_trip(&mut a, &mut b);
+}
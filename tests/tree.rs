@@ -0,0 +1,38 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{fs, thread, time::Duration};
+use linux::inotify::{TreeWatcher, TreeEvent};
+use linux::util::error;
+
+#[test]
+fn rename_before_create_is_read() {
+    let root = format!("/tmp/tree-test-{}", std::process::id());
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir(&root).unwrap();
+    let mut watcher = TreeWatcher::new_non_blocking(&root[..]).unwrap();
+
+    fs::create_dir_all(format!("{}/a/b", root)).unwrap();
+    fs::rename(format!("{}/a", root), format!("{}/c", root)).unwrap();
+    fs::write(format!("{}/c/b/g", root), b"x").unwrap();
+
+    let mut seen = false;
+    for _ in 0..200 {
+        match watcher.next_event() {
+            Ok(TreeEvent::Renamed { from, to }) => {
+                assert_eq!((&from[..], &to[..]), (&b"a"[..], &b"c"[..]));
+                fs::write(format!("{}/c/b/h", root), b"x").unwrap();
+            },
+            Ok(TreeEvent::Created(ref path)) if path == b"c/b/h" => {
+                seen = true;
+                break;
+            },
+            Ok(_) => { },
+            Err(error::WouldBlock) => thread::sleep(Duration::from_millis(5)),
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+    fs::remove_dir_all(&root).unwrap();
+    assert!(seen);
+}